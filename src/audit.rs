@@ -0,0 +1,230 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The audit log. Every request that changes the cluster is appended to a file in the config dir,
+//! one json object per line.
+
+use chrono::{DateTime, Utc};
+use k8s_openapi::http::Method;
+
+use crate::error::ClickError;
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+/// The name of the audit log file in the config dir
+pub const AUDIT_FILE: &str = "audit.jsonl";
+
+#[derive(Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub context: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// The user impersonated with `as`, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub dry_run: bool,
+    /// The response status, or what went wrong if there wasn't one
+    pub outcome: String,
+}
+
+impl AuditEntry {
+    /// Create an entry for a request made now. The namespace is taken from the path
+    pub fn new(
+        context: &str,
+        method: &str,
+        path: &str,
+        user: Option<&str>,
+        dry_run: bool,
+        outcome: String,
+    ) -> AuditEntry {
+        AuditEntry {
+            timestamp: Utc::now(),
+            context: context.to_string(),
+            namespace: namespace_from_path(path),
+            user: user.map(|u| u.to_string()),
+            method: method.to_string(),
+            path: path.to_string(),
+            dry_run,
+            outcome,
+        }
+    }
+
+    /// Did the request fail, either with an error status, a non-zero exit status (for actions run
+    /// with kubectl), or without getting a response
+    pub fn failed(&self) -> bool {
+        if self.outcome.starts_with("error") {
+            return true;
+        }
+        if let Some(code) = self.outcome.strip_prefix("exit status: ") {
+            return code != "0";
+        }
+        match self
+            .outcome
+            .split_whitespace()
+            .next()
+            .map(|c| c.parse::<u16>())
+        {
+            Some(Ok(status)) => status >= 400,
+            _ => false,
+        }
+    }
+}
+
+/// Does a request with this method change anything
+pub fn is_mutating(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Is a request with this query string and body a dry run. That's set either by a `dryRun` query
+/// parameter, or by a top level `dryRun` field in a json body (like in DeleteOptions)
+pub fn is_dry_run(query: Option<&str>, body: &[u8]) -> bool {
+    let in_query = query.is_some_and(|q| {
+        url::form_urlencoded::parse(q.as_bytes())
+            .any(|(key, val)| key == "dryRun" && !val.is_empty())
+    });
+    in_query
+        || serde_json::from_slice::<serde_json::Value>(body)
+            .ok()
+            .and_then(|body| body.get("dryRun").cloned())
+            .is_some_and(|dry_run| match dry_run {
+                serde_json::Value::Null => false,
+                serde_json::Value::Array(a) => !a.is_empty(),
+                _ => true,
+            })
+}
+
+/// Get the namespace from a path like /api/v1/namespaces/default/pods/web-1
+pub fn namespace_from_path(path: &str) -> Option<String> {
+    let path = path.split('?').next().unwrap_or(path);
+    let mut segments = path.split('/');
+    segments.find(|s| *s == "namespaces")?;
+    segments
+        .next()
+        .filter(|ns| !ns.is_empty())
+        .map(|ns| ns.to_string())
+}
+
+/// Append entry to the audit log at path, creating the file if needed
+pub fn append(path: &Path, entry: &AuditEntry) -> Result<(), ClickError> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Read all the entries in the audit log at path, oldest first. Lines that can't be parsed are
+/// skipped. A missing file has no entries
+pub fn read_entries(path: &Path) -> Result<Vec<AuditEntry>, ClickError> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = vec![];
+    for line in reader.lines() {
+        if let Ok(entry) = serde_json::from_str(&line?) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespace_from_path() {
+        assert_eq!(
+            namespace_from_path("/api/v1/namespaces/default/pods/web-1"),
+            Some("default".to_string())
+        );
+        assert_eq!(
+            namespace_from_path("/apis/apps/v1/namespaces/kube-system/deployments/dns?dryRun=All"),
+            Some("kube-system".to_string())
+        );
+        assert_eq!(
+            namespace_from_path("/api/v1/namespaces/test"),
+            Some("test".to_string())
+        );
+        assert_eq!(namespace_from_path("/api/v1/nodes/node-1"), None);
+    }
+
+    #[test]
+    fn test_is_mutating() {
+        assert!(!is_mutating(&Method::GET));
+        assert!(is_mutating(&Method::DELETE));
+        assert!(is_mutating(&Method::PATCH));
+        assert!(is_mutating(&Method::POST));
+    }
+
+    #[test]
+    fn test_is_dry_run() {
+        assert!(is_dry_run(Some("dryRun=All"), b""));
+        assert!(is_dry_run(Some("labelSelector=a%3Db&dryRun=All"), b""));
+        assert!(!is_dry_run(Some("labelSelector=dryRun%3Dx"), b""));
+        assert!(!is_dry_run(None, b""));
+        assert!(is_dry_run(None, br#"{"dryRun":["All"]}"#));
+        assert!(!is_dry_run(None, br#"{"propagationPolicy":"Foreground"}"#));
+        // a patch that sets a key named dryRun isn't a dry run
+        assert!(!is_dry_run(None, br#"{"data":{"dryRun":"true"}}"#));
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let dir = tempdir::TempDir::new("click-audit").unwrap();
+        let path = dir.path().join(AUDIT_FILE);
+        assert!(read_entries(&path).unwrap().is_empty());
+        let entry = AuditEntry::new(
+            "prod",
+            "DELETE",
+            "/api/v1/namespaces/web/pods/web-1",
+            Some("admin"),
+            false,
+            "200 OK".to_string(),
+        );
+        append(&path, &entry).unwrap();
+        let dry = AuditEntry::new(
+            "prod",
+            "DELETE",
+            "/api/v1/nodes/node-1",
+            None,
+            true,
+            "200 OK".to_string(),
+        );
+        append(&path, &dry).unwrap();
+        let entries = read_entries(&path).unwrap();
+        assert_eq!(entries, vec![entry, dry]);
+        assert_eq!(entries[0].namespace, Some("web".to_string()));
+    }
+
+    #[test]
+    fn test_failed() {
+        let entry =
+            |outcome: &str| AuditEntry::new("c", "POST", "/", None, false, outcome.to_string());
+        assert!(!entry("200 OK").failed());
+        assert!(!entry("202 Accepted").failed());
+        assert!(entry("403 Forbidden").failed());
+        assert!(entry("error: connection refused").failed());
+        assert!(!entry("exit status: 0").failed());
+        assert!(entry("exit status: 1").failed());
+        assert!(!entry("started").failed());
+    }
+}
@@ -0,0 +1,254 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Just enough x509 parsing to show what's in a certificate stored in a secret
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use yasna::{ASN1Error, ASN1ErrorKind, ASN1Result, BERReader, Tag};
+
+use crate::error::ClickError;
+
+const SAN_OID: &str = "2.5.29.17";
+
+/// The interesting bits of an x509 certificate
+#[derive(Debug, PartialEq)]
+pub struct CertInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub sans: Vec<String>,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+}
+
+/// Return true if data looks like it contains at least one pem encoded certificate
+pub fn has_pem_cert(data: &[u8]) -> bool {
+    data.windows(27)
+        .any(|w| w == b"-----BEGIN CERTIFICATE-----")
+}
+
+/// Parse all the certificates in a pem encoded string. Any non-certificate blocks (like keys) are
+/// skipped
+pub fn parse_pem_certs(data: &[u8]) -> Result<Vec<CertInfo>, ClickError> {
+    pem::parse_many(data)?
+        .iter()
+        .filter(|p| p.tag() == "CERTIFICATE")
+        .map(|p| parse_der_cert(p.contents()))
+        .collect()
+}
+
+/// Parse a single der encoded certificate
+pub fn parse_der_cert(der: &[u8]) -> Result<CertInfo, ClickError> {
+    yasna::parse_ber(der, |reader| {
+        reader.read_sequence(|reader| {
+            let info = reader.next().read_sequence(read_tbs_certificate)?;
+            // signatureAlgorithm and signature, which we don't care about
+            reader.next().read_der()?;
+            reader.next().read_der()?;
+            Ok(info)
+        })
+    })
+    .map_err(|e| ClickError::ParseErr(format!("Invalid certificate: {e}")))
+}
+
+fn read_tbs_certificate(reader: &mut yasna::BERReaderSeq) -> ASN1Result<CertInfo> {
+    // version is optional and explicitly tagged with [0]
+    reader
+        .read_optional(|reader| reader.read_tagged(Tag::context(0), |reader| reader.read_i64()))?;
+    // serial, can be bigger than an i64 so just skip it
+    reader.next().read_der()?;
+    // signature algorithm
+    reader.next().read_der()?;
+    let issuer = read_name(reader.next())?;
+    let (not_before, not_after) = reader.next().read_sequence(|reader| {
+        let not_before = read_time(reader.next())?;
+        let not_after = read_time(reader.next())?;
+        Ok((not_before, not_after))
+    })?;
+    let subject = read_name(reader.next())?;
+    // subjectPublicKeyInfo
+    reader.next().read_der()?;
+
+    // now there are optional issuerUniqueID [1], subjectUniqueID [2] and extensions [3]
+    let mut sans = vec![];
+    while let Some(der) = reader.read_optional(|reader| reader.read_der())? {
+        if der.first() == Some(&0xa3) {
+            sans = yasna::parse_ber(&der, |reader| {
+                reader.read_tagged(Tag::context(3), read_sans_from_extensions)
+            })?;
+        }
+    }
+
+    Ok(CertInfo {
+        subject,
+        issuer,
+        sans,
+        not_before,
+        not_after,
+    })
+}
+
+// Get the contents of a der encoded value. We only use this on primitive types whose contents we
+// want as raw bytes, like strings and times
+fn der_contents(der: &[u8]) -> ASN1Result<&[u8]> {
+    let invalid = || ASN1Error::new(ASN1ErrorKind::Invalid);
+    let len_byte = *der.get(1).ok_or_else(invalid)?;
+    if len_byte & 0x80 == 0 {
+        der.get(2..).ok_or_else(invalid)
+    } else {
+        // long form, low bits say how many bytes hold the length
+        let start = 2 + (len_byte & 0x7f) as usize;
+        der.get(start..).ok_or_else(invalid)
+    }
+}
+
+fn short_attr_name(oid: &str) -> Option<&'static str> {
+    match oid {
+        "2.5.4.3" => Some("CN"),
+        "2.5.4.6" => Some("C"),
+        "2.5.4.7" => Some("L"),
+        "2.5.4.8" => Some("ST"),
+        "2.5.4.10" => Some("O"),
+        "2.5.4.11" => Some("OU"),
+        _ => None,
+    }
+}
+
+// read a Name, and format it like CN=foo, O=bar
+fn read_name(reader: BERReader) -> ASN1Result<String> {
+    let mut parts = vec![];
+    reader.read_sequence_of(|reader| {
+        reader.read_set_of(|reader| {
+            reader.read_sequence(|reader| {
+                let oid = reader.next().read_oid()?.to_string();
+                let val = reader.next().read_der()?;
+                let val = String::from_utf8_lossy(der_contents(&val)?);
+                match short_attr_name(&oid) {
+                    Some(name) => parts.push(format!("{name}={val}")),
+                    None => parts.push(format!("{oid}={val}")),
+                }
+                Ok(())
+            })
+        })
+    })?;
+    Ok(parts.join(", "))
+}
+
+// read a Time, which can be a UTCTime or a GeneralizedTime
+fn read_time(reader: BERReader) -> ASN1Result<DateTime<Utc>> {
+    let der = reader.read_der()?;
+    let contents = String::from_utf8_lossy(der_contents(&der)?).into_owned();
+    let parsed = match der.first() {
+        // UTCTime, two digit year
+        Some(0x17) => NaiveDateTime::parse_from_str(&contents, "%y%m%d%H%M%SZ"),
+        // GeneralizedTime, four digit year
+        Some(0x18) => NaiveDateTime::parse_from_str(&contents, "%Y%m%d%H%M%SZ"),
+        _ => return Err(ASN1Error::new(ASN1ErrorKind::Invalid)),
+    };
+    parsed
+        .map(|naive| Utc.from_utc_datetime(&naive))
+        .map_err(|_| ASN1Error::new(ASN1ErrorKind::Invalid))
+}
+
+fn read_sans_from_extensions(reader: BERReader) -> ASN1Result<Vec<String>> {
+    let mut sans = vec![];
+    reader.read_sequence_of(|reader| {
+        reader.read_sequence(|reader| {
+            let oid = reader.next().read_oid()?.to_string();
+            reader.read_optional(|reader| reader.read_bool())?; // critical
+            let value = reader.next().read_bytes()?;
+            if oid == SAN_OID {
+                sans = yasna::parse_ber(&value, read_general_names)?;
+            }
+            Ok(())
+        })
+    })?;
+    Ok(sans)
+}
+
+fn read_general_names(reader: BERReader) -> ASN1Result<Vec<String>> {
+    let mut names = vec![];
+    reader.read_sequence_of(|reader| {
+        let der = reader.read_der()?;
+        let contents = der_contents(&der)?;
+        match der.first() {
+            Some(0x81) => names.push(format!("email:{}", String::from_utf8_lossy(contents))),
+            Some(0x82) => names.push(format!("DNS:{}", String::from_utf8_lossy(contents))),
+            Some(0x86) => names.push(format!("URI:{}", String::from_utf8_lossy(contents))),
+            Some(0x87) => match contents.len() {
+                4 => names.push(format!(
+                    "IP:{}",
+                    std::net::Ipv4Addr::new(contents[0], contents[1], contents[2], contents[3])
+                )),
+                16 => {
+                    let mut octets = [0; 16];
+                    octets.copy_from_slice(contents);
+                    names.push(format!("IP:{}", std::net::Ipv6Addr::from(octets)));
+                }
+                _ => names.push("IP:<invalid>".to_string()),
+            },
+            // other name types are rare, ignore them
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TEST_CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBzTCCAXKgAwIBAgIUS7TOLIZYrNzXzFzpa8HOqKjTXtEwCgYIKoZIzj0EAwIw
+JTETMBEGA1UEAwwKY2xpY2sudGVzdDEOMAwGA1UECgwFQ2xpY2swIBcNMjYxMDE4
+MTU0MzA4WhgPMjEyNjA5MjQxNTQzMDhaMCUxEzARBgNVBAMMCmNsaWNrLnRlc3Qx
+DjAMBgNVBAoMBUNsaWNrMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE+B64IaN9
+D/bb3Ix6opsSq3lpK6+Hm3eqzlGhij6FG7wLKlg4F27Fb7LY3OUjJTSSef+VttJo
+4fiWIjGlRZD3MKN+MHwwHQYDVR0OBBYEFDVb/VxAqWvTOr8R5bXFyW/gzS5kMB8G
+A1UdIwQYMBaAFDVb/VxAqWvTOr8R5bXFyW/gzS5kMA8GA1UdEwEB/wQFMAMBAf8w
+KQYDVR0RBCIwIIIKY2xpY2sudGVzdIIMKi5jbGljay50ZXN0hwQKAAABMAoGCCqG
+SM49BAMCA0kAMEYCIQCOspxjM20JDA52QYxSgZfRp0qMHYFXNDvZ90zuCWmTLAIh
+ALJJmU2swbNUH9M7aeM99i/ylUVJHLtRDTRpMB1o2AUN
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_parse_cert() {
+        assert!(has_pem_cert(TEST_CERT.as_bytes()));
+        assert!(!has_pem_cert(b"not a cert"));
+        let certs = parse_pem_certs(TEST_CERT.as_bytes()).unwrap();
+        assert_eq!(certs.len(), 1);
+        let cert = &certs[0];
+        assert_eq!(cert.subject, "CN=click.test, O=Click");
+        assert_eq!(cert.issuer, "CN=click.test, O=Click");
+        assert_eq!(
+            cert.sans,
+            vec!["DNS:click.test", "DNS:*.click.test", "IP:10.0.0.1"]
+        );
+        // not_before is a UTCTime, not_after is a GeneralizedTime
+        assert_eq!(
+            cert.not_before,
+            Utc.with_ymd_and_hms(2026, 10, 18, 15, 43, 8).unwrap()
+        );
+        assert_eq!(
+            cert.not_after,
+            Utc.with_ymd_and_hms(2126, 9, 24, 15, 43, 8).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_cert() {
+        assert!(parse_der_cert(&[0x30, 0x03, 0x02, 0x01, 0x01]).is_err());
+    }
+}
@@ -0,0 +1,335 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Columns for list commands that are defined in the columns section of the click config. Each
+//! column has a path into the object, given as a JSON pointer or a JSONPath expression, and can
+//! have rules to color its cells by value.
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::config::ColumnConfig;
+use crate::env::Env;
+use crate::error::ClickError;
+use crate::table::{CellSpec, TableColor};
+
+use std::borrow::Cow;
+
+/// One step of a JSONPath expression
+#[derive(Debug, PartialEq)]
+pub enum PathStep {
+    Key(String),
+    /// An array index, negative indexes count from the end
+    Index(i64),
+    /// Every element of an array, or every value of an object
+    Wildcard,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ValuePath {
+    Pointer(String),
+    JsonPath(Vec<PathStep>),
+}
+
+fn path_error(path: &str, msg: &str) -> ClickError {
+    ClickError::ConfigFileError(format!("Invalid column path '{path}': {msg}"))
+}
+
+impl ValuePath {
+    /// Parse a path. Paths starting with / are JSON pointers. Anything else is a JSONPath
+    /// expression like .spec.containers[0].image, with an optional leading $ and optionally
+    /// wrapped in {} as with kubectl. Keys with dots in them can be quoted, like
+    /// .metadata.annotations['app.kubernetes.io/name'], and [*] selects every element.
+    pub fn parse(path: &str) -> Result<ValuePath, ClickError> {
+        let trimmed = path.trim();
+        if trimmed.starts_with('/') {
+            return Ok(ValuePath::Pointer(trimmed.to_string()));
+        }
+        let mut expr = trimmed;
+        if let Some(inner) = expr.strip_prefix('{').and_then(|e| e.strip_suffix('}')) {
+            expr = inner;
+        }
+        expr = expr.strip_prefix('$').unwrap_or(expr);
+
+        let mut steps = vec![];
+        let mut chars = expr.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '.' => {
+                    let mut key = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c == '.' || c == '[' {
+                            break;
+                        }
+                        key.push(c);
+                        chars.next();
+                    }
+                    match key.as_str() {
+                        "" => return Err(path_error(path, "empty key (.. isn't supported)")),
+                        "*" => steps.push(PathStep::Wildcard),
+                        _ => steps.push(PathStep::Key(key)),
+                    }
+                }
+                '[' => {
+                    let mut inner = String::new();
+                    let mut quote: Option<char> = None;
+                    let mut quoted = false;
+                    loop {
+                        match chars.next() {
+                            Some(c) if Some(c) == quote => quote = None,
+                            Some(c @ ('\'' | '"')) if quote.is_none() => {
+                                quote = Some(c);
+                                quoted = true;
+                            }
+                            Some(']') if quote.is_none() => break,
+                            Some(c) => inner.push(c),
+                            None => return Err(path_error(path, "unclosed [")),
+                        }
+                    }
+                    steps.push(if quoted {
+                        PathStep::Key(inner)
+                    } else if inner.trim() == "*" {
+                        PathStep::Wildcard
+                    } else {
+                        match inner.trim().parse::<i64>() {
+                            Ok(index) => PathStep::Index(index),
+                            Err(_) => PathStep::Key(inner.trim().to_string()),
+                        }
+                    });
+                }
+                _ => return Err(path_error(path, "expected . or [")),
+            }
+        }
+        if steps.is_empty() {
+            return Err(path_error(path, "no keys"));
+        }
+        Ok(ValuePath::JsonPath(steps))
+    }
+
+    /// Get the values the path points to in value
+    pub fn select<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        match self {
+            ValuePath::Pointer(pointer) => value.pointer(pointer).into_iter().collect(),
+            ValuePath::JsonPath(steps) => {
+                let mut current = vec![value];
+                for step in steps.iter() {
+                    current = current
+                        .into_iter()
+                        .flat_map(|v| -> Vec<&Value> {
+                            match (step, v) {
+                                (PathStep::Key(key), Value::Object(map)) => {
+                                    map.get(key).into_iter().collect()
+                                }
+                                (PathStep::Index(i), Value::Array(arr)) => {
+                                    let index = if *i < 0 { arr.len() as i64 + i } else { *i };
+                                    usize::try_from(index)
+                                        .ok()
+                                        .and_then(|index| arr.get(index))
+                                        .into_iter()
+                                        .collect()
+                                }
+                                (PathStep::Wildcard, Value::Array(arr)) => arr.iter().collect(),
+                                (PathStep::Wildcard, Value::Object(map)) => map.values().collect(),
+                                _ => vec![],
+                            }
+                        })
+                        .collect();
+                }
+                current
+            }
+        }
+    }
+}
+
+/// A column from the config, ready to extract cells from objects
+pub struct UserColumn {
+    pub name: String,
+    path: ValuePath,
+    /// (regex, fg, bg) for each color rule
+    colors: Vec<(Regex, Option<TableColor>, Option<TableColor>)>,
+}
+
+impl UserColumn {
+    pub fn new(conf: &ColumnConfig, env: &Env) -> Result<UserColumn, ClickError> {
+        let path = ValuePath::parse(&conf.path)?;
+        let colors = conf
+            .colors
+            .iter()
+            .map(|rule| {
+                let regex = Regex::new(&rule.value).map_err(|e| {
+                    ClickError::ConfigFileError(format!("Invalid regex for {}: {e}", conf.name))
+                })?;
+                let style = env.styles.resolve(&rule.style).map_err(|e| {
+                    ClickError::ConfigFileError(format!("Invalid style for {}: {e}", conf.name))
+                })?;
+                Ok((
+                    regex,
+                    style.foreground_color.map(TableColor::Color),
+                    style.background_color.map(TableColor::Color),
+                ))
+            })
+            .collect::<Result<Vec<_>, ClickError>>()?;
+        Ok(UserColumn {
+            name: conf.name.clone(),
+            path,
+            colors,
+        })
+    }
+
+    /// Build the cell for this column from an object, which has been serialized to value. Multiple
+    /// values (from a [*]) are joined with commas, and a single integer makes a numeric cell, so it
+    /// sorts numerically.
+    pub fn cell(&self, value: &Value) -> CellSpec<'static> {
+        let values = self.path.select(value);
+        if values.is_empty() {
+            return None::<String>.into();
+        }
+        if let [Value::Number(num)] = values.as_slice() {
+            if let Some(num) = num.as_i64() {
+                return self.color(CellSpec::new_int(num), &num.to_string());
+            }
+        }
+        let text = values
+            .iter()
+            .map(|v| match v {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+        let cell = CellSpec::with_colors(Cow::Owned(text.clone()), None, None);
+        self.color(cell, &text)
+    }
+
+    fn color<'a>(&self, mut cell: CellSpec<'a>, text: &str) -> CellSpec<'a> {
+        if let Some((_, fg, bg)) = self
+            .colors
+            .iter()
+            .find(|(regex, _, _)| regex.is_match(text))
+        {
+            cell.fg = *fg;
+            cell.bg = *bg;
+        }
+        cell
+    }
+}
+
+/// Check if a key of the columns section applies to a resource. Keys are the kind, like Pod, or
+/// the plural used in urls and as the list command, like pods, ignoring case.
+fn key_matches(key: &str, kind: &str, plural: &str) -> bool {
+    key.eq_ignore_ascii_case(kind) || key.eq_ignore_ascii_case(plural)
+}
+
+/// The columns from the config for the resource with the specified kind and plural name (see
+/// key_matches)
+pub fn user_columns(env: &Env, kind: &str, plural: &str) -> Result<Vec<UserColumn>, ClickError> {
+    env.click_config
+        .columns
+        .iter()
+        .filter(|(key, _)| key_matches(key, kind, plural))
+        .flat_map(|(_, columns)| columns.iter())
+        .map(|conf| UserColumn::new(conf, env))
+        .collect()
+}
+
+/// Just the names of the columns for kind/plural, for completion
+pub fn user_column_names(env: &Env, kind: &str, plural: &str) -> Vec<String> {
+    env.click_config
+        .columns
+        .iter()
+        .filter(|(key, _)| key_matches(key, kind, plural))
+        .flat_map(|(_, columns)| columns.iter().map(|c| c.name.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn select(path: &str, value: &Value) -> Vec<Value> {
+        ValuePath::parse(path)
+            .unwrap()
+            .select(value)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_value_paths() {
+        let pod = json!({
+            "metadata": {
+                "name": "web-0",
+                "annotations": {"app.kubernetes.io/name": "web", "1": "one"}
+            },
+            "spec": {"containers": [{"image": "nginx"}, {"image": "envoy"}]}
+        });
+        assert_eq!(select("/metadata/name", &pod), vec![json!("web-0")]);
+        assert_eq!(select(".metadata.name", &pod), vec![json!("web-0")]);
+        assert_eq!(select("{$.metadata.name}", &pod), vec![json!("web-0")]);
+        assert_eq!(
+            select(".spec.containers[1].image", &pod),
+            vec![json!("envoy")]
+        );
+        assert_eq!(
+            select(".spec.containers[-1].image", &pod),
+            vec![json!("envoy")]
+        );
+        assert_eq!(
+            select(".spec.containers[*].image", &pod),
+            vec![json!("nginx"), json!("envoy")]
+        );
+        assert_eq!(
+            select(".metadata.annotations['app.kubernetes.io/name']", &pod),
+            vec![json!("web")]
+        );
+        assert_eq!(
+            select(".metadata.annotations[\"1\"]", &pod),
+            vec![json!("one")]
+        );
+        assert!(select(".spec.containers[5].image", &pod).is_empty());
+        assert!(select(".spec.nodeName", &pod).is_empty());
+
+        assert!(ValuePath::parse("spec").is_err());
+        assert!(ValuePath::parse("..image").is_err());
+        assert!(ValuePath::parse(".spec.containers[0").is_err());
+        assert!(ValuePath::parse("$").is_err());
+    }
+
+    #[test]
+    fn test_key_matches() {
+        assert!(key_matches("Pod", "Pod", "pods"));
+        assert!(key_matches("pod", "Pod", "pods"));
+        assert!(key_matches("pods", "Pod", "pods"));
+        assert!(key_matches(
+            "StorageClass",
+            "StorageClass",
+            "storageclasses"
+        ));
+        // kinds whose plural isn't just an added s or es
+        assert!(key_matches(
+            "NetworkPolicy",
+            "NetworkPolicy",
+            "networkpolicies"
+        ));
+        assert!(key_matches(
+            "networkpolicies",
+            "NetworkPolicy",
+            "networkpolicies"
+        ));
+        assert!(key_matches("Ingress", "Ingress", "ingresses"));
+        assert!(!key_matches("Pod", "Node", "nodes"));
+    }
+}
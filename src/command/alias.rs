@@ -40,6 +40,29 @@ fn validate_alias(value: &str) -> std::result::Result<String, Error> {
     }
 }
 
+// print one alias. aliases from the contexts section of the config are marked with their context
+fn print_alias(alias: &config::Alias, context: Option<&str>, writer: &mut ClickWriter) {
+    let params = alias.params();
+    let mut extra = Vec::new();
+    if !params.is_empty() {
+        extra.push(format!("parameters: {}", params.join(", ")));
+    }
+    if let Some(context) = context {
+        extra.push(format!("context: {context}"));
+    }
+    if extra.is_empty() {
+        clickwriteln!(writer, "alias {} = '{}'", alias.alias, alias.expanded);
+    } else {
+        clickwriteln!(
+            writer,
+            "alias {} = '{}' ({})",
+            alias.alias,
+            alias.expanded,
+            extra.join(", ")
+        );
+    }
+}
+
 command!(
     Alias,
     "alias",
@@ -68,6 +91,10 @@ As with Bash: The first word of the expansion is tested for aliases, but a word
 an alias being expanded is not expanded a second time.  So one can alias logs to \"logs -e\", for
 instance, without causing infinite expansion.
 
+An alias can take parameters. $1, $2, etc. are replaced by the arguments typed after the alias,
+${1:-default} uses default if the argument wasn't given, and $@ is replaced by all the arguments.
+Arguments that aren't used by a parameter are added to the end of the expansion, unless $@ is used.
+
 Examples:
   # Display current aliases
   alias
@@ -79,7 +106,13 @@ Examples:
   alias pn \"pods -r nginx\"
 
   # alias el to run logs and grep for ERROR
-  alias el \"logs | grep ERROR\""
+  alias el \"logs | grep ERROR\"
+
+  # alias restarts to sort pods matching a regex by restarts, 'restarts nginx' lists nginx pods
+  alias restarts 'pods --sort restarts -r $1'
+
+  # alias tl to get the last N lines of logs, 100 by default
+  alias tl 'logs -t ${1:-100}'"
         ),
     vec!["alias", "aliases"],
     noop_complete!(),
@@ -101,7 +134,12 @@ Examples:
             clickwriteln!(writer, "aliased {} = '{}'", alias, expanded);
         } else {
             for alias in env.click_config.aliases.iter() {
-                clickwriteln!(writer, "alias {} = '{}'", alias.alias, alias.expanded);
+                print_alias(alias, None, writer);
+            }
+            if let Some(context) = env.context.as_ref() {
+                for alias in env.context_aliases().iter() {
+                    print_alias(alias, Some(&context.name), writer);
+                }
             }
         }
         Ok(())
@@ -0,0 +1,126 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use chrono::Local;
+use clap::{Arg, Command as ClapCommand};
+use rustyline::completion::Pair as RustlinePair;
+
+use crate::{
+    audit::{self, AUDIT_FILE},
+    command::command_def::{exec_match, start_clap, Cmd},
+    completer,
+    env::Env,
+    output::ClickWriter,
+    table::CellSpec,
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+
+command!(
+    Audit,
+    "audit",
+    "Show recent entries from the audit log of actions that changed a cluster",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("count")
+                .short('n')
+                .long("count")
+                .help("How many entries to show")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("20"),
+        )
+        .arg(
+            Arg::new("context")
+                .short('c')
+                .long("context")
+                .help("Only show entries for the specified context")
+                .takes_value(true),
+        )
+        .after_help(
+            "Every request click makes that changes a cluster (deletes, edits, execs, port \
+             forwards, etc.) is recorded in audit.jsonl in the click config dir, one json object \
+             per line, with the time, context, namespace, impersonated user, method, path and \
+             outcome.",
+        )
+    },
+    vec!["audit"],
+    noop_complete!(),
+    [(
+        "context".to_string(),
+        completer::context_complete as fn(&str, &Env) -> Vec<RustlinePair>
+    )]
+    .into_iter()
+    .collect(),
+    |matches, env, writer| {
+        let count = *matches.get_one::<usize>("count").unwrap(); // safe, has default
+        let context = matches.get_one::<String>("context");
+        let path = env.click_config_dir().join(AUDIT_FILE);
+        let mut entries = audit::read_entries(&path)?;
+        if let Some(context) = context {
+            entries.retain(|entry| &entry.context == context);
+        }
+        if entries.is_empty() {
+            clickwriteln!(writer, "No audit log entries");
+            return Ok(());
+        }
+        let skip = entries.len().saturating_sub(count);
+        let rows = entries
+            .into_iter()
+            .skip(skip)
+            .map(|entry| {
+                let outcome_color = if entry.failed() {
+                    env.styles.danger_color()
+                } else {
+                    env.styles.success_color()
+                };
+                let outcome = if entry.dry_run {
+                    format!("{} (dry run)", entry.outcome)
+                } else {
+                    entry.outcome.clone()
+                };
+                vec![
+                    entry
+                        .timestamp
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string()
+                        .into(),
+                    entry.context.into(),
+                    entry.namespace.unwrap_or_default().into(),
+                    entry.user.unwrap_or_default().into(),
+                    entry.method.into(),
+                    entry.path.into(),
+                    CellSpec::with_colors(outcome.into(), outcome_color.map(Into::into), None),
+                ]
+            })
+            .collect();
+        crate::table::print_table(
+            vec![
+                "Time",
+                "Context",
+                "Namespace",
+                "User",
+                "Method",
+                "Path",
+                "Outcome",
+            ],
+            rows,
+            env,
+            writer,
+        );
+        Ok(())
+    }
+);
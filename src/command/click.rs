@@ -13,21 +13,26 @@
 // limitations under the License.
 
 use chrono::offset::Utc;
-use clap::{Arg, Command as ClapCommand};
+use clap::{Arg, ArgMatches, Command as ClapCommand};
 use comfy_table::Table;
+use regex::Regex;
 use rustyline::completion::Pair as RustlinePair;
 
 use crate::{
     command::command_def::{exec_match, identity, start_clap, Cmd},
     completer, config,
-    env::Env,
+    env::{Env, ObjectSelection, BUILTIN_VARS},
+    error::ClickError,
+    kobj::KObj,
     output::ClickWriter,
-    table::CellSpec,
+    script::Script,
+    table::{filter_table, CellSpec, RowFilter},
 };
 
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::{stderr, Write};
+use std::path::Path;
 
 command!(
     Clear,
@@ -56,7 +61,7 @@ fn print_contexts(env: &Env, writer: &mut ClickWriter) {
             };
             row.push(CellSpec::with_colors(
                 (*context).clone().into(),
-                Some(env.styles.context_table_color().into()),
+                env.styles.context_table_color().map(Into::into),
                 None,
             ));
             row.push(cluster.into());
@@ -190,31 +195,369 @@ command!(
     }
 );
 
+/// Parse a regex given as /regex/ or just regex
+fn name_regex(spec: &str) -> Result<Regex, ClickError> {
+    let pattern = spec
+        .strip_prefix('/')
+        .and_then(|s| s.strip_suffix('/'))
+        .unwrap_or(spec);
+    Regex::new(pattern)
+        .map_err(|e| ClickError::CommandError(format!("Invalid regex {pattern}: {e}")))
+}
+
+/// The objects that targets refer to. Indexes, ranges and comma separated lists refer to the last
+/// list of objects, and regexes select the objects in candidates with matching names
+fn range_targets(
+    env: &Env,
+    targets: &[&str],
+    candidates: &[KObj],
+) -> Result<Vec<KObj>, ClickError> {
+    let mut objs = vec![];
+    for target in targets {
+        let found = match env.items_at_spec(target) {
+            Some(found) => found,
+            None => {
+                let regex = name_regex(target)?;
+                candidates
+                    .iter()
+                    .filter(|obj| regex.is_match(obj.name()))
+                    .cloned()
+                    .collect()
+            }
+        };
+        for obj in found {
+            if !objs.contains(&obj) {
+                objs.push(obj);
+            }
+        }
+    }
+    Ok(objs)
+}
+
+fn print_range(env: &Env, writer: &mut ClickWriter) -> Result<(), ClickError> {
+    if let ObjectSelection::None = env.current_selection() {
+        clickwriteln!(writer, "No objects selected");
+        return Ok(());
+    }
+    let mut table = Table::new();
+    table.set_header(vec!["Name", "Type", "Namespace"]);
+    env.apply_to_selection(writer, None, |obj, _| {
+        table.add_row(vec![
+            obj.name(),
+            obj.type_str(),
+            obj.namespace.as_deref().unwrap_or(""),
+        ]);
+        Ok(())
+    })?;
+    crate::table::print_filled_table(&mut table, writer);
+    Ok(())
+}
+
 command!(
     Range,
     "range",
-    "List the objects that are in the currently selected range (see 'help ranges' for general \
-     information about ranges)",
-    identity,
+    "List the objects that are in the currently selected range, or add or remove objects from it \
+     (see 'help ranges' for general information about ranges)",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("op")
+                .help("Change the current range before listing it")
+                .value_parser(["add", "remove", "intersect"])
+                .required(false)
+                .index(1),
+        )
+        .arg(
+            Arg::new("targets")
+                .help("Indexes, ranges (like 1..3), comma separated lists, or /regex/s")
+                .required(false)
+                .multiple_values(true)
+                .index(2),
+        )
+        .after_help(
+            "add: add objects from the last list to the range
+remove: remove objects from the range
+intersect: keep only the objects in the range that are also targets
+
+Indexes and ranges refer to the last list of objects. A /regex/ matches object names in the last \
+list for add, and in the current range for remove and intersect.
+
+Examples:
+  # add items 5 and 7 to the range
+  range add 5 7
+
+  # remove the first two items of the last list from the range
+  range remove 0..2
+
+  # keep only objects with api- in their names
+  range intersect /api-/",
+        )
+    },
     vec!["range"],
     noop_complete!(),
     no_named_complete!(),
-    |_, env, writer| {
-        let mut table = Table::new();
-        table.set_header(vec!["Name", "Type", "Namespace"]);
-        env.apply_to_selection(writer, None, |obj, _| {
-            table.add_row(vec![
-                obj.name(),
-                obj.type_str(),
-                obj.namespace.as_deref().unwrap_or(""),
-            ]);
-            Ok(())
-        })?;
-        crate::table::print_filled_table(&mut table, writer);
+    |matches, env, writer| {
+        if let Some(op) = matches.get_one::<String>("op") {
+            let targets: Vec<&str> = matches
+                .get_many::<String>("targets")
+                .map(|targets| targets.map(|s| s.as_str()).collect())
+                .unwrap_or_default();
+            if targets.is_empty() {
+                return Err(ClickError::CommandError(format!(
+                    "range {op} needs indexes, ranges or /regex/s"
+                )));
+            }
+            let current = env.selected_objs();
+            let objs: Vec<KObj> = match op.as_str() {
+                "add" => {
+                    let last = env.get_last_objs().cloned().unwrap_or_default();
+                    let mut objs = current;
+                    for obj in range_targets(env, &targets, &last)? {
+                        if !objs.contains(&obj) {
+                            objs.push(obj);
+                        }
+                    }
+                    objs
+                }
+                "remove" => {
+                    let remove = range_targets(env, &targets, &current)?;
+                    current
+                        .into_iter()
+                        .filter(|obj| !remove.contains(obj))
+                        .collect()
+                }
+                _ => {
+                    let keep = range_targets(env, &targets, &current)?;
+                    current
+                        .into_iter()
+                        .filter(|obj| keep.contains(obj))
+                        .collect()
+                }
+            };
+            env.select_objs(objs);
+        }
+        print_range(env, writer)
+    }
+);
+
+command!(
+    Select,
+    "select",
+    "Select objects from the last list by name, status or column value",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("regex")
+                .help(
+                    "Select objects with names that match this regex, written as /regex/ or regex",
+                )
+                .required(false)
+                .index(1),
+        )
+        .arg(
+            Arg::new("status")
+                .short('s')
+                .long("status")
+                .help("Select objects with this value in the Status column")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("where")
+                .short('w')
+                .long("where")
+                .help(
+                    "Select objects whose row matches a column expression, like the ones used \
+with '| where' (see 'help pipes')",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("all")
+                .short('a')
+                .long("all")
+                .help("Select every object in the last list")
+                .conflicts_with_all(&["regex", "status", "where"]),
+        )
+        .after_help(
+            "The name regex, --status and --where can be combined, and objects must match all \
+of them. If exactly one object matches it becomes the current object, otherwise the matches become \
+the current range.
+
+Examples:
+  # select all pods in CrashLoopBackOff
+  pods; select --status CrashLoopBackOff
+
+  # select the pods whose names start with api-
+  select /^api-/",
+        )
+    },
+    vec!["select"],
+    vec![&completer::last_objs_completer],
+    no_named_complete!(),
+    |matches, env, writer| {
+        let last = match env.get_last_objs() {
+            Some(last) => last.clone(),
+            None => {
+                return Err(ClickError::CommandError(
+                    "No objects to select from, list some objects first".to_string(),
+                ))
+            }
+        };
+        let mut exprs = vec![];
+        if let Some(status) = matches.get_one::<String>("status") {
+            exprs.push(("Status", format!("status={status}")));
+        }
+        if let Some(expr) = matches.get_one::<String>("where") {
+            exprs.push(("", expr.clone()));
+        }
+        let regex = matches
+            .get_one::<String>("regex")
+            .map(|regex| name_regex(regex))
+            .transpose()?;
+        if !matches.contains_id("all") && exprs.is_empty() && regex.is_none() {
+            return Err(ClickError::CommandError(
+                "Specify a name regex, --status, --where or --all".to_string(),
+            ));
+        }
+
+        let mut objs = last;
+        if !exprs.is_empty() {
+            let table = env.get_last_table().ok_or_else(|| {
+                ClickError::CommandError(
+                    "The last list has no table to match --status or --where against".to_string(),
+                )
+            })?;
+            let mut filters = vec![];
+            for (column, expr) in exprs.iter() {
+                let filter = RowFilter::new(expr, table)?;
+                if !column.is_empty() && !matches!(filter, RowFilter::Column { .. }) {
+                    return Err(ClickError::CommandError(format!(
+                        "The last list has no {column} column"
+                    )));
+                }
+                filters.push(filter);
+            }
+            objs = filter_table(table, &objs, &filters).1;
+        }
+        if let Some(regex) = regex {
+            objs.retain(|obj| regex.is_match(obj.name()));
+        }
+        if objs.is_empty() {
+            return Err(ClickError::CommandError("No objects matched".to_string()));
+        }
+        env.select_objs(objs);
+        print_range(env, writer)
+    }
+);
+
+// The description of obj shown beside the list in pick
+fn describe_preview(obj: &KObj, describe_matches: &ArgMatches, env: &Env) -> String {
+    let mut buf = ClickWriter::with_buffer(vec![], false);
+    let text = match obj.describe(describe_matches, env, &mut buf) {
+        Ok(()) => buf.finish_output().unwrap_or_default(),
+        Err(e) => e.to_string().into_bytes(),
+    };
+    String::from_utf8_lossy(&text).into_owned()
+}
+
+command!(
+    Pick,
+    "pick",
+    "Pick objects from the last list with a fuzzy finder",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("no_preview")
+                .short('P')
+                .long("no-preview")
+                .help("Don't show a preview of the highlighted object")
+                .takes_value(false),
+        )
+        .after_help(
+            "Type to filter the list, and the picked objects become the current selection (or \
+range, if more than one is picked). Pressing Alt-p on an empty prompt runs pick.
+
+Keys:
+  Up/Down, Ctrl-p/Ctrl-n  Move the highlight
+  Tab/Shift-Tab           Select or unselect the highlighted object
+  Ctrl-a                  Select everything that matches
+  Ctrl-u                  Clear the filter
+  Enter                   Pick the selected objects, or the highlighted one if none are selected
+  Esc/Ctrl-c              Cancel
+
+The preview beside the list shows the output of describe for the highlighted object. It's only \
+shown if the terminal is at least 80 columns wide.",
+        )
+    },
+    vec!["pick"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let objs = match env.get_last_objs() {
+            Some(objs) if !objs.is_empty() => objs.clone(),
+            _ => {
+                return Err(ClickError::CommandError(
+                    "No objects to pick from, list some objects first".to_string(),
+                ))
+            }
+        };
+        let (header, lines) = crate::tui::list_lines(env, &objs);
+        let picked = {
+            let env: &Env = env;
+            let describe_matches = crate::command::describe::Describe::new().default_matches()?;
+            let preview = |index: usize| describe_preview(&objs[index], &describe_matches, env);
+            if matches.contains_id("no_preview") {
+                crate::picker::pick(&header, &lines, None::<fn(usize) -> String>)?
+            } else {
+                crate::picker::pick(&header, &lines, Some(preview))?
+            }
+        };
+        if let Some(picked) = picked {
+            env.select_objs(picked.into_iter().map(|i| objs[i].clone()).collect());
+            print_range(env, writer)?;
+        }
         Ok(())
     }
 );
 
+command!(
+    Dashboard,
+    "dashboard",
+    "Open a full screen dashboard of objects, with their description, events and logs",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("list_command")
+                .help("The command to list objects with, like 'deployments' or 'pods -l app=web'")
+                .default_value("pods")
+                .multiple_values(true)
+                .index(1),
+        )
+        .after_help(
+            "The list of objects is on the left, and the description, events and logs (for pods) \
+of the highlighted object are on the right. The list refreshes every few seconds. This can also \
+be started with 'click --tui'.
+
+Keys:
+  j/k, Up/Down  Move the highlight
+  /             Filter the list (Esc clears the filter)
+  :             Change the list command, for example ':deployments'
+  r             Refresh the list now
+  d             Delete the highlighted object
+  e             Exec a command in the highlighted pod
+  l             Follow the logs of the highlighted pod (stop with ^C)
+  Enter         Exit, leaving the highlighted object selected
+  q/Esc         Exit",
+        )
+    },
+    vec!["dashboard"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, _writer| {
+        let list_command: Vec<&str> = matches
+            .get_many::<String>("list_command")
+            .map(|args| args.map(|s| s.as_str()).collect())
+            .unwrap_or_default();
+        env.request_dashboard(list_command.join(" "))
+    }
+);
+
 command!(
     Last,
     "last",
@@ -233,7 +576,45 @@ command!(
     }
 );
 
-pub const SET_OPTS: [&str; 7] = [
+command!(
+    Source,
+    "source",
+    "Run the click commands in a file, one per line",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("file")
+                .help("The file of commands to run")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::new("stop_on_error")
+                .short('e')
+                .long("stop-on-error")
+                .help("Stop running the file after the first command that fails"),
+        )
+        .after_help(
+            "Blank lines and lines starting with # are ignored. The same files can be run when \
+starting click with --script, and a file named clickrc in the config dir is sourced each time \
+click starts.
+
+Example:
+  # Run the commands in debug.click, stopping if any of them fail
+  source -e debug.click",
+        )
+    },
+    vec!["source"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, _writer| {
+        let file = matches.get_one::<String>("file").unwrap(); // safe, required
+        let script = Script::from_file(Path::new(file), matches.contains_id("stop_on_error"))?;
+        env.queue_script(script);
+        Ok(())
+    }
+);
+
+pub const SET_OPTS: [&str; 10] = [
     "completion_type",
     "edit_mode",
     "editor",
@@ -241,6 +622,9 @@ pub const SET_OPTS: [&str; 7] = [
     "terminal",
     "range_separator",
     "describe_include_events",
+    "restore_session",
+    "prompt",
+    "var",
 ];
 
 command!(
@@ -261,16 +645,29 @@ command!(
                 .required(true)
                 .index(2),
         )
+        .arg(
+            Arg::new("var_value")
+                .help("The value to set the variable to, when setting a var")
+                .required(false)
+                .index(3),
+        )
         .after_help(
             "Note that if your value contains a -, you'll need to tell click it's not an option by
 passing '--' before.
 
+'set var NAME VALUE' sets a variable, the same as 'let NAME VALUE'. See 'help let'.
+
+'set prompt TEMPLATE' changes the prompt. See 'help prompt' for the placeholders it can use.
+
 Example:
   # Set the range_separator (needs the '--' after set since the value contains a -)
   set -- range_separator \"---- {name} [{namespace}] ----\"
 
   # set edit_mode
-  set edit_mode emacs",
+  set edit_mode emacs
+
+  # a compact prompt that shows who you're impersonating, if anyone
+  set prompt '{context}:{namespace}( as {user:danger reverse}) {selection}> '",
         )
     },
     vec!["set"],
@@ -285,8 +682,21 @@ Example:
             .get_one::<String>("value")
             .map(|s| s.as_str())
             .unwrap(); // safe, required
+        if option != "var" && matches.contains_id("var_value") {
+            return Err(ClickError::CommandError(format!(
+                "set {option} only takes one value"
+            )));
+        }
         let mut failed = false;
         match option {
+            "var" => {
+                let var_value = matches
+                    .get_one::<String>("var_value")
+                    .ok_or_else(|| ClickError::CommandError("set var needs a value".to_string()))?;
+                env.set_var(value, var_value.clone())?;
+                clickwriteln!(writer, "Set ${} to '{}'", value, var_value);
+                return Ok(());
+            }
             "completion_type" => match value {
                 "circular" => env.set_completion_type(config::CompletionType::Circular),
                 "list" => env.set_completion_type(config::CompletionType::List),
@@ -331,6 +741,14 @@ Example:
                     failed = true;
                 }
             },
+            "restore_session" => match value.parse() {
+                Ok(b) => env.click_config.restore_session = b,
+                Err(_) => {
+                    clickwriteln!(writer, "restore_session must be set to 'true' or 'false'");
+                    failed = true;
+                }
+            },
+            "prompt" => env.set_prompt_template(Some(value))?,
             _ => {
                 // this shouldn't happen
                 writeln!(stderr(), "Invalid option").unwrap_or(());
@@ -344,7 +762,109 @@ Example:
     }
 );
 
-pub const UNSET_OPTS: [&str; 4] = ["editor", "kubectl_binary", "terminal", "range_separator"];
+command!(
+    Let,
+    "let",
+    "Set a variable, or list variables if run with no arguments",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("unset")
+                .short('u')
+                .long("unset")
+                .help("Remove the variable"),
+        )
+        .arg(
+            Arg::new("name")
+                .help("The name of the variable")
+                .required(false)
+                .index(1),
+        )
+        .arg(
+            Arg::new("value")
+                .help("The value to set the variable to, an '=' before the value is optional")
+                .required(false)
+                .multiple_values(true)
+                .index(2),
+        )
+        .after_help(
+            "Variables are substituted into commands when used as $name or ${name}. The output of \
+a command can be substituted with $(command). As in a shell, nothing is substituted inside single \
+quotes or after a \\. A substituted value is always a single argument, even if it has spaces in it.
+
+The following variables are always defined:
+  $ctx        the current context
+  $ns         the current namespace
+  $name       the name of the selected object
+  $namespace  the namespace of the selected object
+
+Examples:
+  # Save the name of the selected pod
+  let pod = $name
+
+  # Save the output of a command
+  let image = $(describe -j | jq -r '.spec.containers[0].image')
+
+  # Use a variable
+  pods -r $pod
+
+  # Show all variables
+  let",
+        )
+    },
+    vec!["let"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let name = match matches.get_one::<String>("name") {
+            Some(name) => name,
+            None => {
+                for var in BUILTIN_VARS.iter() {
+                    let val = env.get_var(var).unwrap_or_else(|_| "<unset>".to_string());
+                    clickwriteln!(writer, "${} = '{}' (built-in)", var, val);
+                }
+                for (name, val) in env.get_vars().iter() {
+                    clickwriteln!(writer, "${} = '{}'", name, val);
+                }
+                return Ok(());
+            }
+        };
+        // support name=value, as well as name = value and name value
+        let (name, mut values) = match name.split_once('=') {
+            Some((name, val)) if !val.is_empty() => (name, vec![val]),
+            Some((name, _)) => (name, vec![]),
+            None => (name.as_str(), vec![]),
+        };
+        if let Some(vals) = matches.get_many::<String>("value") {
+            values.extend(vals.map(|s| s.as_str()));
+        }
+        if values.first() == Some(&"=") {
+            values.remove(0);
+        }
+
+        if matches.contains_id("unset") {
+            if env.unset_var(name) {
+                clickwriteln!(writer, "Unset ${}", name);
+            } else {
+                clickwriteln!(writer, "No variable named {}", name);
+            }
+        } else if values.is_empty() {
+            clickwriteln!(writer, "{}", env.get_var(name)?);
+        } else {
+            let value = values.join(" ");
+            env.set_var(name, value.clone())?;
+            clickwriteln!(writer, "Set ${} to '{}'", name, value);
+        }
+        Ok(())
+    }
+);
+
+pub const UNSET_OPTS: [&str; 5] = [
+    "editor",
+    "kubectl_binary",
+    "terminal",
+    "range_separator",
+    "prompt",
+];
 
 command!(
     UnSetCmd,
@@ -381,6 +901,9 @@ command!(
             "range_separator" => {
                 env.click_config.range_separator = crate::config::default_range_sep();
             }
+            "prompt" => {
+                env.set_prompt_template(None)?;
+            }
             _ => {
                 // this shouldn't happen
                 writeln!(stderr(), "Invalid option").unwrap_or(());
@@ -407,3 +930,48 @@ command!(
         Ok(())
     }
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{get_test_config, ClickConfig};
+    use crate::k8s::tests::serve;
+    use crate::k8s::Context;
+    use crate::kobj::ObjType;
+
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_describe_preview() {
+        let node = serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Node",
+            "metadata": {"name": "node-1", "creationTimestamp": "2021-01-01T00:00:00Z"},
+        });
+        let url = serve("200 OK", node.to_string());
+        let mut env = Env::new(
+            get_test_config(),
+            ClickConfig::default(),
+            PathBuf::from("/tmp/click.config"),
+        );
+        env.context = Some(Context::new(
+            "test",
+            url.parse().unwrap(),
+            None,
+            None,
+            None,
+            5,
+            5,
+        ));
+        let obj = KObj {
+            name: "node-1".to_string(),
+            namespace: None,
+            typ: ObjType::Node,
+        };
+        let matches = crate::command::describe::Describe::new()
+            .default_matches()
+            .unwrap();
+        let preview = describe_preview(&obj, &matches, &env);
+        assert!(preview.contains("node-1"), "{preview}");
+    }
+}
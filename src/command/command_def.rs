@@ -12,9 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use clap::builder::{PossibleValue, PossibleValuesParser};
+use clap::builder::{PossibleValue, TypedValueParser};
 /// This module contains shared code that's useful for defining commands
-use clap::{Arg, ArgMatches, Command as ClapCommand};
+use clap::{error::ErrorKind, Arg, ArgMatches, Command as ClapCommand};
 use rustyline::completion::Pair as RustlinePair;
 
 use crate::env::Env;
@@ -22,6 +22,7 @@ use crate::error::ClickError;
 use crate::output::ClickWriter;
 
 use std::cell::RefCell;
+use std::ffi::OsStr;
 use std::io::Write;
 
 // command definition
@@ -106,7 +107,7 @@ pub trait Cmd {
         env: &Env,
     ) -> Vec<RustlinePair>;
     fn complete_option(&self, prefix: &str) -> Vec<RustlinePair>;
-    fn write_help(&self, writer: &mut ClickWriter);
+    fn write_help(&self, env: &Env, writer: &mut ClickWriter);
     fn about(&self) -> &'static str;
 }
 
@@ -130,6 +131,27 @@ pub fn start_clap(
     }
 }
 
+/// Write the help for clap to writer. Clap doesn't know about themes, so its help is rendered
+/// without color, and the section headings (like USAGE: and ALIASES:) are styled here instead
+pub fn write_clap_help(clap: &mut ClapCommand<'static>, env: &Env, writer: &mut ClickWriter) {
+    let mut help = vec![];
+    if let Err(e) = clap.write_help(&mut help) {
+        clickwriteln!(writer, "Couldn't print help: {}", e);
+        return;
+    }
+    for line in String::from_utf8_lossy(&help).lines() {
+        let is_heading = line
+            .strip_suffix(':')
+            .map(|h| !h.is_empty() && h.chars().all(|c| c.is_ascii_uppercase() || c == ' '))
+            .unwrap_or(false);
+        if is_heading {
+            clickwriteln!(writer, "{}", env.styles.warning(line));
+        } else {
+            clickwriteln!(writer, "{}", line);
+        }
+    }
+}
+
 /// Run specified closure with given matches. Returns () on success, or an Err if an error occurs
 pub fn exec_match<F>(
     clap: &RefCell<ClapCommand<'static>>,
@@ -147,9 +169,7 @@ where
         Ok(matches) => func(matches, env, writer),
         Err(e) => {
             if e.kind() == clap::ErrorKind::DisplayHelp {
-                cmd.print_help().expect("Couldn't print help");
-                // todo: switch back in the same way as write_help
-                //clickwriteln!(writer, "{}", e);
+                write_clap_help(&mut cmd, env, writer);
                 Ok(())
             } else if e.kind() == clap::ErrorKind::DisplayVersion {
                 clickwriteln!(writer, "{}", e);
@@ -228,10 +248,8 @@ macro_rules! command {
 
         impl $cmd_name {
             pub fn new() -> $cmd_name {
-                use crossterm::style::Stylize;
                 lazy_static! {
-                    static ref ALIASES_STR: String =
-                        format!("{}:\n    {:?}", "ALIASES".yellow(), $aliases);
+                    static ref ALIASES_STR: String = format!("ALIASES:\n    {:?}", $aliases);
                 }
                 let clap = start_clap($name, $about, &ALIASES_STR, $trailing_var_arg);
                 #[allow(clippy::redundant_closure_call)]
@@ -263,12 +281,12 @@ macro_rules! command {
                 $name
             }
 
-            // TODO: switch back to command.write_help when
-            // https://github.com/clap-rs/clap/issues/3480 is resolved
-            fn write_help(&self, writer: &mut ClickWriter) {
-                if let Err(res) = self.clap.borrow_mut().print_help() {
-                    clickwriteln!(writer, "Couldn't print help: {}", res);
-                }
+            fn write_help(&self, env: &Env, writer: &mut ClickWriter) {
+                crate::command::command_def::write_clap_help(
+                    &mut self.clap.borrow_mut(),
+                    env,
+                    writer,
+                );
             }
 
             fn about(&self) -> &'static str {
@@ -353,23 +371,36 @@ macro_rules! command {
 /// adds the common various sorting/showing arguments and completors and then calls the base command
 /// macro
 macro_rules! list_command {
-    ($cmd_name:ident, $name:expr, $about:expr, $cols: expr, $extra_cols:expr, $extra_args:expr,
+    ($cmd_name:ident, $name:expr, $kind:expr, $about:expr, $cols: expr, $extra_cols:expr,
+     $extra_args:expr,
      $aliases:expr, $cmplters: expr, $named_cmplters: expr, $cmd_expr:expr) => {
         mod list_sort_completers {
-            use crate::{command::command_def::try_complete_all, env::Env};
+            use crate::{
+                columns::user_column_names,
+                command::command_def::{try_complete, try_complete_all},
+                env::Env,
+            };
             use rustyline::completion::Pair as RustlinePair;
             #[allow(non_snake_case)]
-            pub fn $cmd_name(prefix: &str, _env: &Env) -> Vec<RustlinePair> {
-                try_complete_all(prefix, $cols, $extra_cols)
+            pub fn $cmd_name(prefix: &str, env: &Env) -> Vec<RustlinePair> {
+                let user_cols = user_column_names(env, $kind, $name);
+                let user_cols: Vec<&str> = user_cols.iter().map(|c| c.as_str()).collect();
+                let mut v = try_complete_all(prefix, $cols, $extra_cols);
+                v.extend(try_complete(prefix, &user_cols, false));
+                v
             }
         }
 
         mod list_show_completers {
-            use crate::{command::command_def::try_complete, env::Env};
+            use crate::{columns::user_column_names, command::command_def::try_complete, env::Env};
             use rustyline::completion::Pair as RustlinePair;
             #[allow(non_snake_case)]
-            pub fn $cmd_name(prefix: &str, _env: &Env) -> Vec<RustlinePair> {
-                try_complete(prefix, $extra_cols, true)
+            pub fn $cmd_name(prefix: &str, env: &Env) -> Vec<RustlinePair> {
+                let user_cols = user_column_names(env, $kind, $name);
+                let user_cols: Vec<&str> = user_cols.iter().map(|c| c.as_str()).collect();
+                let mut v = try_complete(prefix, $extra_cols, true);
+                v.extend(try_complete(prefix, &user_cols, false));
+                v
             }
         }
 
@@ -431,30 +462,51 @@ pub fn add_extra_cols<'a>(
     }
 }
 
-// fn get_value_parser(set1: &[&str], set2: &[&str]) -> ValueParser {
+/// Parser for the values of --sort and --show. Columns can also be defined in the click config,
+/// which clap doesn't know about, so any value is accepted here and the list commands check them
+/// (see print_list_with_matches). The built-in columns are still listed as possible values in the
+/// help.
+#[derive(Clone)]
+struct ColumnParser(Vec<&'static str>);
+
+impl TypedValueParser for ColumnParser {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        _cmd: &ClapCommand,
+        _arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<String, clap::Error> {
+        value
+            .to_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| clap::Error::raw(ErrorKind::InvalidUtf8, "Invalid column name"))
+    }
 
-// }
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue<'static>> + '_>> {
+        Some(Box::new(self.0.iter().map(|s| PossibleValue::new(s))))
+    }
+}
 
 // sort based on column index given
-pub struct SortCol(pub &'static str);
+pub struct SortCol(pub String);
 
 /// get a clap arg for sorting. this takes one or two lists of possible values to allow for passing
 /// normal and extra cols
 pub fn sort_arg<'a>(cols: &'a [&'static str], extra_cols: Option<&'a [&'static str]>) -> Arg<'a> {
-    let pvp = match extra_cols {
-        Some(extra) => PossibleValuesParser::new(
-            cols.iter()
-                .chain(extra.iter())
-                .map(|s| PossibleValue::new(s)),
-        ),
-        None => PossibleValuesParser::new(cols.iter().map(|s| PossibleValue::new(s))),
-    };
+    let pvp = ColumnParser(
+        cols.iter()
+            .chain(extra_cols.unwrap_or_default().iter())
+            .copied()
+            .collect(),
+    );
     Arg::new("sort")
         .short('s')
         .long("sort")
         .help(
             "Sort by specified column (if column isn't shown by default, it will \
-             be shown)",
+             be shown). Columns defined in the click config can be used too",
         )
         .takes_value(true)
         .ignore_case(true)
@@ -463,19 +515,14 @@ pub fn sort_arg<'a>(cols: &'a [&'static str], extra_cols: Option<&'a [&'static s
 
 static SHOW_HELP: &str =
     "Comma separated list (case-insensitive) of extra columns to show in output. \
-     Use '--show all' to show all available columns.";
+     Use '--show all' to show all available columns, including any defined in the click config.";
 static SHOW_HELP_WITH_LABELS: &str =
     "Comma separated list (case-insensitive) of extra columns to show in output. \
      Use '--show all,labels' to show all available columns. (Note that 'all' doesn't \
-     include labels due to their size)";
+     include labels due to their size). Columns defined in the click config can be used too";
 /// get a clap arg for showing extra cols.
 pub fn show_arg<'a>(extra_cols: &'a [&'static str], labels: bool) -> Arg<'a> {
-    let pvp = PossibleValuesParser::new(
-        extra_cols
-            .iter()
-            .chain(["all"].iter())
-            .map(|s| PossibleValue::new(s)),
-    );
+    let pvp = ColumnParser(extra_cols.iter().chain(["all"].iter()).copied().collect());
     let arg = Arg::new("show")
         .short('S')
         .long("show")
@@ -12,22 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use clap::{Arg, Command as ClapCommand};
 use k8s_openapi::api::core::v1 as api;
+use k8s_openapi::{apimachinery::pkg::apis::meta::v1::Patch, PatchOptional, PatchResponse};
+use serde_json::json;
 
 use crate::{
     command::command_def::{exec_match, show_arg, sort_arg, start_clap, Cmd},
+    command::datakeys::{dump_dir_for, edit_value, get_key, write_keys_to_dir, write_raw_value},
     command::{run_list_command, Extractor},
     completer,
     env::Env,
+    error::ClickError,
     kobj::{KObj, ObjType},
     output::ClickWriter,
     table::CellSpec,
+    values::val_str,
 };
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::Write;
+use std::path::Path;
 
 lazy_static! {
     static ref CM_EXTRACTORS: HashMap<String, Extractor<api::ConfigMap>> = {
@@ -63,6 +70,7 @@ fn cm_data(configmap: &api::ConfigMap) -> Option<CellSpec<'_>> {
 list_command!(
     ConfigMaps,
     "configmaps",
+    "ConfigMap",
     "Get configmaps (in current namespace if set)",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
@@ -113,3 +121,186 @@ list_command!(
         )
     }
 );
+
+pub const CONFIGMAP_ACTIONS: [&str; 3] = ["get", "dump", "edit"];
+
+fn read_configmap(obj: &KObj, env: &Env) -> Result<api::ConfigMap, ClickError> {
+    if !matches!(obj.typ, ObjType::ConfigMap) {
+        return Err(ClickError::CommandError(format!(
+            "{} is a {}, not a configmap",
+            obj.name(),
+            obj.type_str()
+        )));
+    }
+    let ns = obj.namespace.as_deref().unwrap_or("default");
+    let (request, _) =
+        api::ConfigMap::read_namespaced_config_map(obj.name(), ns, Default::default())?;
+    match env.run_on_context(|c| c.read(env.get_impersonate_user(), request))? {
+        api::ReadNamespacedConfigMapResponse::Ok(cm) => Ok(cm),
+        _ => Err(ClickError::CommandError(format!(
+            "Could not read configmap {}",
+            obj.name()
+        ))),
+    }
+}
+
+/// Get all the data in a configmap as bytes, along with the set of keys that came from binaryData
+fn configmap_data(cm: api::ConfigMap) -> (BTreeMap<String, Vec<u8>>, HashSet<String>) {
+    let mut data: BTreeMap<String, Vec<u8>> = cm
+        .data
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(k, v)| (k, v.into_bytes()))
+        .collect();
+    let mut binary_keys = HashSet::new();
+    for (k, v) in cm.binary_data.unwrap_or_default().into_iter() {
+        binary_keys.insert(k.clone());
+        data.insert(k, v.0);
+    }
+    (data, binary_keys)
+}
+
+fn edit_configmap_key(
+    obj: &KObj,
+    key: &str,
+    dry_run: bool,
+    env: &Env,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    if !dry_run
+        && !env.check_mutation(&format!("edit {key} in configmap {}", obj.name()), writer)?
+    {
+        clickwriteln!(writer, "Not updating {}", obj.name());
+        return Ok(());
+    }
+    let (data, binary_keys) = configmap_data(read_configmap(obj, env)?);
+    let val = get_key(&data, key, obj.name())?;
+    let new_val = match edit_value(env, obj.name(), key, val, writer)? {
+        Some(new_val) => new_val,
+        None => {
+            clickwriteln!(writer, "No changes to {key}, not updating");
+            return Ok(());
+        }
+    };
+    let patch = if binary_keys.contains(key) {
+        json!({ "binaryData": { key: STANDARD.encode(new_val) } })
+    } else {
+        let new_str = String::from_utf8(new_val).map_err(|_| {
+            ClickError::CommandError(format!(
+                "{key} is in data, so it must be valid utf-8. Not updating"
+            ))
+        })?;
+        json!({ "data": { key: new_str } })
+    };
+    let (request, _) = api::ConfigMap::patch_namespaced_config_map(
+        obj.name(),
+        obj.namespace.as_deref().unwrap_or("default"),
+        &Patch::Merge(patch),
+        PatchOptional {
+            dry_run: dry_run.then_some("All"),
+            ..Default::default()
+        },
+    )?;
+    match env.run_on_context(|c| c.read(env.get_impersonate_user(), request))? {
+        PatchResponse::<api::ConfigMap>::Ok(_) if dry_run => {
+            clickwriteln!(writer, "Would update key {key} in {} (dry run)", obj.name());
+            Ok(())
+        }
+        PatchResponse::<api::ConfigMap>::Ok(_) => {
+            clickwriteln!(writer, "Updated key {key} in {}", obj.name());
+            Ok(())
+        }
+        PatchResponse::Other(Ok(Some(val))) => Err(ClickError::CommandError(format!(
+            "Failed to update {}: {}",
+            obj.name(),
+            val_str("/message", &val, "<no message>")
+        ))),
+        _ => Err(ClickError::CommandError(format!(
+            "Failed to update {}",
+            obj.name()
+        ))),
+    }
+}
+
+command!(
+    ConfigMap,
+    "configmap",
+    "Extract or edit the data in the active configmap(s)",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("action")
+                .help("Action to take")
+                .required(true)
+                .value_parser(CONFIGMAP_ACTIONS)
+                .index(1),
+        )
+        .arg(
+            Arg::new("key")
+                .help("The key to act on, or for dump, the directory to write to")
+                .required(true)
+                .index(2),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .help("For edit, ask the server to validate the change without saving it")
+                .takes_value(false),
+        )
+        .after_help(
+            "Actions:
+  get <key>   Print the raw value of key, so it can be piped or redirected
+  dump <dir>  Write each key to a file (named after the key) in dir
+  edit <key>  Open the value of key in your editor, and update the configmap when you exit
+
+Keys in both data and binaryData are handled. The 'secret' command supports the same actions for \
+secrets.
+
+Examples:
+  # Save nginx.conf out of the active configmap
+  configmap get nginx.conf > /tmp/nginx.conf
+
+  # Write all the keys in the configmap to files in /tmp/config
+  configmap dump /tmp/config
+
+  # Edit just application.yaml
+  configmap edit application.yaml",
+        )
+    },
+    vec!["configmap"],
+    vec![&completer::configmapaction_values_completer],
+    no_named_complete!(),
+    |matches, env, writer| {
+        let action = matches.get_one::<String>("action").unwrap(); // safe, required
+        let key = matches.get_one::<String>("key").unwrap().as_str(); // safe, required
+        match action.as_str() {
+            "get" => env.apply_to_selection(
+                writer,
+                Some(&env.click_config.range_separator),
+                |obj, writer| {
+                    let (data, _) = configmap_data(read_configmap(obj, env)?);
+                    write_raw_value(get_key(&data, key, obj.name())?, writer)
+                },
+            ),
+            "dump" => {
+                let dir = Path::new(key);
+                env.apply_to_selection(
+                    writer,
+                    Some(&env.click_config.range_separator),
+                    |obj, writer| {
+                        let (data, _) = configmap_data(read_configmap(obj, env)?);
+                        write_keys_to_dir(&data, &dump_dir_for(dir, obj, env), writer)
+                    },
+                )
+            }
+            "edit" => {
+                let dry_run = matches.contains_id("dry_run");
+                env.apply_to_selection(
+                    writer,
+                    Some(&env.click_config.range_separator),
+                    |obj, writer| edit_configmap_key(obj, key, dry_run, env, writer),
+                )
+            }
+            _ => unreachable!(), // clap validates action
+        }
+    }
+);
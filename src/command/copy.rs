@@ -17,6 +17,7 @@ use rustyline::completion::Pair as RustlinePair;
 
 use crate::{
     command::command_def::{exec_match, start_clap, Cmd},
+    command::exec::exec_path,
     completer,
     env::Env,
     error::ClickError,
@@ -32,6 +33,7 @@ use std::process::Command;
 
 #[allow(clippy::too_many_arguments)]
 fn do_copy(
+    env: &Env,
     pod: &KObj,
     context: &str,
     src: &str,
@@ -62,7 +64,24 @@ fn do_copy(
         .arg(&*dest_arg)
         .arg("--retries")
         .arg(format!("{}", retries));
-    match command.output() {
+    let output = command.output();
+    if !from {
+        // kubectl cp execs tar in the pod to unpack the file into the destination directory
+        let dest_dir = match dest.rfind('/') {
+            Some(0) => "/",
+            Some(i) => &dest[..i],
+            None => ".",
+        };
+        env.audit(
+            "POST",
+            &exec_path(ns, pod.name(), &None, &["tar", "-xmf", "-", "-C", dest_dir]),
+            match output {
+                Ok(ref output) => output.status.to_string(),
+                Err(ref e) => format!("error: {e}"),
+            },
+        );
+    }
+    match output {
         Ok(output) => {
             if output.status.success() {
                 clickwriteln!(writer, "copied");
@@ -179,7 +198,13 @@ Examples:
             Some(&env.click_config.range_separator),
             |obj, writer| {
                 if obj.is_pod() {
-                    do_copy(obj, &context.name, src, dest, from, retries, writer)
+                    if !from
+                        && !env.check_mutation(&format!("copy {src} to {}", obj.name()), writer)?
+                    {
+                        clickwriteln!(writer, "Not copying to {}", obj.name());
+                        return Ok(());
+                    }
+                    do_copy(env, obj, &context.name, src, dest, from, retries, writer)
                 } else {
                     Err(ClickError::CommandError(
                         "Copy only possible on pods".to_string(),
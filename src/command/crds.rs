@@ -19,9 +19,7 @@ use rustyline::completion::Pair as RustlinePair;
 use crate::{
     command::command_def::{exec_match, start_clap, Cmd},
     completer,
-    crd::GetAPIGroupResourcesResponse,
     env::Env,
-    error::ClickError,
     k8s_table::{get_k8s_table, GetTableResponse},
     output::ClickWriter,
 };
@@ -30,61 +28,6 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::Write;
 
-struct CrdApiDesc {
-    group_version: String,
-    name: String,
-    namespaced: bool,
-}
-
-impl CrdApiDesc {
-    fn url(&self, namespace: Option<&str>) -> String {
-        if self.namespaced && namespace.is_some() {
-            format!(
-                "/apis/{}/namespaces/{}/{}",
-                self.group_version,
-                namespace.as_ref().unwrap(), // safe: checked
-                self.name
-            )
-        } else {
-            format!("/apis/{}/{}", self.group_version, self.name)
-        }
-    }
-}
-
-// If the server indicates that it knows about crds named 'name', return the description we can use
-// to access them. Otherwise, return None
-fn find_desc_for(env: &mut Env, name: &str) -> Result<Option<CrdApiDesc>, ClickError> {
-    let groups = crate::crd::get_api_groups(env)?;
-    for group in groups.iter() {
-        let version = match group.preferred_version.as_ref() {
-            Some(pv) => Some(pv.group_version.as_str()),
-            None => group.versions.first().map(|v| v.group_version.as_str()),
-        };
-        if let Some(group_version) = version {
-            let (group_req, _) = crate::crd::get_api_group_resources(group_version)?;
-            match env.run_on_context::<_, GetAPIGroupResourcesResponse>(|c| {
-                c.read(env.get_impersonate_user(), group_req)
-            })? {
-                GetAPIGroupResourcesResponse::Ok(resp) => {
-                    for resource in resp.resources.iter() {
-                        if resource.name == name || resource.singular_name == name {
-                            return Ok(Some(CrdApiDesc {
-                                group_version: group_version.to_string(),
-                                name: resource.name.clone(),
-                                namespaced: resource.namespaced,
-                            }));
-                        }
-                    }
-                }
-                GetAPIGroupResourcesResponse::Other(_) => {
-                    println!("Error"); // TODO: Print something more useful
-                }
-            }
-        }
-    }
-    Ok(None)
-}
-
 command!(
     Crd,
     "crd",
@@ -96,14 +39,14 @@ command!(
             .index(1)
     ),
     vec!["crd"],
-    noop_complete!(),
+    vec![&completer::crd_resource_completer],
     no_named_complete!(),
     |matches, env, writer| {
         let name = matches
             .get_one::<String>("name")
             .map(|s| s.as_str())
             .unwrap(); // safe: required
-        let api_desc = find_desc_for(env, name)?;
+        let api_desc = crate::crd::find_resource_desc(env, name)?;
         match api_desc {
             Some(desc) => {
                 let (request, _) = get_k8s_table(&desc.url(env.namespace.as_deref()))?;
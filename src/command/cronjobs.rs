@@ -27,7 +27,6 @@ use crate::{
 
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::Write;
 
 lazy_static! {
     static ref JOB_EXTRACTORS: HashMap<String, Extractor<batch_api::CronJob>> = {
@@ -147,6 +146,7 @@ fn cjob_selector(cjob: &batch_api::CronJob) -> Option<CellSpec<'_>> {
 list_command!(
     CronJobs,
     "cronjobs",
+    "CronJob",
     "Get jobs (in current namespace if set)",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
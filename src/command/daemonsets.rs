@@ -27,7 +27,6 @@ use crate::{
 
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::Write;
 
 lazy_static! {
     static ref DS_EXTRACTORS: HashMap<String, Extractor<apps_api::DaemonSet>> = {
@@ -136,6 +135,7 @@ fn ds_up_to_date(daemonset: &apps_api::DaemonSet) -> Option<CellSpec<'_>> {
 list_command!(
     DaemonSets,
     "daemonsets",
+    "DaemonSet",
     "Get daemonsets (in current namespace if set)",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
@@ -0,0 +1,164 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers for commands that work on the individual keys of configmaps and secrets
+
+use crate::{
+    env::{Env, ObjectSelection},
+    error::ClickError,
+    kobj::KObj,
+    output::ClickWriter,
+};
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Figure out which editor to use. An explicitly passed editor wins, then the one set in the click
+/// config, and finally $EDITOR
+pub fn get_editor(env: &Env, editor_opt: Option<&str>) -> Result<String, ClickError> {
+    if let Some(v) = editor_opt {
+        Ok(v.to_owned())
+    } else if let Some(ref e) = env.click_config.editor {
+        Ok(e.clone())
+    } else {
+        std::env::var("EDITOR").map_err(|e| {
+            ClickError::CommandError(format!("Could not get EDITOR environment variable: {e}"))
+        })
+    }
+}
+
+/// Build the expression to run editor on path. The editor can include arguments
+pub fn editor_expr(editor: &str, path: &Path) -> duct::Expression {
+    if editor.contains(' ') {
+        // split the whitespace
+        let mut eargs: Vec<&str> = editor.split_whitespace().collect();
+        eargs.push(path.to_str().unwrap());
+        duct::cmd(eargs[0], &eargs[1..])
+    } else {
+        cmd!(editor, path)
+    }
+}
+
+// create a file only the current user can read, since it might hold secret data
+fn create_private_file(path: &Path) -> Result<File, ClickError> {
+    let mut opts = OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(0o600);
+    }
+    opts.open(path).map_err(ClickError::from)
+}
+
+/// Most actions need a second argument, this returns a nice error if it wasn't passed
+pub fn require_arg<'a>(
+    arg: Option<&'a str>,
+    action: &str,
+    what: &str,
+) -> Result<&'a str, ClickError> {
+    arg.ok_or_else(|| ClickError::CommandError(format!("{action} requires a {what}")))
+}
+
+/// Get the directory to write the keys of obj to. When operating on a range, each object gets its
+/// own subdir so keys with the same name don't collide
+pub fn dump_dir_for(dir: &Path, obj: &KObj, env: &Env) -> PathBuf {
+    match env.current_selection() {
+        ObjectSelection::Range(_) => dir.join(obj.name()),
+        _ => dir.to_path_buf(),
+    }
+}
+
+/// Get the value for key out of data, with a nice error if it's missing
+pub fn get_key<'a>(
+    data: &'a BTreeMap<String, Vec<u8>>,
+    key: &str,
+    obj_name: &str,
+) -> Result<&'a [u8], ClickError> {
+    data.get(key)
+        .map(|v| v.as_slice())
+        .ok_or_else(|| ClickError::CommandError(format!("{obj_name} has no key {key}")))
+}
+
+/// Write the raw value of a key, with nothing else, so it can be piped or redirected
+pub fn write_raw_value(val: &[u8], writer: &mut ClickWriter) -> Result<(), ClickError> {
+    writer.write_all(val)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write each key of data into its own file in dir
+pub fn write_keys_to_dir(
+    data: &BTreeMap<String, Vec<u8>>,
+    dir: &Path,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    fs::create_dir_all(dir)?;
+    for (key, val) in data.iter() {
+        // kubernetes doesn't allow these in keys, but don't trust that we got a sane key
+        if key.contains('/') || key == "." || key == ".." {
+            return Err(ClickError::CommandError(format!(
+                "Refusing to write key with invalid file name: {key}"
+            )));
+        }
+        let path = dir.join(key);
+        create_private_file(&path)?.write_all(val)?;
+        clickwriteln!(writer, "Wrote {} ({} bytes)", path.display(), val.len());
+    }
+    Ok(())
+}
+
+/// Open val in an editor, and wait for the editor to exit. Returns the new value if it was
+/// changed, or None if it wasn't
+pub fn edit_value(
+    env: &Env,
+    obj_name: &str,
+    key: &str,
+    val: &[u8],
+    writer: &mut ClickWriter,
+) -> Result<Option<Vec<u8>>, ClickError> {
+    let editor = get_editor(env, None)?;
+    let tmpdir = match env.tempdir {
+        Ok(ref td) => td,
+        Err(ref e) => {
+            return Err(ClickError::CommandError(format!(
+                "Failed to create tempdir: {e}"
+            )));
+        }
+    };
+    // keep the key as the suffix so the editor can pick a mode based on the extension
+    let file_path = tmpdir.path().join(format!("{obj_name}_{key}"));
+    create_private_file(&file_path)?.write_all(val)?;
+
+    clickwriteln!(
+        writer,
+        "Editing {key}, save and exit the editor to update it"
+    );
+    let res = editor_expr(&editor, &file_path).run();
+
+    let mut new_val = vec![];
+    let read = File::open(&file_path).and_then(|mut f| f.read_to_end(&mut new_val));
+    // don't leave secret data lying around any longer than needed
+    fs::remove_file(&file_path)?;
+    res?;
+    read?;
+
+    if new_val == val {
+        Ok(None)
+    } else {
+        Ok(Some(new_val))
+    }
+}
@@ -15,41 +15,58 @@
 use clap::{Arg, Command as ClapCommand};
 use k8s_openapi::{
     api::apps::v1 as api_apps, api::batch::v1 as api_batch, api::core::v1 as api,
-    api::storage::v1 as api_storage, http::Request, DeleteOptional, DeleteResponse,
+    api::storage::v1 as api_storage, apimachinery::pkg::apis::meta::v1::ObjectMeta, http::Request,
+    DeleteOptional, DeleteResponse, List, ListableResource, Metadata,
 };
 use rustyline::completion::Pair as RustlinePair;
 use serde::de::DeserializeOwned;
 
 use crate::{
     command::command_def::{exec_match, start_clap, Cmd},
-    command::uppercase_first,
+    command::{format_duration, uppercase_first},
     completer,
-    env::Env,
-    error::ClickError,
+    crd::{self, ApiResourceDesc, ReadResourceValueResponse},
+    env::{Env, ObjectSelection},
+    error::{ClickError, ErrorClass},
     kobj::{KObj, ObjType},
     output::ClickWriter,
     values::val_str,
 };
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::io::{self, stderr, Write};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often delete --wait checks if the object is gone
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 fn send_delete<D: DeserializeOwned + Debug>(
     env: &Env,
     writer: &mut ClickWriter,
     request: Request<Vec<u8>>,
+    dry_run: bool,
 ) -> Result<(), ClickError> {
     let r = env
         .run_on_context::<_, DeleteResponse<D>>(|c| c.read(env.get_impersonate_user(), request))?;
     match r {
         DeleteResponse::OkStatus(_) | DeleteResponse::OkValue(_) => {
-            clickwriteln!(writer, "Deleted");
+            if dry_run {
+                clickwriteln!(writer, "Would be deleted (dry run)");
+            } else {
+                clickwriteln!(writer, "Deleted");
+            }
             Ok(())
         }
         DeleteResponse::Accepted(_) => {
-            clickwriteln!(writer, "Delete request accepted");
+            if dry_run {
+                clickwriteln!(writer, "Delete request would be accepted (dry run)");
+            } else {
+                clickwriteln!(writer, "Delete request accepted");
+            }
             Ok(())
         }
         DeleteResponse::Other(res) => match res {
@@ -78,6 +95,7 @@ fn delete_obj(
     obj: &KObj,
     options: DeleteOptional,
 ) -> Result<(), ClickError> {
+    let dry_run = options.dry_run.is_some();
     match obj.namespace.as_ref() {
         Some(ns) => match obj.typ {
             ObjType::ConfigMap => {
@@ -87,7 +105,7 @@ fn delete_obj(
                     options,
                 )?
                 .0;
-                send_delete::<api::ConfigMap>(env, writer, req)
+                send_delete::<api::ConfigMap>(env, writer, req, dry_run)
             }
             ObjType::DaemonSet => {
                 let req = api_apps::DaemonSet::delete_namespaced_daemon_set(
@@ -96,7 +114,7 @@ fn delete_obj(
                     options,
                 )?
                 .0;
-                send_delete::<api_apps::DaemonSet>(env, writer, req)
+                send_delete::<api_apps::DaemonSet>(env, writer, req, dry_run)
             }
             ObjType::Deployment => {
                 let req = api_apps::Deployment::delete_namespaced_deployment(
@@ -105,13 +123,13 @@ fn delete_obj(
                     options,
                 )?
                 .0;
-                send_delete::<api_apps::Deployment>(env, writer, req)
+                send_delete::<api_apps::Deployment>(env, writer, req, dry_run)
             }
             ObjType::Job => {
                 let req =
                     api_batch::Job::delete_namespaced_job(obj.name.as_str(), ns.as_str(), options)?
                         .0;
-                send_delete::<api_batch::Job>(env, writer, req)
+                send_delete::<api_batch::Job>(env, writer, req, dry_run)
             }
             ObjType::CronJob => {
                 let req = api_batch::CronJob::delete_namespaced_cron_job(
@@ -120,7 +138,7 @@ fn delete_obj(
                     options,
                 )?
                 .0;
-                send_delete::<api_batch::Job>(env, writer, req)
+                send_delete::<api_batch::Job>(env, writer, req, dry_run)
             }
             ObjType::Namespace => {
                 clickwriteln!(
@@ -129,7 +147,7 @@ fn delete_obj(
                      Deleting anyway"
                 );
                 let req = api::Namespace::delete_namespace(obj.name.as_str(), options)?.0;
-                send_delete::<api::Namespace>(env, writer, req)
+                send_delete::<api::Namespace>(env, writer, req, dry_run)
             }
             ObjType::Node => {
                 clickwriteln!(
@@ -138,7 +156,7 @@ fn delete_obj(
                          Deleting anyway"
                 );
                 let req = api::Node::delete_node(obj.name.as_str(), options)?.0;
-                send_delete::<api::Node>(env, writer, req)
+                send_delete::<api::Node>(env, writer, req, dry_run)
             }
             ObjType::PersistentVolume => {
                 clickwriteln!(
@@ -148,16 +166,17 @@ fn delete_obj(
                 );
                 let req =
                     api::PersistentVolume::delete_persistent_volume(obj.name.as_str(), options)?.0;
-                send_delete::<api::PersistentVolume>(env, writer, req)
+                send_delete::<api::PersistentVolume>(env, writer, req, dry_run)
             }
             ObjType::Pod { .. } => {
                 let req =
                     api::Pod::delete_namespaced_pod(obj.name.as_str(), ns.as_str(), options)?.0;
-                send_delete::<api::Pod>(env, writer, req)
+                send_delete::<api::Pod>(env, writer, req, dry_run)
             }
-            ObjType::Crd { .. } => Err(ClickError::CommandError(
-                "Can't delete CRDs yet".to_string(),
-            )),
+            ObjType::Crd {
+                ref _type,
+                ref group_version,
+            } => delete_crd_obj(env, writer, obj, _type, group_version, Some(ns), options),
             ObjType::ReplicaSet => {
                 let req = api_apps::ReplicaSet::delete_namespaced_replica_set(
                     obj.name.as_str(),
@@ -165,7 +184,7 @@ fn delete_obj(
                     options,
                 )?
                 .0;
-                send_delete::<api_apps::ReplicaSet>(env, writer, req)
+                send_delete::<api_apps::ReplicaSet>(env, writer, req, dry_run)
             }
             ObjType::StatefulSet => {
                 let req = api_apps::StatefulSet::delete_namespaced_stateful_set(
@@ -174,13 +193,13 @@ fn delete_obj(
                     options,
                 )?
                 .0;
-                send_delete::<api_apps::StatefulSet>(env, writer, req)
+                send_delete::<api_apps::StatefulSet>(env, writer, req, dry_run)
             }
             ObjType::Secret => {
                 let req =
                     api::Secret::delete_namespaced_secret(obj.name.as_str(), ns.as_str(), options)?
                         .0;
-                send_delete::<api::Secret>(env, writer, req)
+                send_delete::<api::Secret>(env, writer, req, dry_run)
             }
             ObjType::Service => {
                 let req = api::Service::delete_namespaced_service(
@@ -189,7 +208,7 @@ fn delete_obj(
                     options,
                 )?
                 .0;
-                send_delete::<api::Service>(env, writer, req)
+                send_delete::<api::Service>(env, writer, req, dry_run)
             }
             ObjType::StorageClass => {
                 clickwriteln!(
@@ -199,32 +218,43 @@ fn delete_obj(
                 );
                 let req =
                     api_storage::StorageClass::delete_storage_class(obj.name.as_str(), options)?.0;
-                send_delete::<api_storage::StorageClass>(env, writer, req)
+                send_delete::<api_storage::StorageClass>(env, writer, req, dry_run)
             }
             #[cfg(feature = "argorollouts")]
-            ObjType::Rollout => Err(ClickError::CommandError(
-                "Cannot delete rollouts".to_string(),
-            )),
+            ObjType::Rollout => {
+                use crate::command::rollouts::RolloutValue;
+                let req = RolloutValue::delete_namespaced_rollout(
+                    obj.name.as_str(),
+                    ns.as_str(),
+                    options,
+                )?
+                .0;
+                send_delete::<RolloutValue>(env, writer, req, dry_run)
+            }
         },
         None => match obj.typ {
             ObjType::Node => {
                 let req = api::Node::delete_node(obj.name.as_str(), options)?.0;
-                send_delete::<api::Node>(env, writer, req)
+                send_delete::<api::Node>(env, writer, req, dry_run)
             }
             ObjType::Namespace => {
                 let req = api::Namespace::delete_namespace(obj.name.as_str(), options)?.0;
-                send_delete::<api::Namespace>(env, writer, req)
+                send_delete::<api::Namespace>(env, writer, req, dry_run)
             }
             ObjType::PersistentVolume => {
                 let req =
                     api::PersistentVolume::delete_persistent_volume(obj.name.as_str(), options)?.0;
-                send_delete::<api::PersistentVolume>(env, writer, req)
+                send_delete::<api::PersistentVolume>(env, writer, req, dry_run)
             }
             ObjType::StorageClass => {
                 let req =
                     api_storage::StorageClass::delete_storage_class(obj.name.as_str(), options)?.0;
-                send_delete::<api_storage::StorageClass>(env, writer, req)
+                send_delete::<api_storage::StorageClass>(env, writer, req, dry_run)
             }
+            ObjType::Crd {
+                ref _type,
+                ref group_version,
+            } => delete_crd_obj(env, writer, obj, _type, group_version, None, options),
             _ => {
                 let msg = format!("Object {} has no namespace. Cannot delete", obj.name());
                 Err(ClickError::CommandError(msg))
@@ -233,28 +263,754 @@ fn delete_obj(
     }
 }
 
-fn confirm_delete(
+// Delete an object of a type defined by a crd. The plural name used in the url is found with
+// discovery, since _type might be the kind or singular name
+fn delete_crd_obj(
+    env: &Env,
+    writer: &mut ClickWriter,
+    obj: &KObj,
+    _type: &str,
+    group_version: &str,
+    namespace: Option<&str>,
+    options: DeleteOptional,
+) -> Result<(), ClickError> {
+    let desc = crd::find_resource_in(env, group_version, _type)?.ok_or_else(|| {
+        ClickError::CommandError(format!(
+            "Cluster doesn't have a resource of type {_type} in {group_version}"
+        ))
+    })?;
+    if desc.namespaced && namespace.is_none() {
+        return Err(ClickError::CommandError(format!(
+            "{} is namespaced, but {} has no namespace. Cannot delete",
+            desc.name,
+            obj.name()
+        )));
+    }
+    let url = format!("{}/{}", desc.url(namespace), obj.name());
+    let req = crd::delete_resource(url, options)?.0;
+    send_delete::<serde_json::Value>(env, writer, req, options.dry_run.is_some())
+}
+
+// Built in resources, as (plural name, group version, kind, namespaced, supports deletecollection)
+const BUILTIN_RESOURCES: &[(&str, &str, &str, bool, bool)] = &[
+    ("configmaps", "v1", "ConfigMap", true, true),
+    ("namespaces", "v1", "Namespace", false, false),
+    ("nodes", "v1", "Node", false, true),
+    (
+        "persistentvolumeclaims",
+        "v1",
+        "PersistentVolumeClaim",
+        true,
+        true,
+    ),
+    ("persistentvolumes", "v1", "PersistentVolume", false, true),
+    ("pods", "v1", "Pod", true, true),
+    ("secrets", "v1", "Secret", true, true),
+    ("services", "v1", "Service", true, true),
+    ("daemonsets", "apps/v1", "DaemonSet", true, true),
+    ("deployments", "apps/v1", "Deployment", true, true),
+    ("replicasets", "apps/v1", "ReplicaSet", true, true),
+    ("statefulsets", "apps/v1", "StatefulSet", true, true),
+    ("cronjobs", "batch/v1", "CronJob", true, true),
+    ("jobs", "batch/v1", "Job", true, true),
+    (
+        "storageclasses",
+        "storage.k8s.io/v1",
+        "StorageClass",
+        false,
+        true,
+    ),
+    #[cfg(feature = "argorollouts")]
+    ("rollouts", "argoproj.io/v1alpha1", "Rollout", true, true),
+    (
+        "controllerrevisions",
+        "apps/v1",
+        "ControllerRevision",
+        true,
+        true,
+    ),
+    ("events", "v1", "Event", true, true),
+];
+
+/// Built in kinds that can be deleted with --selector without a discovery lookup
+pub fn collection_kinds() -> Vec<&'static str> {
+    BUILTIN_RESOURCES
+        .iter()
+        .filter(|(_, _, _, _, deletecollection)| *deletecollection)
+        .map(|(plural, _, _, _, _)| *plural)
+        .collect()
+}
+
+// Find a built in resource by its plural name or kind (which is also its singular name)
+fn builtin_desc(name: &str) -> Option<ApiResourceDesc> {
+    BUILTIN_RESOURCES
+        .iter()
+        .find(|(plural, _, kind, _, _)| *plural == name || kind.eq_ignore_ascii_case(name))
+        .map(
+            |(plural, group_version, kind, namespaced, deletecollection)| ApiResourceDesc {
+                group_version: group_version.to_string(),
+                name: plural.to_string(),
+                kind: kind.to_string(),
+                namespaced: *namespaced,
+                deletecollection: *deletecollection,
+            },
+        )
+}
+
+// Find how to reach the collection of kind, which can be a plural or singular name or a kind,
+// looking in the built in kinds first and then in every api group via discovery
+fn collection_desc(env: &Env, kind: &str) -> Result<ApiResourceDesc, ClickError> {
+    match builtin_desc(&kind.to_lowercase()) {
+        Some(desc) => Ok(desc),
+        None => crd::find_resource_desc(env, kind)?.ok_or_else(|| {
+            ClickError::CommandError(format!("Cluster doesn't have a resource of type {kind}"))
+        }),
+    }
+}
+
+// Get the api url of obj
+fn obj_url(env: &Env, obj: &KObj) -> Result<String, ClickError> {
+    let desc = match obj.typ {
+        ObjType::Crd {
+            ref _type,
+            ref group_version,
+        } => crd::find_resource_in(env, group_version, _type)?,
+        _ => builtin_desc(obj.type_str()),
+    }
+    .ok_or_else(|| {
+        ClickError::CommandError(format!(
+            "Cluster doesn't have a resource of type {}",
+            obj.type_str()
+        ))
+    })?;
+    Ok(format!(
+        "{}/{}",
+        desc.url(obj.namespace.as_deref()),
+        obj.name()
+    ))
+}
+
+// Read the object at url, or None if it doesn't exist
+fn read_obj_value(env: &Env, url: &str) -> Result<Option<serde_json::Value>, ClickError> {
+    let (request, _) = crd::read_resource_url(url.to_string())?;
+    let response = match env.run_on_context::<_, ReadResourceValueResponse>(|c| {
+        c.read(env.get_impersonate_user(), request)
+    }) {
+        Err(e) if e.class() == ErrorClass::NotFound => return Ok(None),
+        response => response?,
+    };
+    match response {
+        ReadResourceValueResponse::Ok(value) => Ok(Some(value)),
+        ReadResourceValueResponse::Other(Ok(Some(status))) => {
+            Err(ClickError::CommandError(format!(
+                "Could not read {url}: {}",
+                val_str("/message", &status, "<No message>")
+            )))
+        }
+        ReadResourceValueResponse::Other(_) => {
+            Err(ClickError::CommandError(format!("Could not read {url}")))
+        }
+    }
+}
+
+// Delete everything of kind that selector selects in the current namespace, with a single
+// deletecollection request. The matching objects are listed, and the user asked to confirm, first
+fn delete_collection(
+    env: &Env,
+    kind: &str,
+    selector: &str,
+    options: DeleteOptional,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let desc = collection_desc(env, kind)?;
+    if !desc.deletecollection {
+        return Err(ClickError::CommandError(format!(
+            "{} can't be deleted by selector, the cluster doesn't support deleting them as a \
+             collection",
+            desc.name
+        )));
+    }
+    let namespace = if desc.namespaced {
+        Some(env.namespace.as_deref().ok_or_else(|| {
+            ClickError::CommandError(
+                "Deleting by selector needs an active namespace, set one with `namespace`"
+                    .to_string(),
+            )
+        })?)
+    } else {
+        None
+    };
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    query.append_pair("labelSelector", selector);
+    let url = format!("{}?{}", desc.url(namespace), query.finish());
+
+    let (request, _) = crd::read_resource_url(url.clone())?;
+    let names: Vec<String> = match env.run_on_context::<_, ReadResourceValueResponse>(|c| {
+        c.read(env.get_impersonate_user(), request)
+    })? {
+        ReadResourceValueResponse::Ok(list) => list
+            .pointer("/items")
+            .and_then(|items| items.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| val_str("/metadata/name", item, "<Unknown>").into_owned())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        ReadResourceValueResponse::Other(_) => {
+            return Err(ClickError::CommandError(format!(
+                "Could not list {} matching {selector}",
+                desc.name
+            )))
+        }
+    };
+    let location = namespace
+        .map(|ns| format!(" in namespace {ns}"))
+        .unwrap_or_default();
+    if names.is_empty() {
+        clickwriteln!(writer, "No {} match {selector}{location}", desc.name);
+        return Ok(());
+    }
+    clickwriteln!(
+        writer,
+        "{} {} match {selector}{location}:",
+        names.len(),
+        desc.name
+    );
+    for name in names.iter() {
+        clickwriteln!(writer, "  {} {name}", desc.kind);
+    }
+
+    let dry_run = options.dry_run.is_some();
+    if !dry_run {
+        let action = format!("delete these {} {}", names.len(), desc.name);
+        env.ensure_writable(&action)?;
+        if !confirm(env, &action, writer) {
+            clickwriteln!(writer, "Not deleting");
+            return Ok(());
+        }
+    }
+    let req = crd::delete_resource(url, options)?.0;
+    send_delete::<serde_json::Value>(env, writer, req, dry_run)
+}
+
+// An object that the garbage collector deletes when one of its owners is deleted
+struct Dependent {
+    kind: &'static str,
+    name: String,
+    // (kind, name) of each owner
+    owners: Vec<(String, String)>,
+}
+
+fn list_dependents<T>(
+    env: &Env,
+    request: Request<Vec<u8>>,
+    kind: &'static str,
+    dependents: &mut Vec<Dependent>,
+) -> Result<(), ClickError>
+where
+    T: ListableResource + Metadata<Ty = ObjectMeta> + DeserializeOwned + Debug,
+{
+    let list: List<T> =
+        env.run_on_context(|c| c.execute_list(env.get_impersonate_user(), request))?;
+    for item in list.items.iter() {
+        let meta = item.metadata();
+        let owners: Vec<(String, String)> = meta
+            .owner_references
+            .iter()
+            .flatten()
+            .map(|owner| (owner.kind.clone(), owner.name.clone()))
+            .collect();
+        if !owners.is_empty() {
+            dependents.push(Dependent {
+                kind,
+                name: meta.name.clone().unwrap_or_default(),
+                owners,
+            });
+        }
+    }
+    Ok(())
+}
+
+// Get every object in namespace that has an owner, for the kinds that workloads own
+fn namespace_dependents(env: &Env, namespace: &str) -> Result<Vec<Dependent>, ClickError> {
+    let mut dependents = vec![];
+    let (request, _) =
+        api_apps::ReplicaSet::list_namespaced_replica_set(namespace, Default::default())?;
+    list_dependents::<api_apps::ReplicaSet>(env, request, "ReplicaSet", &mut dependents)?;
+    let (request, _) = api_apps::ControllerRevision::list_namespaced_controller_revision(
+        namespace,
+        Default::default(),
+    )?;
+    list_dependents::<api_apps::ControllerRevision>(
+        env,
+        request,
+        "ControllerRevision",
+        &mut dependents,
+    )?;
+    let (request, _) = api_batch::Job::list_namespaced_job(namespace, Default::default())?;
+    list_dependents::<api_batch::Job>(env, request, "Job", &mut dependents)?;
+    let (request, _) = api::Pod::list_namespaced_pod(namespace, Default::default())?;
+    list_dependents::<api::Pod>(env, request, "Pod", &mut dependents)?;
+    Ok(dependents)
+}
+
+// Print the objects owned by (kind, name), and the objects they own, indented by depth
+fn print_dependents(
+    dependents: &[Dependent],
+    kind: &str,
+    name: &str,
+    depth: usize,
+    seen: &mut HashSet<(String, String)>,
+    writer: &mut ClickWriter,
+) {
+    for dep in dependents.iter() {
+        if dep.owners.iter().any(|(k, n)| k == kind && n == name)
+            && seen.insert((dep.kind.to_string(), dep.name.clone()))
+        {
+            clickwriteln!(
+                writer,
+                "{}would also delete {} {}",
+                "  ".repeat(depth),
+                dep.kind,
+                dep.name
+            );
+            print_dependents(dependents, dep.kind, &dep.name, depth + 1, seen, writer);
+        }
+    }
+}
+
+// Send a dry run delete for obj, and show what the garbage collector would delete along with it.
+// dependents caches the owned objects of each namespace, so a range only lists each one once
+fn dry_run_delete(
     env: &Env,
     obj: &KObj,
     options: DeleteOptional,
+    dependents: &mut HashMap<String, Vec<Dependent>>,
     writer: &mut ClickWriter,
 ) -> Result<(), ClickError> {
-    let name = obj.name();
-    clickwrite!(writer, "Delete {} {} [y/N]? ", obj.type_str(), name);
+    clickwrite!(writer, "{} {}: ", obj.type_str(), obj.name());
+    delete_obj(env, writer, obj, options)?;
+    if options.propagation_policy == Some("Orphan") {
+        clickwriteln!(writer, "  dependents would be orphaned");
+        return Ok(());
+    }
+    if obj.is(ObjType::Namespace) {
+        clickwriteln!(
+            writer,
+            "  everything in namespace {} would also be deleted",
+            obj.name()
+        );
+        return Ok(());
+    }
+    if let Some(ns) = obj.namespace.as_ref() {
+        if !dependents.contains_key(ns) {
+            dependents.insert(ns.clone(), namespace_dependents(env, ns)?);
+        }
+        print_dependents(
+            &dependents[ns],
+            obj.type_str(),
+            obj.name(),
+            1,
+            &mut HashSet::new(),
+            writer,
+        );
+    }
+    Ok(())
+}
+
+// Ask the user to confirm action. In a protected context they have to type the context name,
+// otherwise y/N is enough
+fn confirm(env: &Env, action: &str, writer: &mut ClickWriter) -> bool {
+    if env.is_protected() {
+        return env.confirm_protected(action, writer);
+    }
+    clickwrite!(writer, "{} [y/N]? ", uppercase_first(action));
     io::stdout().flush().expect("Could not flush stdout");
     let mut conf = String::new();
     if io::stdin().read_line(&mut conf).is_ok() {
-        if conf.trim() == "y" || conf.trim() == "yes" {
-            delete_obj(env, writer, obj, options)?;
-        } else {
-            clickwriteln!(writer, "Not deleting");
-        }
+        conf.trim() == "y" || conf.trim() == "yes"
     } else {
         writeln!(stderr(), "Could not read response, not deleting.").unwrap_or(());
+        false
+    }
+}
+
+// Delete obj, then if wait is Some, wait up to that long for it to actually be gone
+fn delete_and_wait(
+    env: &Env,
+    writer: &mut ClickWriter,
+    obj: &KObj,
+    options: DeleteOptional,
+    wait: Option<Duration>,
+) -> Result<(), ClickError> {
+    delete_obj(env, writer, obj, options)?;
+    match wait {
+        Some(timeout) => wait_for_deletion(env, obj, timeout, writer),
+        None => Ok(()),
+    }
+}
+
+fn confirm_delete(
+    env: &Env,
+    obj: &KObj,
+    options: DeleteOptional,
+    wait: Option<Duration>,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let action = format!("delete {} {}", obj.type_str(), obj.name());
+    env.ensure_writable(&action)?;
+    if confirm(env, &action, writer) {
+        delete_and_wait(env, writer, obj, options, wait)?;
+    } else {
+        clickwriteln!(writer, "Not deleting");
+    }
+    Ok(())
+}
+
+// Show every object in range and ask once before deleting them all. Used in protected contexts,
+// where typing the context name for every object would be too much
+fn confirm_delete_range(
+    env: &Env,
+    range: &[KObj],
+    options: DeleteOptional,
+    wait: Option<Duration>,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let action = format!("delete these {} objects", range.len());
+    env.ensure_writable(&action)?;
+    clickwriteln!(writer, "About to delete {} objects:", range.len());
+    for obj in range.iter() {
+        match obj.namespace {
+            Some(ref ns) => {
+                clickwriteln!(
+                    writer,
+                    "  {} {} (namespace {})",
+                    obj.type_str(),
+                    obj.name(),
+                    ns
+                )
+            }
+            None => clickwriteln!(writer, "  {} {}", obj.type_str(), obj.name()),
+        }
+    }
+    if !confirm(env, &action, writer) {
+        clickwriteln!(writer, "Not deleting");
+        return Ok(());
+    }
+    env.apply_to_selection(
+        writer,
+        Some(&env.click_config.range_separator),
+        |obj, writer| delete_and_wait(env, writer, obj, options, wait),
+    )
+}
+
+// Poll until obj is gone. If it's still there after timeout, explain what's probably holding it up
+fn wait_for_deletion(
+    env: &Env,
+    obj: &KObj,
+    timeout: Duration,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let url = obj_url(env, obj)?;
+    clickwriteln!(
+        writer,
+        "Waiting up to {} for {} {} to be gone (Ctrl-C to stop waiting)",
+        format_duration(
+            chrono::Duration::from_std(timeout).unwrap_or_else(|_| chrono::Duration::max_value())
+        ),
+        obj.type_str(),
+        obj.name()
+    );
+    let start = Instant::now();
+    env.ctrlcbool.store(false, Ordering::SeqCst);
+    let last = loop {
+        let value = match read_obj_value(env, &url)? {
+            Some(value) => value,
+            None => {
+                clickwriteln!(writer, "{} {} is gone", obj.type_str(), obj.name());
+                return Ok(());
+            }
+        };
+        if start.elapsed() >= timeout || env.ctrlcbool.load(Ordering::SeqCst) {
+            break value;
+        }
+        thread::sleep(WAIT_POLL_INTERVAL.min(timeout.saturating_sub(start.elapsed())));
+    };
+    env.ctrlcbool.store(false, Ordering::SeqCst);
+    clickwriteln!(
+        writer,
+        "{} {} still exists after {}",
+        obj.type_str(),
+        obj.name(),
+        format_duration(
+            chrono::Duration::from_std(start.elapsed())
+                .unwrap_or_else(|_| chrono::Duration::max_value())
+        )
+    );
+    diagnose_stuck_deletion(env, obj, &last, writer)?;
+    Err(ClickError::CommandError(format!(
+        "{} {} was not deleted in time",
+        obj.type_str(),
+        obj.name()
+    )))
+}
+
+// Get the finalizers of an object read as json
+fn finalizers_of(value: &serde_json::Value) -> Vec<String> {
+    strings_at("/metadata/finalizers", value)
+}
+
+// Get the spec.finalizers of a namespace read as json. These are removed by the namespace
+// controller once everything in the namespace is gone, not by removing metadata.finalizers
+fn namespace_finalizers_of(obj: &KObj, value: &serde_json::Value) -> Vec<String> {
+    match obj.typ {
+        ObjType::Namespace => strings_at("/spec/finalizers", value),
+        _ => vec![],
+    }
+}
+
+// Get the array of strings at pointer in value
+fn strings_at(pointer: &str, value: &serde_json::Value) -> Vec<String> {
+    value
+        .pointer(pointer)
+        .and_then(|f| f.as_array())
+        .map(|f| {
+            f.iter()
+                .filter_map(|f| f.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// If the node is unreachable or not ready, return how it's unhealthy
+fn node_problem(env: &Env, node_name: &str) -> Result<Option<String>, ClickError> {
+    let (request, _) = api::Node::read_node(node_name, Default::default())?;
+    let node = match env.run_on_context(|c| c.read(env.get_impersonate_user(), request))? {
+        api::ReadNodeResponse::Ok(node) => node,
+        _ => return Ok(Some("missing".to_string())),
+    };
+    let unreachable = node
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.taints.as_ref())
+        .map(|taints| {
+            taints
+                .iter()
+                .any(|t| t.key == "node.kubernetes.io/unreachable")
+        })
+        .unwrap_or(false);
+    if unreachable {
+        return Ok(Some("unreachable".to_string()));
+    }
+    let ready = node
+        .status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .and_then(|conditions| conditions.iter().find(|c| c.type_ == "Ready"))
+        .map(|c| c.status.as_str());
+    match ready {
+        Some("True") => Ok(None),
+        Some(status) => Ok(Some(format!("not ready (Ready is {status})"))),
+        None => Ok(Some("not reporting if it's ready".to_string())),
+    }
+}
+
+// Explain the likely reasons obj, whose current state is value, hasn't been deleted: finalizers
+// that haven't been removed, dependents that foreground deletion is waiting for, or a pod on a node
+// that can't confirm it has stopped
+fn diagnose_stuck_deletion(
+    env: &Env,
+    obj: &KObj,
+    value: &serde_json::Value,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    if value.pointer("/metadata/deletionTimestamp").is_none() {
+        clickwriteln!(
+            writer,
+            "  It isn't being deleted (it has no deletionTimestamp). Was the delete accepted?"
+        );
+        return Ok(());
+    }
+    clickwriteln!(
+        writer,
+        "  It has been terminating since {}",
+        val_str("/metadata/deletionTimestamp", value, "<unknown>")
+    );
+    let mut explained = false;
+    let finalizers = finalizers_of(value);
+    let others: Vec<&str> = finalizers
+        .iter()
+        .map(|f| f.as_str())
+        .filter(|f| *f != "foregroundDeletion")
+        .collect();
+    if !others.is_empty() {
+        explained = true;
+        clickwriteln!(
+            writer,
+            "  Finalizers still present: {}. The controllers responsible for them must finish \
+             their cleanup and remove them. If those controllers are gone, `remove-finalizers` \
+             clears them",
+            others.join(", ")
+        );
+    }
+    let namespace_finalizers = namespace_finalizers_of(obj, value);
+    if !namespace_finalizers.is_empty() {
+        explained = true;
+        clickwriteln!(
+            writer,
+            "  Namespace finalizers still present (in spec.finalizers): {}. The namespace \
+             controller removes them once everything in the namespace has been deleted. \
+             `namespace-check {}` shows what's left",
+            namespace_finalizers.join(", "),
+            obj.name()
+        );
+    }
+    if finalizers.iter().any(|f| f == "foregroundDeletion") {
+        explained = true;
+        clickwriteln!(
+            writer,
+            "  Foreground deletion is waiting for its dependents to be deleted first:"
+        );
+        if let Some(ns) = obj.namespace.as_ref() {
+            let dependents = namespace_dependents(env, ns)?;
+            let mut seen = HashSet::new();
+            print_dependents(
+                &dependents,
+                obj.type_str(),
+                obj.name(),
+                2,
+                &mut seen,
+                writer,
+            );
+            if seen.is_empty() {
+                clickwriteln!(writer, "    (no dependents found)");
+            }
+        }
+    }
+    if obj.is_pod() {
+        if let Some(node_name) = value.pointer("/spec/nodeName").and_then(|n| n.as_str()) {
+            if let Some(problem) = node_problem(env, node_name)? {
+                explained = true;
+                clickwriteln!(
+                    writer,
+                    "  The pod is on node {node_name}, which is {problem}. The kubelet can't \
+                     confirm the pod has stopped, so it stays Terminating until the node comes \
+                     back, or it's deleted with --force"
+                );
+            }
+        }
+    }
+    if !explained {
+        clickwriteln!(
+            writer,
+            "  No finalizers, blocking dependents or node problems found. It may still be \
+             shutting down within its grace period ({}s)",
+            val_str("/metadata/deletionGracePeriodSeconds", value, "?")
+        );
     }
     Ok(())
 }
 
+// Remove all the finalizers of obj, after listing them and having the user type its name
+fn remove_finalizers(
+    env: &Env,
+    obj: &KObj,
+    dry_run: bool,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let url = obj_url(env, obj)?;
+    let value = read_obj_value(env, &url)?.ok_or_else(|| {
+        ClickError::CommandError(format!("{} {} doesn't exist", obj.type_str(), obj.name()))
+    })?;
+    let finalizers = finalizers_of(&value);
+    let namespace_finalizers = namespace_finalizers_of(obj, &value);
+    if finalizers.is_empty() && !namespace_finalizers.is_empty() {
+        return Err(ClickError::CommandError(format!(
+            "{} {} is only held by its spec.finalizers ({}), which remove-finalizers doesn't \
+             remove. The namespace controller removes them once everything in the namespace has \
+             been deleted, see `namespace-check {}` for what's left",
+            obj.type_str(),
+            obj.name(),
+            namespace_finalizers.join(", "),
+            obj.name()
+        )));
+    }
+    if finalizers.is_empty() {
+        clickwriteln!(
+            writer,
+            "{} {} has no finalizers",
+            obj.type_str(),
+            obj.name()
+        );
+        return Ok(());
+    }
+    clickwriteln!(
+        writer,
+        "{} {} has finalizers: {}",
+        obj.type_str(),
+        obj.name(),
+        finalizers.join(", ")
+    );
+    if !dry_run {
+        let action = format!("remove the finalizers of {} {}", obj.type_str(), obj.name());
+        env.ensure_writable(&action)?;
+        clickwriteln!(
+            writer,
+            "Removing finalizers skips the cleanup they guard, and can leave resources (like \
+             volumes or cloud load balancers) behind."
+        );
+        clickwrite!(writer, "Type the name of the object to {action}: ");
+        io::stdout().flush().expect("Could not flush stdout");
+        let mut conf = String::new();
+        if io::stdin().read_line(&mut conf).is_err() || conf.trim() != obj.name() {
+            clickwriteln!(writer, "Name didn't match, not removing finalizers");
+            return Ok(());
+        }
+        if env.is_protected() && !env.confirm_protected(&action, writer) {
+            clickwriteln!(writer, "Not removing finalizers");
+            return Ok(());
+        }
+    }
+    let url = if dry_run {
+        format!("{url}?dryRun=All")
+    } else {
+        url
+    };
+    let patch = serde_json::json!({ "metadata": { "finalizers": null } });
+    let (request, _) = crd::merge_patch_resource(url, &patch)?;
+    match env.run_on_context::<_, ReadResourceValueResponse>(|c| {
+        c.read(env.get_impersonate_user(), request)
+    })? {
+        ReadResourceValueResponse::Ok(_) if dry_run => {
+            clickwriteln!(writer, "Finalizers would be removed (dry run)");
+            Ok(())
+        }
+        ReadResourceValueResponse::Ok(_) => {
+            clickwriteln!(writer, "Removed finalizers");
+            if !namespace_finalizers.is_empty() {
+                clickwriteln!(
+                    writer,
+                    "It's still held by its spec.finalizers ({}), which the namespace controller \
+                     removes once everything in the namespace has been deleted",
+                    namespace_finalizers.join(", ")
+                );
+            }
+            Ok(())
+        }
+        ReadResourceValueResponse::Other(Ok(Some(status))) => {
+            Err(ClickError::CommandError(format!(
+                "Failed to remove finalizers: {}",
+                val_str("/message", &status, "<No message>")
+            )))
+        }
+        ReadResourceValueResponse::Other(_) => Err(ClickError::CommandError(
+            "Failed to remove finalizers".to_string(),
+        )),
+    }
+}
+
 command!(
     Delete,
     "delete",
@@ -288,6 +1044,56 @@ command!(
                 .takes_value(false)
                 .conflicts_with("grace"),
         )
+        .arg(
+            Arg::new("wait")
+                .short('w')
+                .long("wait")
+                .help(
+                    "Wait until the object is actually gone, and if it isn't gone by --timeout, \
+                     explain what's holding it up",
+                )
+                .takes_value(false)
+                .conflicts_with_all(&["dry_run", "selector"]),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .help("How long --wait waits, e.g. 30s, 5m")
+                .value_parser(humantime::parse_duration)
+                .default_value("60s")
+                .requires("wait"),
+        )
+        .arg(
+            Arg::new("selector")
+                .short('l')
+                .long("selector")
+                .help(
+                    "Instead of the active object, delete everything of --kind matching this \
+                     label selector in the current namespace",
+                )
+                .takes_value(true)
+                .requires("kind"),
+        )
+        .arg(
+            Arg::new("kind")
+                .short('k')
+                .long("kind")
+                .help(
+                    "The kind of object to delete with --selector, like pods or deployments. \
+                     Resources defined by crds work too",
+                )
+                .takes_value(true)
+                .requires("selector"),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .help(
+                    "Ask the server what would be deleted without deleting anything, and show \
+                     the dependents that would be deleted along with each object",
+                )
+                .takes_value(false),
+        )
         .arg(
             Arg::new("force")
                 .long("force")
@@ -302,7 +1108,12 @@ command!(
     },
     vec!["delete"],
     noop_complete!(),
-    no_named_complete!(),
+    [(
+        "kind".to_string(),
+        completer::deletekind_values_completer as fn(&str, &Env) -> Vec<RustlinePair>
+    )]
+    .into_iter()
+    .collect(),
     |matches, env, writer| {
         let grace = if matches.contains_id("force") {
             Some(0)
@@ -321,16 +1132,109 @@ command!(
                 uppercase_first(lower.as_str())
             });
 
+        let dry_run_all = ["All".to_string()];
+        let dry_run = matches.contains_id("dry_run");
         let delete_options: DeleteOptional = DeleteOptional {
             propagation_policy: propagation_policy.as_deref(),
             grace_period_seconds: grace,
+            dry_run: dry_run.then_some(&dry_run_all[..]),
             ..Default::default()
         };
 
+        if let (Some(selector), Some(kind)) = (
+            matches.get_one::<String>("selector"),
+            matches.get_one::<String>("kind"),
+        ) {
+            return delete_collection(env, kind, selector, delete_options, writer);
+        }
+
+        if dry_run {
+            // nothing is changed, so there's nothing to confirm
+            let mut dependents = HashMap::new();
+            return env.apply_to_selection(writer, None, |obj, writer| {
+                dry_run_delete(env, obj, delete_options, &mut dependents, writer)
+            });
+        }
+
+        let wait = if matches.contains_id("wait") {
+            matches.get_one::<Duration>("timeout").copied()
+        } else {
+            None
+        };
+        match env.current_selection() {
+            ObjectSelection::Range(range) if env.is_protected() => {
+                confirm_delete_range(env, range, delete_options, wait, writer)
+            }
+            _ => env.apply_to_selection(
+                writer,
+                Some(&env.click_config.range_separator),
+                |obj, writer| confirm_delete(env, obj, delete_options, wait, writer),
+            ),
+        }
+    }
+);
+
+command!(
+    RemoveFinalizers,
+    "remove-finalizers",
+    "Remove all the finalizers from the active object(s), so a stuck deletion can finish \
+     (will ask you to type the object's name)",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .help("Ask the server to validate the change without saving it")
+                .takes_value(false),
+        )
+        .after_help(
+            "Finalizers are how controllers make sure they clean up after an object before it's \
+             deleted. Only remove them if the controller responsible is gone or broken, since \
+             whatever cleanup it would have done won't happen. `delete --wait` shows which \
+             finalizers are holding up a deletion.\n\n\
+             A namespace's spec.finalizers (like 'kubernetes') aren't removed. The namespace \
+             controller removes them once everything in the namespace is gone, which \
+             `namespace-check` helps with.",
+        )
+    },
+    vec!["remove-finalizers"],
+    noop_complete!(),
+    no_named_complete!(),
+    |matches, env, writer| {
+        let dry_run = matches.contains_id("dry_run");
         env.apply_to_selection(
             writer,
             Some(&env.click_config.range_separator),
-            |obj, writer| confirm_delete(env, obj, delete_options, writer),
+            |obj, writer| remove_finalizers(env, obj, dry_run, writer),
         )
     }
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finalizers_of() {
+        let value = serde_json::json!({
+            "metadata": {"name": "stuck", "finalizers": ["example.com/cleanup"]},
+            "spec": {"finalizers": ["kubernetes"]},
+        });
+        let namespace = KObj {
+            name: "stuck".to_string(),
+            namespace: None,
+            typ: ObjType::Namespace,
+        };
+        let pod = KObj {
+            name: "stuck".to_string(),
+            namespace: Some("default".to_string()),
+            typ: ObjType::Pod { containers: vec![] },
+        };
+        assert_eq!(finalizers_of(&value), vec!["example.com/cleanup"]);
+        assert_eq!(
+            namespace_finalizers_of(&namespace, &value),
+            vec!["kubernetes"]
+        );
+        assert!(namespace_finalizers_of(&pod, &value).is_empty());
+        assert!(finalizers_of(&serde_json::json!({"metadata": {}})).is_empty());
+    }
+}
@@ -27,7 +27,6 @@ use crate::{
 
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::Write;
 
 lazy_static! {
     static ref DEPLOYMENT_EXTRACTORS: HashMap<String, Extractor<apps_api::Deployment>> = {
@@ -135,6 +134,7 @@ fn deployment_uptodate(deployment: &apps_api::Deployment) -> Option<CellSpec<'_>
 list_command!(
     Deployments,
     "deployments",
+    "Deployment",
     "Get deployments (in current namespace if set)",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
@@ -12,13 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use clap::{Arg, Command as ClapCommand};
+use clap::{Arg, ArgMatches, Command as ClapCommand};
 use rustyline::completion::Pair as RustlinePair;
 
 use crate::{
     command::command_def::{exec_match, start_clap, Cmd},
     completer,
     env::Env,
+    error::ClickError,
     output::ClickWriter,
 };
 
@@ -85,3 +86,14 @@ command!(
         )
     }
 );
+
+impl Describe {
+    /// Matches for describing with the default options, for commands (like pick) that show the
+    /// description of an object themselves
+    pub fn default_matches(&self) -> Result<ArgMatches, ClickError> {
+        self.clap
+            .borrow_mut()
+            .try_get_matches_from_mut(Vec::<&str>::new())
+            .map_err(ClickError::Clap)
+    }
+}
@@ -29,6 +29,18 @@ use std::collections::HashMap;
 use std::io::{self, Write};
 use std::process::Command;
 
+// the api path kubectl execs with, for the audit log
+pub fn exec_path(ns: &str, pod: &str, container: &Option<&str>, cmd: &[&str]) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    for arg in cmd.iter() {
+        query.append_pair("command", arg);
+    }
+    if let Some(cont) = container {
+        query.append_pair("container", cont);
+    }
+    format!("/api/v1/namespaces/{ns}/pods/{pod}/exec?{}", query.finish())
+}
+
 #[allow(clippy::too_many_arguments)]
 fn do_exec(
     env: &Env,
@@ -42,6 +54,7 @@ fn do_exec(
     writer: &mut ClickWriter,
 ) -> Result<(), ClickError> {
     let ns = pod.namespace.as_ref().unwrap();
+    let audit_path = exec_path(ns, pod.name(), cont_opt, cmd);
     let kubectl_binary = env
         .click_config
         .kubectl_binary
@@ -80,7 +93,16 @@ fn do_exec(
         targs.push("--");
         targs.extend(cmd.iter());
         clickwriteln!(writer, "Starting on {} in terminal", pod.name());
-        duct::cmd(targs[0], &targs[1..]).start()?;
+        let started = duct::cmd(targs[0], &targs[1..]).start();
+        env.audit(
+            "POST",
+            &audit_path,
+            match started {
+                Ok(_) => "started in terminal".to_string(),
+                Err(ref e) => format!("error: {e}"),
+            },
+        );
+        started?;
         Ok(())
     } else {
         let mut command = Command::new(kubectl_binary);
@@ -102,7 +124,16 @@ fn do_exec(
         } else {
             command.arg("--").args(cmd);
         };
-        match command.status() {
+        let status = command.status();
+        env.audit(
+            "POST",
+            &audit_path,
+            match status {
+                Ok(s) => s.to_string(),
+                Err(ref e) => format!("error: {e}"),
+            },
+        );
+        match status {
             Ok(s) => {
                 if s.success() {
                     Ok(())
@@ -210,6 +241,10 @@ command!(
             Some(&env.click_config.range_separator),
             |obj, writer| {
                 if obj.is_pod() {
+                    if !env.check_mutation(&format!("exec in {}", obj.name()), writer)? {
+                        clickwriteln!(writer, "Not running command in {}", obj.name());
+                        return Ok(());
+                    }
                     do_exec(
                         env,
                         obj,
@@ -27,7 +27,6 @@ use crate::{
 
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::Write;
 
 lazy_static! {
     static ref JOB_EXTRACTORS: HashMap<String, Extractor<batch_api::Job>> = {
@@ -63,7 +62,7 @@ fn job_to_kobj(job: &batch_api::Job) -> KObj {
     KObj {
         name: meta.name.clone().unwrap_or_else(|| "<Unknown>".into()),
         namespace: meta.namespace.clone(),
-        typ: ObjType::StatefulSet,
+        typ: ObjType::Job,
     }
 }
 
@@ -143,6 +142,7 @@ fn job_selector(job: &batch_api::Job) -> Option<CellSpec<'_>> {
 list_command!(
     Jobs,
     "jobs",
+    "Job",
     "Get jobs (in current namespace if set)",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
@@ -23,6 +23,7 @@ use strfmt::strfmt;
 
 use crate::{
     command::command_def::{exec_match, start_clap, Cmd},
+    command::datakeys::{editor_expr, get_editor},
     completer,
     env::Env,
     error::ClickError,
@@ -117,20 +118,7 @@ fn do_logs<'a>(
                 }
             } else if editor {
                 // We're opening in an editor, save to a temp
-                let editor = if let Some(v) = editor_opt {
-                    v.to_owned()
-                } else if let Some(ref e) = env.click_config.editor {
-                    e.clone()
-                } else {
-                    match std::env::var("EDITOR") {
-                        Ok(ed) => ed,
-                        Err(e) => {
-                            return Err(ClickError::CommandError(format!(
-                                "Could not get EDITOR environment variable: {e}"
-                            )));
-                        }
-                    }
-                };
+                let editor = get_editor(env, editor_opt)?;
                 let tmpdir = match env.tempdir {
                     Ok(ref td) => td,
                     Err(ref e) => {
@@ -148,14 +136,7 @@ fn do_logs<'a>(
                 write_logs_to_file(env, &file_path, reader)?;
 
                 clickwriteln!(writer, "Logs downloaded, starting editor");
-                let expr = if editor.contains(' ') {
-                    // split the whitespace
-                    let mut eargs: Vec<&str> = editor.split_whitespace().collect();
-                    eargs.push(file_path.to_str().unwrap());
-                    duct::cmd(eargs[0], &eargs[1..])
-                } else {
-                    cmd!(editor, file_path)
-                };
+                let expr = editor_expr(&editor, &file_path);
                 expr.start()?;
                 Ok(())
             } else {
@@ -16,13 +16,14 @@ use chrono::offset::Utc;
 use chrono::{DateTime, Duration};
 use clap::ArgMatches;
 use k8s_openapi::{
-    apimachinery::pkg::apis::meta::v1::ObjectMeta,
+    apimachinery::pkg::apis::meta::v1::{LabelSelector, ObjectMeta},
     http::{self, Request},
     List, ListOptional, ListResponse, ListableResource, Metadata, RequestError, ResponseBody,
 };
 use regex::Regex;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
+use crate::columns::{user_columns, UserColumn};
 use crate::env::Env;
 use crate::error::ClickError;
 use crate::kobj::KObj;
@@ -38,12 +39,14 @@ use std::io::{stderr, Write};
 pub mod command_def;
 
 pub mod alias; // commands for alias/unalias
+pub mod audit; // command to show the audit log
 pub mod click; // commands internal to click (setting config values, etc)
 pub mod configmaps; // commands relating to configmaps
 pub mod copy; // command to copy files to/from pods
 pub mod crds; // commands to query crd created objects
 pub mod cronjobs; // commands relating to cronjobs
 pub mod daemonsets; // commands for daemonsets
+pub mod datakeys; // helpers for working with configmap and secret keys
 pub mod delete; // command to delete objects
 pub mod deployments; // command to list deployments
 pub mod describe; // the describe command
@@ -58,6 +61,7 @@ pub mod portforwards; // commands for forwarding ports
 pub mod replicasets; // commands relating to relicasets
 pub mod secrets; // commands for secrets
 pub mod services; // commands for services
+pub mod session; // commands to save and load sessions
 pub mod statefulsets; // commands for statefulsets
 pub mod storage; // commands relating to storage objects (like storageclass)
 pub mod volumes; // commands relating to volumes
@@ -83,7 +87,7 @@ pub fn run_list_command<T, F>(
     matches: ArgMatches,
     env: &mut Env,
     writer: &mut ClickWriter,
-    mut cols: Vec<&str>,
+    cols: Vec<&str>,
     request: Request<Vec<u8>>,
     col_map: &[(&'static str, &'static str)],
     extra_col_map: Option<&[(&'static str, &'static str)]>,
@@ -91,9 +95,51 @@ pub fn run_list_command<T, F>(
     get_kobj: F,
 ) -> Result<(), ClickError>
 where
-    T: ListableResource + Metadata<Ty = ObjectMeta> + for<'de> Deserialize<'de> + Debug,
+    T: ListableResource + Metadata<Ty = ObjectMeta> + for<'de> Deserialize<'de> + Serialize + Debug,
+    F: Fn(&T) -> KObj,
+{
+    let list_res =
+        env.run_on_context::<_, List<T>>(|c| c.execute_list(env.get_impersonate_user(), request));
+    if list_res.is_err() {
+        env.clear_last_objs();
+    }
+    let list = list_res?;
+    print_list_with_matches(
+        matches,
+        env,
+        writer,
+        cols,
+        list,
+        col_map,
+        extra_col_map,
+        extractors,
+        get_kobj,
+    )
+}
+
+/// Print an already fetched list, honoring the standard --regex, --show, --sort and --reverse
+/// arguments in `matches`. This is the second half of `run_list_command`, and is useful for
+/// commands that need to build their list from more than one request. Any columns defined for T
+/// in the click config can be shown and sorted by as well.
+#[allow(clippy::too_many_arguments)]
+pub fn print_list_with_matches<T, F>(
+    matches: ArgMatches,
+    env: &mut Env,
+    writer: &mut ClickWriter,
+    cols: Vec<&str>,
+    list: List<T>,
+    col_map: &[(&'static str, &'static str)],
+    extra_col_map: Option<&[(&'static str, &'static str)]>,
+    extractors: Option<&HashMap<String, Extractor<T>>>,
+    get_kobj: F,
+) -> Result<(), ClickError>
+where
+    T: ListableResource + Metadata<Ty = ObjectMeta> + Serialize,
     F: Fn(&T) -> KObj,
 {
+    let user_cols = user_columns(env, T::KIND, T::URL_PATH_SEGMENT)?;
+    // rebind so cols can also hold the names of user_cols
+    let mut cols = cols;
     let regex = match crate::table::get_regex(&matches) {
         Ok(r) => r,
         Err(s) => {
@@ -102,40 +148,57 @@ where
         }
     };
 
-    let list_res =
-        env.run_on_context::<_, List<T>>(|c| c.execute_list(env.get_impersonate_user(), request));
-    if list_res.is_err() {
-        env.clear_last_objs();
-    }
-    let list = list_res?;
     let mut flags: Vec<&str> = match matches.try_get_many::<String>("show") {
         Ok(Some(v)) => v.map(|s| s.as_str()).collect(),
         _ => vec![],
     };
 
-    let sort = matches
-        .get_one::<String>("sort")
-        .map(|s| s.as_str())
-        .map(|s| {
+    // clap accepts any column name, since it doesn't know about user columns, so check them here
+    let is_user_col = |flag: &str| user_cols.iter().any(|c| c.name.eq_ignore_ascii_case(flag));
+    let extra_flags: Vec<&str> = extra_col_map
+        .unwrap_or_default()
+        .iter()
+        .map(|(flag, _)| *flag)
+        .collect();
+    if let Some(flag) = flags.iter().find(|flag| {
+        !flag.eq_ignore_ascii_case("all")
+            && !extra_flags.iter().any(|f| f.eq_ignore_ascii_case(flag))
+            && !is_user_col(flag)
+    }) {
+        return Err(ClickError::CommandError(format!(
+            "Can't show column '{flag}', possible values are: {}",
+            possible_cols(&extra_flags, &user_cols).join(", ")
+        )));
+    }
+
+    let sort = match matches.get_one::<String>("sort") {
+        Some(s) => {
             let colname = s.to_lowercase();
             if let Some(col) = mapped_val(&colname, col_map) {
-                command_def::SortCol(col)
-            } else if let Some(ecm) = extra_col_map {
-                let mut func = None;
-                for (flag, col) in ecm.iter() {
-                    if flag.eq(&colname) {
-                        flags.push(flag);
-                        func = Some(command_def::SortCol(col));
-                    }
-                }
-                match func {
-                    Some(f) => f,
-                    None => panic!("Shouldn't be allowed to ask to sort by: {colname}"),
-                }
+                Some(command_def::SortCol(col.to_string()))
+            } else if let Some((flag, col)) = extra_col_map
+                .unwrap_or_default()
+                .iter()
+                .find(|(flag, _)| flag.eq(&colname))
+            {
+                flags.push(flag);
+                Some(command_def::SortCol(col.to_string()))
+            } else if let Some(user_col) =
+                user_cols.iter().find(|c| c.name.to_lowercase() == colname)
+            {
+                flags.push(&user_col.name);
+                Some(command_def::SortCol(user_col.name.clone()))
             } else {
-                panic!("Shouldn't be allowed to ask to sort by: {colname}");
+                let mut sortable: Vec<&str> = col_map.iter().map(|(flag, _)| *flag).collect();
+                sortable.extend(extra_flags.iter());
+                return Err(ClickError::CommandError(format!(
+                    "Can't sort by '{s}', possible values are: {}",
+                    possible_cols(&sortable, &user_cols).join(", ")
+                )));
             }
-        });
+        }
+        None => None,
+    };
 
     if let Some(ecm) = extra_col_map {
         // if we're not in a namespace, we want to add a namespace col if it's in extra_col_map
@@ -150,6 +213,13 @@ where
             ecm,
         );
     }
+    let show_all = flags.iter().any(|f| f.eq_ignore_ascii_case("all"));
+    for user_col in user_cols.iter() {
+        let shown = show_all || flags.iter().any(|f| f.eq_ignore_ascii_case(&user_col.name));
+        if shown && !cols.contains(&user_col.name.as_str()) {
+            cols.push(&user_col.name);
+        }
+    }
 
     handle_list_result(
         env,
@@ -157,6 +227,7 @@ where
         cols,
         list,
         extractors,
+        &user_cols,
         regex,
         sort,
         matches.contains_id("reverse"),
@@ -164,6 +235,14 @@ where
     )
 }
 
+fn possible_cols(flags: &[&str], user_cols: &[UserColumn]) -> Vec<String> {
+    flags
+        .iter()
+        .map(|f| f.to_string())
+        .chain(user_cols.iter().map(|c| c.name.clone()))
+        .collect()
+}
+
 /// Uppercase the first letter of the given str
 pub fn uppercase_first(s: &str) -> String {
     let mut cs = s.chars();
@@ -191,16 +270,17 @@ pub fn handle_list_result<'a, T, F>(
     cols: Vec<&str>,
     list: List<T>,
     extractors: Option<&HashMap<String, Extractor<T>>>,
+    user_cols: &[UserColumn],
     regex: Option<Regex>,
     sort: Option<command_def::SortCol>,
     reverse: bool,
     get_kobj: F,
 ) -> Result<(), ClickError>
 where
-    T: 'a + ListableResource + Metadata<Ty = ObjectMeta>,
+    T: 'a + ListableResource + Metadata<Ty = ObjectMeta> + Serialize,
     F: Fn(&T) -> KObj,
 {
-    let mut specs = build_specs(&cols, &list, extractors, true, regex, get_kobj);
+    let mut specs = build_specs(&cols, &list, extractors, user_cols, true, regex, get_kobj);
 
     let mut titles: Vec<&str> = vec!["####"];
     titles.reserve(cols.len());
@@ -209,7 +289,7 @@ where
     }
 
     if let Some(command_def::SortCol(colname)) = sort {
-        let index = cols.iter().position(|&c| c == colname);
+        let index = cols.iter().position(|&c| c == colname.as_str());
         match index {
             Some(index) => {
                 let idx = index + 1; // +1 for #### col
@@ -239,7 +319,8 @@ where
 /* Build row specs and a kobj vec from data returned from k8s.
  *
  * cols is a list of names of columns to build. "Name" * and "Age" are handled, other names need to
- * be in 'extractors', and the extractor for the specified name will be used.
+ * be in 'extractors', and the extractor for the specified name will be used, or be one of
+ * 'user_cols', which are extracted from the item serialized to json.
  *
  * include_index = true will put an index (numbered) column as the first item in the row
  *
@@ -255,16 +336,23 @@ pub fn build_specs<'a, T, F>(
     cols: &[&str],
     list: &'a List<T>,
     extractors: Option<&HashMap<String, Extractor<T>>>,
+    user_cols: &[UserColumn],
     include_index: bool,
     regex: Option<Regex>,
     get_kobj: F,
 ) -> Vec<(KObj, RowSpec<'a>)>
 where
-    T: 'a + ListableResource + Metadata<Ty = ObjectMeta>,
+    T: 'a + ListableResource + Metadata<Ty = ObjectMeta> + Serialize,
     F: Fn(&T) -> KObj,
 {
+    let need_json = user_cols.iter().any(|uc| cols.contains(&uc.name.as_str()));
     let mut ret = vec![];
     for item in list.items.iter() {
+        let value = if need_json {
+            serde_json::to_value(item).ok()
+        } else {
+            None
+        };
         let mut row: Vec<CellSpec> = if include_index {
             vec![CellSpec::new_index()]
         } else {
@@ -276,12 +364,15 @@ where
                 "Labels" => row.push(extract_labels(item).into()),
                 "Name" => row.push(extract_name(item).into()),
                 "Namespace" => row.push(extract_namespace(item).into()),
-                _ => match extractors {
-                    Some(extractors) => match extractors.get(*col) {
-                        Some(extractor) => row.push(extractor(item).into()),
+                _ => match extractors.and_then(|extractors| extractors.get(*col)) {
+                    Some(extractor) => row.push(extractor(item).into()),
+                    None => match user_cols.iter().find(|uc| uc.name == *col) {
+                        Some(user_col) => row.push(match value {
+                            Some(ref value) => user_col.cell(value),
+                            None => None::<String>.into(),
+                        }),
                         None => panic!("Can't extract"),
                     },
-                    None => panic!("Can't extract"),
                 },
             }
         }
@@ -404,6 +495,39 @@ where
     buf
 }
 
+/// Build a label selector string (as accepted by the `labelSelector` query parameter) out of a
+/// LabelSelector. Returns None if the selector has no requirements, since an empty selector
+/// matches everything, which is almost never what the caller wants.
+pub fn label_selector_string(selector: &LabelSelector) -> Option<String> {
+    let mut reqs = vec![];
+    if let Some(match_labels) = selector.match_labels.as_ref() {
+        for (key, val) in match_labels.iter() {
+            reqs.push(format!("{key}={val}"));
+        }
+    }
+    if let Some(match_expressions) = selector.match_expressions.as_ref() {
+        for expr in match_expressions.iter() {
+            let values = expr
+                .values
+                .as_ref()
+                .map(|v| v.join(","))
+                .unwrap_or_default();
+            match expr.operator.as_str() {
+                "In" => reqs.push(format!("{} in ({})", expr.key, values)),
+                "NotIn" => reqs.push(format!("{} notin ({})", expr.key, values)),
+                "Exists" => reqs.push(expr.key.clone()),
+                "DoesNotExist" => reqs.push(format!("!{}", expr.key)),
+                _ => {} // unknown operator, skip it
+            }
+        }
+    }
+    if reqs.is_empty() {
+        None
+    } else {
+        Some(reqs.join(","))
+    }
+}
+
 // utils for getting custom requests
 
 /// Get a read request for a custom url
@@ -445,3 +569,53 @@ pub fn get_list_request_for_url<T: ListableResource + for<'de> serde::Deserializ
         Err(err) => Err(RequestError::Http(err)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelectorRequirement;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_label_selector_string() {
+        let empty = LabelSelector::default();
+        assert_eq!(label_selector_string(&empty), None);
+
+        let mut labels = BTreeMap::new();
+        labels.insert("app".to_string(), "nginx".to_string());
+        labels.insert("tier".to_string(), "web".to_string());
+        let sel = LabelSelector {
+            match_labels: Some(labels),
+            match_expressions: None,
+        };
+        assert_eq!(
+            label_selector_string(&sel),
+            Some("app=nginx,tier=web".to_string())
+        );
+
+        let sel = LabelSelector {
+            match_labels: None,
+            match_expressions: Some(vec![
+                LabelSelectorRequirement {
+                    key: "env".to_string(),
+                    operator: "In".to_string(),
+                    values: Some(vec!["dev".to_string(), "prod".to_string()]),
+                },
+                LabelSelectorRequirement {
+                    key: "canary".to_string(),
+                    operator: "DoesNotExist".to_string(),
+                    values: None,
+                },
+                LabelSelectorRequirement {
+                    key: "track".to_string(),
+                    operator: "Exists".to_string(),
+                    values: None,
+                },
+            ]),
+        };
+        assert_eq!(
+            label_selector_string(&sel),
+            Some("env in (dev,prod),!canary,track".to_string())
+        );
+    }
+}
@@ -18,16 +18,19 @@ use rustyline::completion::Pair as RustlinePair;
 
 use crate::{
     command::command_def::{exec_match, sort_arg, start_clap, Cmd},
-    command::{run_list_command, Extractor},
+    command::{format_duration, run_list_command, time_since, Extractor},
     completer,
+    crd::{self, ApiResourceDesc, ReadResourceValueResponse},
     env::Env,
+    error::{ClickError, ErrorClass},
     kobj::{KObj, ObjType},
     output::ClickWriter,
     table::CellSpec,
+    values::{val_str, val_str_opt},
 };
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 
 lazy_static! {
@@ -121,3 +124,244 @@ command!(
         )
     }
 );
+
+// The type to give the kobj for an object of the kind desc describes, so the built in kinds get
+// their own commands, and everything else is handled like a crd
+fn resource_obj_type(desc: &ApiResourceDesc, value: &serde_json::Value) -> ObjType {
+    match (desc.group_version.as_str(), desc.kind.as_str()) {
+        ("v1", "Pod") => ObjType::Pod {
+            containers: value
+                .pointer("/spec/containers")
+                .and_then(|c| c.as_array())
+                .map(|containers| {
+                    containers
+                        .iter()
+                        .filter_map(|c| val_str_opt("/name", c))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        },
+        ("v1", "Service") => ObjType::Service,
+        ("v1", "ConfigMap") => ObjType::ConfigMap,
+        ("v1", "Secret") => ObjType::Secret,
+        ("apps/v1", "Deployment") => ObjType::Deployment,
+        ("apps/v1", "ReplicaSet") => ObjType::ReplicaSet,
+        ("apps/v1", "StatefulSet") => ObjType::StatefulSet,
+        ("apps/v1", "DaemonSet") => ObjType::DaemonSet,
+        ("batch/v1", "CronJob") => ObjType::CronJob,
+        ("batch/v1", "Job") => ObjType::Job,
+        #[cfg(feature = "argorollouts")]
+        ("argoproj.io/v1alpha1", "Rollout") => ObjType::Rollout,
+        _ => ObjType::Crd {
+            _type: desc.name.clone(),
+            group_version: desc.group_version.clone(),
+        },
+    }
+}
+
+// Print the phase, finalizers and status conditions of the namespace, which say why the namespace
+// controller hasn't been able to finish deleting it
+fn print_namespace_status(namespace: &api::Namespace, env: &Env, writer: &mut ClickWriter) -> bool {
+    let status = namespace.status.clone().unwrap_or_default();
+    let phase = status.phase.as_deref().unwrap_or("Unknown");
+    clickwriteln!(
+        writer,
+        "Namespace {} is {phase}",
+        namespace.metadata.name.as_deref().unwrap_or("<Unknown>")
+    );
+    if let Some(deleted) = namespace.metadata.deletion_timestamp.as_ref() {
+        clickwriteln!(
+            writer,
+            "  Deleted {} ago",
+            format_duration(time_since(deleted.0))
+        );
+    }
+    let finalizers = namespace
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.finalizers.as_ref())
+        .filter(|f| !f.is_empty());
+    if let Some(finalizers) = finalizers {
+        clickwriteln!(writer, "  Finalizers: {}", finalizers.join(", "));
+    }
+    let conditions = status.conditions.unwrap_or_default();
+    if !conditions.is_empty() {
+        let rows = conditions
+            .into_iter()
+            .map(|cond| {
+                // for namespaces, a condition being True means something is wrong
+                let color = if cond.status == "True" {
+                    env.styles.danger_color()
+                } else {
+                    env.styles.success_color()
+                };
+                vec![
+                    cond.type_.into(),
+                    CellSpec::with_colors(cond.status.into(), color.map(Into::into), None),
+                    cond.reason.unwrap_or_default().into(),
+                    cond.message.unwrap_or_default().into(),
+                ]
+            })
+            .collect();
+        crate::table::print_table(
+            vec!["Condition", "Status", "Reason", "Message"],
+            rows,
+            env,
+            writer,
+        );
+    }
+    phase == "Terminating"
+}
+
+// List every object of the kind desc describes that's left in namespace
+fn list_remaining(
+    env: &Env,
+    desc: &ApiResourceDesc,
+    namespace: &str,
+) -> Result<Vec<serde_json::Value>, ClickError> {
+    let (request, _) = crd::read_resource_url(desc.url(Some(namespace)))?;
+    match env.run_on_context::<_, ReadResourceValueResponse>(|c| {
+        c.read(env.get_impersonate_user(), request)
+    })? {
+        ReadResourceValueResponse::Ok(mut list) => Ok(match list["items"].take() {
+            serde_json::Value::Array(items) => items,
+            _ => vec![],
+        }),
+        ReadResourceValueResponse::Other(Ok(Some(status))) => {
+            Err(ClickError::CommandError(format!(
+                "Could not list {}: {}",
+                desc.name,
+                val_str("/message", &status, "<No message>")
+            )))
+        }
+        ReadResourceValueResponse::Other(_) => Err(ClickError::CommandError(format!(
+            "Could not list {}",
+            desc.name
+        ))),
+    }
+}
+
+command!(
+    NamespaceCheck,
+    "namespace-check",
+    "Check why the active namespace(s) might be stuck terminating, and select what's left in them",
+    |clap: ClapCommand<'static>| {
+        clap.after_help(
+            "Prints the namespace's status conditions, which report things like \
+NamespaceDeletionContentFailure or NamespaceFinalizersRemaining, then lists every object of every \
+namespaced kind (found via discovery) still in the namespace, with its finalizers. The objects \
+listed can then be selected by number, so `delete --wait` or `remove-finalizers` can be run on \
+them.",
+        )
+    },
+    vec!["namespace-check", "nscheck"],
+    noop_complete!(),
+    no_named_complete!(),
+    |_matches, env, writer| {
+        let mut namespaces = vec![];
+        env.apply_to_selection(writer, None, |obj, _| {
+            if obj.is(ObjType::Namespace) {
+                namespaces.push(obj.name().to_string());
+                Ok(())
+            } else {
+                Err(ClickError::CommandError(format!(
+                    "namespace-check only works on namespaces, not {}",
+                    obj.type_str()
+                )))
+            }
+        })?;
+        let resources = crd::get_namespaced_resources(env)?;
+        let mut kobjs = vec![];
+        let mut rows = vec![];
+        // some objects are served by more than one api group, like events from both v1 and
+        // events.k8s.io, so only list each one once
+        let mut seen = HashSet::new();
+        for name in namespaces.iter() {
+            let (request, _) = api::Namespace::read_namespace(name, Default::default())?;
+            let namespace =
+                match env.run_on_context(|c| c.read(env.get_impersonate_user(), request)) {
+                    Ok(api::ReadNamespaceResponse::Ok(namespace)) => namespace,
+                    Err(e) if e.class() != ErrorClass::NotFound => return Err(e),
+                    _ => {
+                        clickwriteln!(writer, "Namespace {name} doesn't exist");
+                        continue;
+                    }
+                };
+            if !print_namespace_status(&namespace, env, writer) {
+                clickwriteln!(writer, "  It isn't terminating");
+            }
+            for desc in resources.iter() {
+                let items = match list_remaining(env, desc, name) {
+                    Ok(items) => items,
+                    Err(e) => {
+                        clickwriteln!(writer, "  {e}");
+                        continue;
+                    }
+                };
+                for item in items.iter() {
+                    if let Some(uid) = val_str_opt("/metadata/uid", item) {
+                        if !seen.insert(uid) {
+                            continue;
+                        }
+                    }
+                    let kobj = match KObj::from_value(item, resource_obj_type(desc, item)) {
+                        Some(kobj) => kobj,
+                        None => continue,
+                    };
+                    let finalizers = item
+                        .pointer("/metadata/finalizers")
+                        .and_then(|f| f.as_array())
+                        .map(|f| {
+                            f.iter()
+                                .filter_map(|f| f.as_str())
+                                .collect::<Vec<&str>>()
+                                .join(", ")
+                        })
+                        .unwrap_or_default();
+                    let finalizers_color = if finalizers.is_empty() {
+                        None
+                    } else {
+                        env.styles.warning_color()
+                    };
+                    rows.push(vec![
+                        CellSpec::new_index(),
+                        name.clone().into(),
+                        format!("{} ({})", desc.kind, desc.group_version).into(),
+                        kobj.name().to_string().into(),
+                        val_str_opt("/metadata/deletionTimestamp", item)
+                            .unwrap_or_default()
+                            .into(),
+                        CellSpec::with_colors(
+                            finalizers.into(),
+                            finalizers_color.map(Into::into),
+                            None,
+                        ),
+                    ]);
+                    kobjs.push(kobj);
+                }
+            }
+        }
+        if kobjs.is_empty() {
+            clickwriteln!(writer, "No objects left");
+            // so the objects from an earlier check can't be selected by number anymore
+            env.clear_last_objs();
+            return Ok(());
+        }
+        clickwriteln!(writer, "Objects left:");
+        let table = crate::table::print_table(
+            vec![
+                "####",
+                "Namespace",
+                "Kind",
+                "Name",
+                "Deleting Since",
+                "Finalizers",
+            ],
+            rows,
+            env,
+            writer,
+        );
+        env.set_last_objs(kobjs, Some(table));
+        Ok(())
+    }
+);
@@ -28,7 +28,6 @@ use crate::{
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::Write;
 
 lazy_static! {
     static ref NODE_EXTRACTORS: HashMap<String, Extractor<api::Node>> = {
@@ -185,6 +184,7 @@ fn node_version(node: &api::Node) -> Option<CellSpec<'_>> {
 list_command!(
     Nodes,
     "nodes",
+    "Node",
     "Get nodes in the current context",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
@@ -13,12 +13,12 @@
 // limitations under the License.
 
 use clap::{Arg, Command as ClapCommand};
-use k8s_openapi::api::core::v1 as api;
-use k8s_openapi::ListOptional;
+use k8s_openapi::api::{apps::v1 as api_apps, batch::v1 as api_batch, core::v1 as api};
+use k8s_openapi::{List, ListOptional};
 
 use crate::{
     command::command_def::{exec_match, show_arg, sort_arg, start_clap, Cmd},
-    command::{run_list_command, Extractor},
+    command::{label_selector_string, print_list_with_matches, run_list_command, Extractor},
     completer,
     env::{Env, ObjectSelection},
     error::ClickError,
@@ -28,7 +28,7 @@ use crate::{
     table::{CellSpec, ColorType},
 };
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use std::{cell::RefCell, collections::BTreeMap};
 
@@ -234,11 +234,18 @@ fn pod_status(pod: &api::Pod) -> Option<CellSpec<'_>> {
 list_command!(
     Pods,
     "pods",
+    "Pod",
     "Get pods (in current namespace if set)",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
     |clap: ClapCommand<'static>| {
         clap.arg(
+            Arg::new("name")
+                .help("Only get the pod with this name")
+                .required(false)
+                .index(1),
+        )
+        .arg(
             Arg::new("labels")
                 .short('L')
                 .long("labels")
@@ -277,27 +284,33 @@ list_command!(
         )
     },
     vec!["pods"],
-    noop_complete!(),
+    vec![&completer::pod_completer],
     [].into_iter(),
     |matches, env, writer| {
         let mut opts: ListOptional = ListOptional::<'_> {
             label_selector: matches.get_one::<String>("label").map(|s| s.as_str()),
             ..Default::default()
         };
-        let mut field_sel = None;
+        let mut field_sels = vec![];
         match matches.get_one::<String>("node").map(|s| s.as_str()) {
             Some(nodeval) => {
-                field_sel = Some(format!("spec.nodeName={nodeval}"));
+                field_sels.push(format!("spec.nodeName={nodeval}"));
             }
             None => {
                 if let ObjectSelection::Single(obj) = env.current_selection() {
                     if obj.is(ObjType::Node) {
-                        field_sel = Some(format!("spec.nodeName={}", obj.name()));
+                        field_sels.push(format!("spec.nodeName={}", obj.name()));
                     }
                 }
             }
         }
-        opts.field_selector = field_sel.as_deref();
+        if let Some(name) = matches.get_one::<String>("name") {
+            field_sels.push(format!("metadata.name={name}"));
+        }
+        let field_sel = field_sels.join(",");
+        if !field_sel.is_empty() {
+            opts.field_selector = Some(&field_sel);
+        }
 
         let (request, _response_body) = match &env.namespace {
             Some(ns) => api::Pod::list_namespaced_pod(ns, opts)?,
@@ -320,6 +333,207 @@ list_command!(
     }
 );
 
+// get the label selector that an object uses to pick its pods
+fn pod_selector_for(obj: &KObj, env: &Env) -> Result<String, ClickError> {
+    let ns = obj
+        .namespace
+        .as_deref()
+        .ok_or_else(|| ClickError::CommandError(format!("{} has no namespace", obj.name())))?;
+    // each read_x call returns a different type, so use a macro to read the object and pull out
+    // its selector
+    macro_rules! read_selector {
+        ($read_func:expr, $resp_typ:ty, $resp_ok:path, $get_sel:expr) => {{
+            let (request, _) = $read_func(obj.name(), ns, Default::default())?;
+            match env
+                .run_on_context(|c| c.read::<$resp_typ>(env.get_impersonate_user(), request))?
+            {
+                $resp_ok(t) => $get_sel(t),
+                _ => {
+                    return Err(ClickError::CommandError(format!(
+                        "Could not read {} {}",
+                        obj.type_str(),
+                        obj.name()
+                    )))
+                }
+            }
+        }};
+    }
+    let selector = match obj.typ {
+        ObjType::DaemonSet => read_selector!(
+            api_apps::DaemonSet::read_namespaced_daemon_set,
+            api_apps::ReadNamespacedDaemonSetResponse,
+            api_apps::ReadNamespacedDaemonSetResponse::Ok,
+            |ds: api_apps::DaemonSet| ds
+                .spec
+                .and_then(|spec| label_selector_string(&spec.selector))
+        ),
+        ObjType::Deployment => read_selector!(
+            api_apps::Deployment::read_namespaced_deployment,
+            api_apps::ReadNamespacedDeploymentResponse,
+            api_apps::ReadNamespacedDeploymentResponse::Ok,
+            |dep: api_apps::Deployment| dep
+                .spec
+                .and_then(|spec| label_selector_string(&spec.selector))
+        ),
+        ObjType::Job => read_selector!(
+            api_batch::Job::read_namespaced_job,
+            api_batch::ReadNamespacedJobResponse,
+            api_batch::ReadNamespacedJobResponse::Ok,
+            |job: api_batch::Job| job
+                .spec
+                .and_then(|spec| spec.selector)
+                .and_then(|sel| label_selector_string(&sel))
+        ),
+        ObjType::ReplicaSet => read_selector!(
+            api_apps::ReplicaSet::read_namespaced_replica_set,
+            api_apps::ReadNamespacedReplicaSetResponse,
+            api_apps::ReadNamespacedReplicaSetResponse::Ok,
+            |rs: api_apps::ReplicaSet| rs
+                .spec
+                .and_then(|spec| label_selector_string(&spec.selector))
+        ),
+        ObjType::Service => read_selector!(
+            api::Service::read_namespaced_service,
+            api::ReadNamespacedServiceResponse,
+            api::ReadNamespacedServiceResponse::Ok,
+            |svc: api::Service| svc
+                .spec
+                .and_then(|spec| spec.selector)
+                .filter(|sel| !sel.is_empty())
+                .map(|sel| {
+                    sel.iter()
+                        .map(|(k, v)| format!("{k}={v}"))
+                        .collect::<Vec<String>>()
+                        .join(",")
+                })
+        ),
+        ObjType::StatefulSet => read_selector!(
+            api_apps::StatefulSet::read_namespaced_stateful_set,
+            api_apps::ReadNamespacedStatefulSetResponse,
+            api_apps::ReadNamespacedStatefulSetResponse::Ok,
+            |ss: api_apps::StatefulSet| ss
+                .spec
+                .and_then(|spec| label_selector_string(&spec.selector))
+        ),
+        _ => {
+            return Err(ClickError::CommandError(format!(
+                "pods-of not supported for {}",
+                obj.type_str()
+            )))
+        }
+    };
+    selector.ok_or_else(|| {
+        ClickError::CommandError(format!(
+            "{} {} has no selector, can't find its pods",
+            obj.type_str(),
+            obj.name()
+        ))
+    })
+}
+
+// get all the pods selected by obj, skipping any that are already in seen
+fn pods_of_obj(
+    obj: &KObj,
+    env: &Env,
+    seen: &mut HashSet<(Option<String>, Option<String>)>,
+    pods: &mut Vec<api::Pod>,
+) -> Result<(), ClickError> {
+    let selector = pod_selector_for(obj, env)?;
+    let opts = ListOptional {
+        label_selector: Some(&selector),
+        ..Default::default()
+    };
+    // safe, pod_selector_for fails if there's no namespace
+    let (request, _) = api::Pod::list_namespaced_pod(obj.namespace.as_ref().unwrap(), opts)?;
+    let list: List<api::Pod> =
+        env.run_on_context(|c| c.execute_list(env.get_impersonate_user(), request))?;
+    for pod in list.items.into_iter() {
+        let key = (pod.metadata.namespace.clone(), pod.metadata.name.clone());
+        if seen.insert(key) {
+            pods.push(pod);
+        }
+    }
+    Ok(())
+}
+
+command!(
+    PodsOf,
+    "pods-of",
+    "Get the pods selected by the active object(s), and select them",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("regex")
+                .short('r')
+                .long("regex")
+                .help("Filter returned value by the specified regex")
+                .takes_value(true),
+        )
+        .arg(show_arg(EXTRA_COL_FLAGS, true))
+        .arg(sort_arg(COL_FLAGS, Some(EXTRA_COL_FLAGS)))
+        .arg(
+            Arg::new("reverse")
+                .short('R')
+                .long("reverse")
+                .help("Reverse the order of the returned list")
+                .takes_value(false),
+        )
+        .after_help(
+            "The active object (or every object in the active range) must be a Deployment, \
+StatefulSet, DaemonSet, ReplicaSet, Job or Service. The pods matching its selector are listed, and \
+then become the active selection, so commands like logs, exec, describe and delete can be run on \
+them directly.
+
+Example:
+  # Select a deployment, then tail the logs of all its pods
+  deployments
+  0
+  pods-of
+  logs -t 10",
+        )
+    },
+    vec!["pods-of", "podsof"],
+    noop_complete!(),
+    // pods-of takes the same sort and show args as pods
+    [
+        (
+            "sort".to_string(),
+            list_sort_completers::Pods as fn(&str, &Env) -> Vec<RustlinePair>
+        ),
+        (
+            "show".to_string(),
+            list_show_completers::Pods as fn(&str, &Env) -> Vec<RustlinePair>
+        ),
+    ]
+    .into_iter()
+    .collect(),
+    |matches, env, writer| {
+        let mut seen = HashSet::new();
+        let mut pods = vec![];
+        env.apply_to_selection(writer, Some(&env.click_config.range_separator), |obj, _| {
+            pods_of_obj(obj, env, &mut seen, &mut pods)
+        })?;
+        let list = List {
+            items: pods,
+            metadata: Default::default(),
+        };
+        let cols: Vec<&str> = COL_MAP.iter().map(|(_, col)| *col).collect();
+        print_list_with_matches(
+            matches,
+            env,
+            writer,
+            cols,
+            list,
+            COL_MAP,
+            Some(EXTRA_COL_MAP),
+            Some(&POD_EXTRACTORS),
+            pod_to_kobj,
+        )?;
+        let objs = env.get_last_objs().cloned().unwrap_or_default();
+        env.select_objs(objs);
+        Ok(())
+    }
+);
+
 // also add a command to print all the containers of a pod
 command!(
     Containers,
@@ -366,12 +580,9 @@ fn print_containers(
         obj.namespace.as_ref().unwrap(),
         Default::default(),
     )?;
-    match env
-        .run_on_context(|c| {
-            c.read::<api::ReadNamespacedPodResponse>(env.get_impersonate_user(), request)
-        })
-        .unwrap()
-    {
+    match env.run_on_context(|c| {
+        c.read::<api::ReadNamespacedPodResponse>(env.get_impersonate_user(), request)
+    })? {
         api::ReadNamespacedPodResponse::Ok(pod) => match pod
             .status
             .and_then(|status| status.container_statuses)
@@ -54,6 +54,106 @@ fn parse_ports(value: &str) -> std::result::Result<String, Error> {
     }
 }
 
+/// Start forwarding ports to the specified pod with kubectl, and add the forward to env
+pub fn start_port_forward(
+    env: &mut Env,
+    pod: String,
+    ns: String,
+    ports: Vec<String>,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let context = if let Some(ref context) = env.context {
+        context.name.clone()
+    } else {
+        return Err(ClickError::CommandError("No active context".to_string()));
+    };
+
+    let kubectl_binary = env
+        .click_config
+        .kubectl_binary
+        .as_deref()
+        .unwrap_or("kubectl");
+    let mut command = Command::new(kubectl_binary);
+    let command = if let Some(user) = env.get_impersonate_user() {
+        command.arg("--as").arg(user)
+    } else {
+        &mut command
+    };
+    command
+        .arg("--namespace")
+        .arg(&ns)
+        .arg("--context")
+        .arg(context)
+        .arg("port-forward")
+        .arg(&pod)
+        .args(&ports);
+    let spawned = command.stdout(Stdio::piped()).spawn();
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    for port in ports.iter() {
+        query.append_pair("ports", port);
+    }
+    env.audit(
+        "POST",
+        &format!(
+            "/api/v1/namespaces/{ns}/pods/{pod}/portforward?{}",
+            query.finish()
+        ),
+        match spawned {
+            Ok(_) => "started".to_string(),
+            Err(ref e) => format!("error: {e}"),
+        },
+    );
+    match spawned {
+        Ok(mut child) => {
+            let mut stdout = child.stdout.take().unwrap();
+            let output = Arc::new(Mutex::new(String::new()));
+            let output_clone = output.clone();
+
+            thread::spawn(move || {
+                let mut buffer = [0; 128];
+                loop {
+                    match stdout.read(&mut buffer[..]) {
+                        Ok(read) => {
+                            if read > 0 {
+                                let readstr = String::from_utf8_lossy(&buffer[0..read]);
+                                let mut res = output_clone.lock().unwrap();
+                                res.push_str(&readstr);
+                            } else {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            write!(stderr(), "Error reading child output: {e}").unwrap_or(());
+                            break;
+                        }
+                    }
+                }
+            });
+
+            clickwriteln!(writer, "Forwarding port(s): {}", ports.join(", "));
+
+            env.add_port_forward(env::PortForward {
+                child,
+                pod,
+                namespace: ns,
+                ports,
+                output,
+            });
+            Ok(())
+        }
+        Err(e) => Err(ClickError::CommandError(match e.kind() {
+            io::ErrorKind::NotFound => {
+                if kubectl_binary.starts_with('/') {
+                    format!("Could not find kubectl binary '{kubectl_binary}'. Does it exist?")
+                } else {
+                    format!("Could not find kubectl binary '{kubectl_binary}'. Is it in your PATH.")
+                }
+            }
+            _ => format!("Couldn't execute kubectl, not forwarding.  Error is: {e}"),
+        })),
+    }
+}
+
 command!(
     PortForward,
     "port-forward",
@@ -99,101 +199,12 @@ Examples:
                     p.name().to_string(),
                     p.namespace.as_ref().unwrap().to_string(),
                 ),
-                None => {
-                    write!(stderr(), "No active pod").unwrap_or(());
-                    return Ok(()); // TODO: Return error
-                }
+                None => return Err(ClickError::CommandError("No active pod".to_string())),
             }
         };
 
-        let context = if let Some(ref context) = env.context {
-            context.name.clone()
-        } else {
-            return Err(ClickError::CommandError("No active context".to_string()));
-        };
-
-        let kubectl_binary = env
-            .click_config
-            .kubectl_binary
-            .as_deref()
-            .unwrap_or("kubectl");
-        let mut command = Command::new(kubectl_binary);
-        let command = if let Some(user) = env.get_impersonate_user() {
-            command.arg("--as").arg(user)
-        } else {
-            &mut command
-        };
-        command
-            .arg("--namespace")
-            .arg(ns)
-            .arg("--context")
-            .arg(context)
-            .arg("port-forward")
-            .arg(&pod)
-            .args(ports.clone());
-        match command.stdout(Stdio::piped()).spawn() {
-            Ok(mut child) => {
-                let mut stdout = child.stdout.take().unwrap();
-                let output = Arc::new(Mutex::new(String::new()));
-                let output_clone = output.clone();
-
-                thread::spawn(move || {
-                    let mut buffer = [0; 128];
-                    loop {
-                        match stdout.read(&mut buffer[..]) {
-                            Ok(read) => {
-                                if read > 0 {
-                                    let readstr = String::from_utf8_lossy(&buffer[0..read]);
-                                    let mut res = output_clone.lock().unwrap();
-                                    res.push_str(&readstr);
-                                } else {
-                                    break;
-                                }
-                            }
-                            Err(e) => {
-                                write!(stderr(), "Error reading child output: {e}").unwrap_or(());
-                                break;
-                            }
-                        }
-                    }
-                });
-
-                let ports: Vec<String> = ports.map(|s| s.to_string()).collect();
-                clickwriteln!(writer, "Forwarding port(s): {}", ports.join(", "));
-
-                env.add_port_forward(env::PortForward {
-                    child,
-                    pod,
-                    ports,
-                    output,
-                });
-            }
-            Err(e) => match e.kind() {
-                io::ErrorKind::NotFound => {
-                    if kubectl_binary.starts_with('/') {
-                        writeln!(
-                            stderr(),
-                            "Could not find kubectl binary '{kubectl_binary}'. Does it exist?"
-                        )
-                        .unwrap_or(());
-                    } else {
-                        writeln!(
-                            stderr(),
-                            "Could not find kubectl binary '{kubectl_binary}'. Is it in your PATH."
-                        )
-                        .unwrap_or(());
-                    }
-                }
-                _ => {
-                    write!(
-                        stderr(),
-                        "Couldn't execute kubectl, not forwarding.  Error is: {e}"
-                    )
-                    .unwrap_or(());
-                }
-            },
-        }
-        Ok(()) // TODO: Return errors above if things fail
+        let ports: Vec<String> = ports.map(|s| s.to_string()).collect();
+        start_port_forward(env, pod, ns, ports, writer)
     }
 );
 
@@ -284,8 +295,9 @@ command!(
                     }
                 }
                 None => {
-                    clickwriteln!(writer, "Invalid index (try without args to get a list)");
-                    return Ok(()); // TODO: Return error
+                    return Err(ClickError::CommandError(
+                        "Invalid index (try without args to get a list)".to_string(),
+                    ))
                 }
             }
 
@@ -300,7 +312,9 @@ command!(
                                 clickwriteln!(writer, "Stopped");
                             }
                             Err(e) => {
-                                write!(stderr(), "Failed to stop: {e}").unwrap_or(());
+                                return Err(ClickError::CommandError(format!(
+                                    "Failed to stop: {e}"
+                                )))
                             }
                         }
                     } else {
@@ -27,7 +27,6 @@ use crate::{
 
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::Write;
 
 lazy_static! {
     static ref RS_EXTRACTORS: HashMap<String, Extractor<apps_api::ReplicaSet>> = {
@@ -133,6 +132,7 @@ fn rs_selector(replicaset: &apps_api::ReplicaSet) -> Option<CellSpec<'_>> {
 list_command!(
     ReplicaSets,
     "replicasets",
+    "ReplicaSet",
     "Get replicasets (in current namespace if set)",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
@@ -15,9 +15,9 @@
 /// Support for argo rollouts https://argoproj.github.io/argo-rollouts/
 use clap::{Arg, Command as ClapCommand};
 use k8s_openapi::{
-    apimachinery::pkg::apis::meta::v1::ObjectMeta, http, ListOptional, ListResponse,
-    ListableResource, Metadata, NamespaceResourceScope, RequestError, Resource, Response,
-    ResponseBody, ResponseError,
+    apimachinery::pkg::apis::meta::v1::ObjectMeta, http, DeleteOptional, DeleteResponse,
+    ListOptional, ListResponse, ListableResource, Metadata, NamespaceResourceScope, RequestError,
+    Resource, Response, ResponseBody, ResponseError,
 };
 use serde_json::{value::from_value, Error, Value};
 
@@ -34,7 +34,6 @@ use crate::{
 
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::Write;
 
 lazy_static! {
     static ref RO_EXTRACTORS: HashMap<String, Extractor<RolloutValue>> = {
@@ -89,6 +88,7 @@ fn ro_available(rollout: &RolloutValue) -> Option<CellSpec<'_>> {
 list_command!(
     Rollouts,
     "rollouts",
+    "Rollout",
     "Get argo rollouts (in current namespace if set)",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
@@ -276,4 +276,28 @@ impl RolloutValue {
         let url = format!("/apis/argoproj.io/v1alpha1/namespaces/{namespace}/rollouts/{name}",);
         get_read_request_for_url(url)
     }
+
+    #[allow(clippy::type_complexity)] // type is from k8s_openapi
+    pub fn delete_namespaced_rollout(
+        name: &str,
+        namespace: &str,
+        optional: DeleteOptional<'_>,
+    ) -> Result<
+        (
+            http::Request<Vec<u8>>,
+            fn(_: http::StatusCode) -> ResponseBody<DeleteResponse<Self>>,
+        ),
+        RequestError,
+    > {
+        let url = format!("/apis/argoproj.io/v1alpha1/namespaces/{namespace}/rollouts/{name}",);
+        let request = http::Request::delete(url).header(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("application/json"),
+        );
+        let body = serde_json::to_vec(&optional).map_err(RequestError::Json)?;
+        match request.body(body) {
+            Ok(request) => Ok((request, ResponseBody::new)),
+            Err(err) => Err(RequestError::Http(err)),
+        }
+    }
 }
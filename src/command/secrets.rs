@@ -12,22 +12,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::Utc;
 use clap::{Arg, Command as ClapCommand};
 use k8s_openapi::api::core::v1 as api;
+use k8s_openapi::{apimachinery::pkg::apis::meta::v1::Patch, PatchOptional, PatchResponse};
+use serde_json::{json, Value};
 
 use crate::{
+    cert,
     command::command_def::{exec_match, show_arg, sort_arg, start_clap, Cmd},
-    command::{run_list_command, Extractor},
+    command::datakeys::{
+        dump_dir_for, edit_value, get_key, require_arg, write_keys_to_dir, write_raw_value,
+    },
+    command::{format_duration, run_list_command, Extractor},
     completer,
     env::Env,
+    error::ClickError,
     kobj::{KObj, ObjType},
     output::ClickWriter,
     table::CellSpec,
+    values::val_str,
 };
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Write;
+use std::path::{Path, PathBuf};
 
 lazy_static! {
     static ref SECRET_EXTRACTORS: HashMap<String, Extractor<api::Secret>> = {
@@ -70,6 +81,7 @@ fn secret_data(secret: &api::Secret) -> Option<CellSpec<'_>> {
 list_command!(
     Secrets,
     "secrets",
+    "Secret",
     "Get secrets (in current namespace if set)",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
@@ -120,3 +132,385 @@ list_command!(
         )
     }
 );
+
+pub const SECRET_ACTIONS: [&str; 4] = ["show", "get", "dump", "edit"];
+
+// how many bytes of a binary value to show when revealing it
+const HEX_PREVIEW_BYTES: usize = 32;
+
+// warn about certs that expire sooner than this many days
+const CERT_EXPIRY_WARN_DAYS: i64 = 30;
+
+/// Return true if data is utf-8 without any control chars that would mess up a terminal
+fn is_text(data: &[u8]) -> bool {
+    match std::str::from_utf8(data) {
+        Ok(s) => !s
+            .chars()
+            .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t')),
+        Err(_) => false,
+    }
+}
+
+fn hex_preview(data: &[u8]) -> String {
+    let mut hex: Vec<String> = data
+        .iter()
+        .take(HEX_PREVIEW_BYTES)
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    if data.len() > HEX_PREVIEW_BYTES {
+        hex.push("...".to_string());
+    }
+    hex.join(" ")
+}
+
+pub fn read_secret(obj: &KObj, env: &Env) -> Result<api::Secret, ClickError> {
+    if !matches!(obj.typ, ObjType::Secret) {
+        return Err(ClickError::CommandError(format!(
+            "{} is a {}, not a secret",
+            obj.name(),
+            obj.type_str()
+        )));
+    }
+    let ns = obj.namespace.as_deref().unwrap_or("default");
+    let (request, _) = api::Secret::read_namespaced_secret(obj.name(), ns, Default::default())?;
+    match env.run_on_context(|c| c.read(env.get_impersonate_user(), request))? {
+        api::ReadNamespacedSecretResponse::Ok(secret) => Ok(secret),
+        _ => Err(ClickError::CommandError(format!(
+            "Could not read secret {}",
+            obj.name()
+        ))),
+    }
+}
+
+/// Get the data out of a secret, limited to the specified key if there is one
+fn secret_data_for_key(
+    secret: api::Secret,
+    key: Option<&str>,
+) -> Result<BTreeMap<String, Vec<u8>>, ClickError> {
+    let mut data: BTreeMap<String, Vec<u8>> = secret
+        .data
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(k, v)| (k, v.0))
+        .collect();
+    match key {
+        Some(key) => match data.remove_entry(key) {
+            Some((k, v)) => Ok([(k, v)].into_iter().collect()),
+            None => Err(ClickError::CommandError(format!(
+                "Secret {} has no key {key}",
+                secret.metadata.name.as_deref().unwrap_or("<Unknown>")
+            ))),
+        },
+        None => Ok(data),
+    }
+}
+
+fn print_value(val: &[u8], reveal: bool, writer: &mut ClickWriter) {
+    if !is_text(val) {
+        if reveal {
+            clickwriteln!(writer, "  <binary> {}", hex_preview(val));
+        } else {
+            clickwriteln!(writer, "  <binary, {} bytes>", val.len());
+        }
+    } else if reveal {
+        // safe, is_text checks this is utf-8
+        for line in std::str::from_utf8(val).unwrap().lines() {
+            clickwriteln!(writer, "  {line}");
+        }
+    } else {
+        clickwriteln!(writer, "  ******** (use --reveal to show)");
+    }
+}
+
+/// Print the registries and users in a .dockerconfigjson or .dockercfg value
+fn print_docker_config(val: &[u8], reveal: bool, writer: &mut ClickWriter) {
+    let config: Value = match serde_json::from_slice(val) {
+        Ok(config) => config,
+        Err(e) => {
+            clickwriteln!(writer, "  Could not parse docker config: {e}");
+            return;
+        }
+    };
+    // .dockerconfigjson nests registries under "auths", the older .dockercfg doesn't
+    let auths = config.get("auths").unwrap_or(&config);
+    let auths = match auths.as_object() {
+        Some(auths) if !auths.is_empty() => auths,
+        _ => {
+            clickwriteln!(writer, "  No registries in docker config");
+            return;
+        }
+    };
+    for (registry, entry) in auths.iter() {
+        // username and password can be specified directly, or base64 encoded as "user:pass"
+        let decoded_auth = entry
+            .get("auth")
+            .and_then(|a| a.as_str())
+            .and_then(|a| STANDARD.decode(a).ok())
+            .map(|a| String::from_utf8_lossy(&a).into_owned());
+        let (auth_user, auth_pass) = match decoded_auth.as_deref().and_then(|a| a.split_once(':')) {
+            Some((user, pass)) => (Some(user.to_string()), Some(pass.to_string())),
+            None => (None, None),
+        };
+        let user = entry
+            .get("username")
+            .and_then(|u| u.as_str())
+            .map(|u| u.to_string())
+            .or(auth_user)
+            .unwrap_or_else(|| "<none>".to_string());
+        let password = entry
+            .get("password")
+            .and_then(|p| p.as_str())
+            .map(|p| p.to_string())
+            .or(auth_pass);
+        let password = match password {
+            Some(password) if reveal => password,
+            Some(_) => "********".to_string(),
+            None => "<none>".to_string(),
+        };
+        clickwriteln!(
+            writer,
+            "  Registry: {registry}  User: {user}  Password: {password}"
+        );
+    }
+}
+
+fn print_certs(val: &[u8], env: &Env, writer: &mut ClickWriter) {
+    let certs = match cert::parse_pem_certs(val) {
+        Ok(certs) => certs,
+        Err(e) => {
+            clickwriteln!(writer, "  Could not parse certificate(s): {e}");
+            return;
+        }
+    };
+    for (i, cert) in certs.iter().enumerate() {
+        clickwriteln!(writer, "  Certificate {i}:");
+        clickwriteln!(writer, "    Subject:    {}", cert.subject);
+        clickwriteln!(writer, "    Issuer:     {}", cert.issuer);
+        if !cert.sans.is_empty() {
+            clickwriteln!(writer, "    SANs:       {}", cert.sans.join(", "));
+        }
+        clickwriteln!(writer, "    Not Before: {}", cert.not_before);
+        let until_expiry = cert.not_after.signed_duration_since(Utc::now());
+        if until_expiry.num_seconds() < 0 {
+            let expired = format!("EXPIRED {} ago", format_duration(-until_expiry));
+            clickwriteln!(
+                writer,
+                "    Not After:  {} ({})",
+                cert.not_after,
+                env.styles.danger(&expired)
+            );
+        } else {
+            let expires = format!("expires in {}", format_duration(until_expiry));
+            if until_expiry.num_days() < CERT_EXPIRY_WARN_DAYS {
+                clickwriteln!(
+                    writer,
+                    "    Not After:  {} ({})",
+                    cert.not_after,
+                    env.styles.warning(&expires)
+                );
+            } else {
+                clickwriteln!(writer, "    Not After:  {} ({expires})", cert.not_after);
+            }
+        }
+    }
+}
+
+fn show_secret(
+    obj: &KObj,
+    key: Option<&str>,
+    reveal: bool,
+    to_dir: Option<&Path>,
+    env: &Env,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    let secret = read_secret(obj, env)?;
+    let secret_type = secret.type_.clone().unwrap_or_else(|| "<none>".to_string());
+    let data = secret_data_for_key(secret, key)?;
+
+    if let Some(dir) = to_dir {
+        return write_keys_to_dir(&data, &dump_dir_for(dir, obj, env), writer);
+    }
+
+    clickwriteln!(writer, "Type: {secret_type}");
+    if data.is_empty() {
+        clickwriteln!(writer, "No data");
+    }
+    for (key, val) in data.iter() {
+        clickwriteln!(writer, "{key} ({} bytes):", val.len());
+        print_value(val, reveal, writer);
+        if key == ".dockerconfigjson" || key == ".dockercfg" {
+            print_docker_config(val, reveal, writer);
+        } else if cert::has_pem_cert(val) {
+            print_certs(val, env, writer);
+        }
+    }
+    Ok(())
+}
+
+fn edit_secret_key(
+    obj: &KObj,
+    key: &str,
+    dry_run: bool,
+    env: &Env,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    if !dry_run && !env.check_mutation(&format!("edit {key} in secret {}", obj.name()), writer)? {
+        clickwriteln!(writer, "Not updating {}", obj.name());
+        return Ok(());
+    }
+    let data = secret_data_for_key(read_secret(obj, env)?, Some(key))?;
+    let val = get_key(&data, key, obj.name())?;
+    match edit_value(env, obj.name(), key, val, writer)? {
+        Some(new_val) => {
+            let patch = json!({ "data": { key: STANDARD.encode(new_val) } });
+            let (request, _) = api::Secret::patch_namespaced_secret(
+                obj.name(),
+                obj.namespace.as_deref().unwrap_or("default"),
+                &Patch::Merge(patch),
+                PatchOptional {
+                    dry_run: dry_run.then_some("All"),
+                    ..Default::default()
+                },
+            )?;
+            match env.run_on_context(|c| c.read(env.get_impersonate_user(), request))? {
+                PatchResponse::<api::Secret>::Ok(_) if dry_run => {
+                    clickwriteln!(writer, "Would update key {key} in {} (dry run)", obj.name());
+                    Ok(())
+                }
+                PatchResponse::<api::Secret>::Ok(_) => {
+                    clickwriteln!(writer, "Updated key {key} in {}", obj.name());
+                    Ok(())
+                }
+                PatchResponse::Other(Ok(Some(val))) => Err(ClickError::CommandError(format!(
+                    "Failed to update {}: {}",
+                    obj.name(),
+                    val_str("/message", &val, "<no message>")
+                ))),
+                _ => Err(ClickError::CommandError(format!(
+                    "Failed to update {}",
+                    obj.name()
+                ))),
+            }
+        }
+        None => {
+            clickwriteln!(writer, "No changes to {key}, not updating");
+            Ok(())
+        }
+    }
+}
+
+command!(
+    Secret,
+    "secret",
+    "Show, extract, or edit the contents of the active secret(s)",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("action")
+                .help("Action to take")
+                .required(true)
+                .value_parser(SECRET_ACTIONS)
+                .index(1),
+        )
+        .arg(
+            Arg::new("key")
+                .help("The key to act on, or for dump, the directory to write to")
+                .required(false)
+                .index(2),
+        )
+        .arg(
+            Arg::new("reveal")
+                .long("reveal")
+                .help("Show the decoded values, instead of masking them")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("to_dir")
+                .long("to-dir")
+                .help("Write each key to a file (named after the key) in the specified directory")
+                .value_parser(clap::value_parser!(PathBuf))
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .help("For edit, ask the server to validate the change without saving it")
+                .takes_value(false),
+        )
+        .after_help(
+            "Actions:
+  show [key]  Show the keys of the secret. Values are masked unless --reveal is passed. Binary
+              values are shown as their size, or as hex if revealed. Docker registry secrets have
+              their registries and users listed, and any certificates have their subject, SANs and
+              expiry shown.
+  get <key>   Print the raw decoded value of key, so it can be piped or redirected
+  dump <dir>  Write each key to a file (named after the key) in dir
+  edit <key>  Open the decoded value of key in your editor, and update the secret when you exit
+
+Examples:
+  # Show all the keys in the active secret, with masked values
+  secret show
+
+  # Show the decoded value of the 'password' key
+  secret show password --reveal
+
+  # Save the 'tls.crt' key to a file
+  secret get tls.crt > /tmp/tls.crt
+
+  # Write all the keys in the secret to files in /tmp/mysecret
+  secret dump /tmp/mysecret",
+        )
+    },
+    vec!["secret"],
+    vec![
+        &completer::secretaction_values_completer,
+        &completer::secret_key_completer
+    ],
+    no_named_complete!(),
+    |matches, env, writer| {
+        let action = matches.get_one::<String>("action").unwrap(); // safe, required
+        let key = matches.get_one::<String>("key").map(|s| s.as_str());
+        match action.as_str() {
+            "show" => {
+                let reveal = matches.contains_id("reveal");
+                let to_dir = matches.get_one::<PathBuf>("to_dir").map(|p| p.as_path());
+                env.apply_to_selection(
+                    writer,
+                    Some(&env.click_config.range_separator),
+                    |obj, writer| show_secret(obj, key, reveal, to_dir, env, writer),
+                )
+            }
+            "get" => {
+                let key = require_arg(key, "get", "key")?;
+                env.apply_to_selection(
+                    writer,
+                    Some(&env.click_config.range_separator),
+                    |obj, writer| {
+                        let data = secret_data_for_key(read_secret(obj, env)?, Some(key))?;
+                        write_raw_value(get_key(&data, key, obj.name())?, writer)
+                    },
+                )
+            }
+            "dump" => {
+                let dir = Path::new(require_arg(key, "dump", "directory")?);
+                env.apply_to_selection(
+                    writer,
+                    Some(&env.click_config.range_separator),
+                    |obj, writer| {
+                        let data = secret_data_for_key(read_secret(obj, env)?, None)?;
+                        write_keys_to_dir(&data, &dump_dir_for(dir, obj, env), writer)
+                    },
+                )
+            }
+            "edit" => {
+                let key = require_arg(key, "edit", "key")?;
+                let dry_run = matches.contains_id("dry_run");
+                env.apply_to_selection(
+                    writer,
+                    Some(&env.click_config.range_separator),
+                    |obj, writer| edit_secret_key(obj, key, dry_run, env, writer),
+                )
+            }
+            _ => unreachable!(), // clap validates action
+        }
+    }
+);
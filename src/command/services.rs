@@ -13,20 +13,25 @@
 // limitations under the License.
 
 use clap::{Arg, Command as ClapCommand};
+use comfy_table::{Cell, Table};
 use k8s_openapi::api::core::v1 as api;
+use k8s_openapi::api::discovery::v1 as api_discovery;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use k8s_openapi::{List, ListOptional};
 
 use crate::{
     command::command_def::{exec_match, show_arg, sort_arg, start_clap, Cmd},
     command::{keyval_string, run_list_command, Extractor},
     completer,
     env::Env,
+    error::ClickError,
     kobj::{KObj, ObjType},
     output::ClickWriter,
-    table::CellSpec,
+    table::{color_cell, CellSpec},
 };
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Write;
 
 lazy_static! {
@@ -141,6 +146,7 @@ fn service_selector(service: &api::Service) -> Option<CellSpec<'_>> {
 list_command!(
     Services,
     "services",
+    "Service",
     "Get services in the current context",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
@@ -192,3 +198,382 @@ list_command!(
         )
     }
 );
+
+fn list_pods_matching(
+    selector: &str,
+    namespace: &str,
+    env: &Env,
+) -> Result<Vec<api::Pod>, ClickError> {
+    let opts = ListOptional {
+        label_selector: Some(selector),
+        ..Default::default()
+    };
+    let (request, _) = api::Pod::list_namespaced_pod(namespace, opts)?;
+    let list: List<api::Pod> =
+        env.run_on_context(|c| c.execute_list(env.get_impersonate_user(), request))?;
+    Ok(list.items)
+}
+
+/// Find the container port on the pod that the target port of a service refers to. A named
+/// target port must match the name of a container port, a numeric one must match the number of a
+/// port some container declares. Returns the container name and port number if found.
+fn resolve_target_port<'a>(
+    target: &IntOrString,
+    protocol: &str,
+    pod: &'a api::Pod,
+) -> Option<(&'a str, i32)> {
+    let spec = pod.spec.as_ref()?;
+    for container in spec.containers.iter() {
+        for port in container.ports.iter().flatten() {
+            if port.protocol.as_deref().unwrap_or("TCP") != protocol {
+                continue;
+            }
+            let found = match target {
+                IntOrString::Int(num) => port.container_port == *num,
+                IntOrString::String(name) => port.name.as_deref() == Some(name.as_str()),
+            };
+            if found {
+                return Some((container.name.as_str(), port.container_port));
+            }
+        }
+    }
+    None
+}
+
+fn print_selector_check(
+    selector: &BTreeMap<String, String>,
+    pods: &[api::Pod],
+    namespace: &str,
+    env: &Env,
+    writer: &mut ClickWriter,
+) -> Result<(), ClickError> {
+    clickwriteln!(writer, "Selector:");
+    if selector.is_empty() {
+        clickwriteln!(
+            writer,
+            "  <none> (endpoints for this service are managed manually)"
+        );
+        return Ok(());
+    }
+    let mut table = Table::new();
+    table.set_header(vec!["Label", "Matching Pods"]);
+    for (key, val) in selector.iter() {
+        let label = format!("{key}={val}");
+        let count = list_pods_matching(&label, namespace, env)?.len();
+        let count_cell = if count == 0 {
+            color_cell(Cell::new("0 (matches no pods)"), env.styles.danger_color())
+        } else {
+            Cell::new(count)
+        };
+        table.add_row(vec![Cell::new(label), count_cell]);
+    }
+    crate::table::print_filled_table(&mut table, writer);
+    if pods.is_empty() {
+        clickwriteln!(
+            writer,
+            "{}",
+            env.styles
+                .danger("No pods match the full selector, this service has no backends")
+        );
+    } else {
+        clickwriteln!(writer, "{} pod(s) match the full selector", pods.len());
+    }
+    Ok(())
+}
+
+// An address backing a service, from either an EndpointSlice or the Endpoints object
+struct EndpointRow {
+    address: String,
+    ports: String,
+    pod: Option<String>,
+    node: Option<String>,
+    ready: bool,
+}
+
+// Format ports as "name port/protocol", joined with commas
+fn format_endpoint_ports<'a>(
+    ports: impl Iterator<Item = (Option<&'a str>, Option<i32>, Option<&'a str>)>,
+) -> String {
+    ports
+        .map(|(name, port, protocol)| {
+            format!(
+                "{}{}/{}",
+                name.map(|name| format!("{name} ")).unwrap_or_default(),
+                port.map(|p| p.to_string())
+                    .unwrap_or_else(|| "*".to_string()),
+                protocol.unwrap_or("TCP")
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// the pod an endpoint's target_ref points to, if it points to one
+fn target_pod(target_ref: Option<&api::ObjectReference>) -> Option<String> {
+    target_ref
+        .filter(|tr| tr.kind.as_deref() == Some("Pod"))
+        .and_then(|tr| tr.name.clone())
+}
+
+fn rows_from_slices(slices: &[api_discovery::EndpointSlice]) -> Vec<EndpointRow> {
+    let mut rows = vec![];
+    for slice in slices.iter() {
+        let ports = format_endpoint_ports(
+            slice
+                .ports
+                .iter()
+                .flatten()
+                .map(|port| (port.name.as_deref(), port.port, port.protocol.as_deref())),
+        );
+        for endpoint in slice.endpoints.iter() {
+            // a missing ready condition means ready
+            let ready = endpoint
+                .conditions
+                .as_ref()
+                .and_then(|c| c.ready)
+                .unwrap_or(true);
+            for address in endpoint.addresses.iter() {
+                rows.push(EndpointRow {
+                    address: address.clone(),
+                    ports: ports.clone(),
+                    pod: target_pod(endpoint.target_ref.as_ref()),
+                    node: endpoint.node_name.clone(),
+                    ready,
+                });
+            }
+        }
+    }
+    rows
+}
+
+fn rows_from_endpoints(endpoints: api::Endpoints) -> Vec<EndpointRow> {
+    let mut rows = vec![];
+    for subset in endpoints.subsets.iter().flatten() {
+        let ports = format_endpoint_ports(subset.ports.iter().flatten().map(|port| {
+            (
+                port.name.as_deref(),
+                Some(port.port),
+                port.protocol.as_deref(),
+            )
+        }));
+        let ready = subset.addresses.iter().flatten().map(|addr| (addr, true));
+        let not_ready = subset
+            .not_ready_addresses
+            .iter()
+            .flatten()
+            .map(|addr| (addr, false));
+        for (addr, ready) in ready.chain(not_ready) {
+            rows.push(EndpointRow {
+                address: addr.ip.clone(),
+                ports: ports.clone(),
+                pod: target_pod(addr.target_ref.as_ref()),
+                node: addr.node_name.clone(),
+                ready,
+            });
+        }
+    }
+    rows
+}
+
+// Get the addresses backing the service. These come from its EndpointSlices (found by the
+// kubernetes.io/service-name label), which unlike Endpoints aren't truncated at 1000 addresses. If
+// the cluster doesn't serve EndpointSlices, or the service has none, fall back to its Endpoints
+fn get_endpoint_rows(
+    name: &str,
+    namespace: &str,
+    env: &Env,
+) -> Result<Vec<EndpointRow>, ClickError> {
+    let selector = format!("kubernetes.io/service-name={name}");
+    let opts = ListOptional {
+        label_selector: Some(&selector),
+        ..Default::default()
+    };
+    let (request, _) =
+        api_discovery::EndpointSlice::list_namespaced_endpoint_slice(namespace, opts)?;
+    let slices: Option<List<api_discovery::EndpointSlice>> = env
+        .run_on_context(|c| c.execute_list(env.get_impersonate_user(), request))
+        .ok();
+    match slices {
+        Some(slices) if !slices.items.is_empty() => Ok(rows_from_slices(&slices.items)),
+        _ => {
+            let (request, _) =
+                api::Endpoints::read_namespaced_endpoints(name, namespace, Default::default())?;
+            match env.run_on_context(|c| c.read(env.get_impersonate_user(), request)) {
+                Ok(api::ReadNamespacedEndpointsResponse::Ok(endpoints)) => {
+                    Ok(rows_from_endpoints(endpoints))
+                }
+                _ => Ok(vec![]),
+            }
+        }
+    }
+}
+
+fn print_endpoints_check(rows: Vec<EndpointRow>, env: &Env, writer: &mut ClickWriter) {
+    clickwriteln!(writer, "\nEndpoints:");
+    if rows.is_empty() {
+        clickwriteln!(writer, "  {}", env.styles.danger("<none>"));
+        return;
+    }
+    let mut table = Table::new();
+    table.set_header(vec!["Address", "Port(s)", "Pod", "Node", "Ready"]);
+    for row in rows.into_iter() {
+        let ready_cell = if row.ready {
+            color_cell(Cell::new("true"), env.styles.success_color())
+        } else {
+            color_cell(Cell::new("false"), env.styles.danger_color())
+        };
+        table.add_row(vec![
+            Cell::new(row.address),
+            Cell::new(row.ports),
+            Cell::new(row.pod.as_deref().unwrap_or("<none>")),
+            Cell::new(row.node.as_deref().unwrap_or("<none>")),
+            ready_cell,
+        ]);
+    }
+    crate::table::print_filled_table(&mut table, writer);
+}
+
+fn print_ports_check(
+    service_ports: &[api::ServicePort],
+    pods: &[api::Pod],
+    env: &Env,
+    writer: &mut ClickWriter,
+) {
+    clickwriteln!(writer, "\nPorts:");
+    if service_ports.is_empty() {
+        clickwriteln!(writer, "  <none>");
+        return;
+    }
+    let mut table = Table::new();
+    table.set_header(vec!["Port", "Target Port", "Resolves To", "Pods"]);
+    for port in service_ports.iter() {
+        let protocol = port.protocol.as_deref().unwrap_or("TCP");
+        let target = port
+            .target_port
+            .clone()
+            .unwrap_or(IntOrString::Int(port.port));
+        let target_str = match &target {
+            IntOrString::Int(num) => num.to_string(),
+            IntOrString::String(name) => name.clone(),
+        };
+        let mut resolved: Vec<String> = vec![];
+        let mut resolved_count = 0;
+        for pod in pods.iter() {
+            if let Some((container, num)) = resolve_target_port(&target, protocol, pod) {
+                resolved_count += 1;
+                let res = format!("{container}:{num}");
+                if !resolved.contains(&res) {
+                    resolved.push(res);
+                }
+            }
+        }
+        let resolved_cell = if resolved.is_empty() {
+            match target {
+                // a numeric target port doesn't need to be declared by the container, so this is
+                // suspicious but not necessarily broken
+                IntOrString::Int(_) if !pods.is_empty() => color_cell(
+                    Cell::new("<not declared by any container>"),
+                    env.styles.warning_color(),
+                ),
+                _ => color_cell(Cell::new("<unresolved>"), env.styles.danger_color()),
+            }
+        } else {
+            Cell::new(resolved.join(", "))
+        };
+        let count_str = format!("{resolved_count}/{}", pods.len());
+        let count_cell = if resolved_count == pods.len() && !pods.is_empty() {
+            color_cell(Cell::new(count_str), env.styles.success_color())
+        } else {
+            color_cell(Cell::new(count_str), env.styles.warning_color())
+        };
+        table.add_row(vec![
+            Cell::new(format!(
+                "{}{}/{}",
+                port.name
+                    .as_ref()
+                    .map(|name| format!("{name} "))
+                    .unwrap_or_default(),
+                port.port,
+                protocol
+            )),
+            Cell::new(target_str),
+            resolved_cell,
+            count_cell,
+        ]);
+    }
+    crate::table::print_filled_table(&mut table, writer);
+}
+
+fn service_check(obj: &KObj, env: &Env, writer: &mut ClickWriter) -> Result<(), ClickError> {
+    if !matches!(obj.typ, ObjType::Service) {
+        return Err(ClickError::CommandError(format!(
+            "service-check only works on services, not {}",
+            obj.type_str()
+        )));
+    }
+    let namespace = obj.namespace.as_deref().unwrap_or("default");
+
+    let (request, _) =
+        api::Service::read_namespaced_service(obj.name(), namespace, Default::default())?;
+    let service = match env.run_on_context(|c| c.read(env.get_impersonate_user(), request))? {
+        api::ReadNamespacedServiceResponse::Ok(service) => service,
+        _ => {
+            return Err(ClickError::CommandError(format!(
+                "Could not read service {}",
+                obj.name()
+            )))
+        }
+    };
+
+    let endpoint_rows = get_endpoint_rows(obj.name(), namespace, env)?;
+
+    let spec = service.spec.unwrap_or_default();
+    let selector = spec.selector.unwrap_or_default();
+    let pods = if selector.is_empty() {
+        vec![]
+    } else {
+        let selector_str = selector
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<String>>()
+            .join(",");
+        list_pods_matching(&selector_str, namespace, env)?
+    };
+
+    print_selector_check(&selector, &pods, namespace, env, writer)?;
+    print_endpoints_check(endpoint_rows, env, writer);
+    print_ports_check(
+        spec.ports.as_deref().unwrap_or_default(),
+        &pods,
+        env,
+        writer,
+    );
+    Ok(())
+}
+
+command!(
+    ServiceCheck,
+    "service-check",
+    "Check why the active service(s) might not be working",
+    |clap: ClapCommand<'static>| {
+        clap.after_help(
+            "Prints three sections for each service:
+  Selector:  How many pods each label in the service's selector matches on its own, to spot labels
+             that match no pods, and how many match the full selector
+  Endpoints: Every endpoint address with the pod and node it belongs to, and if it is ready, from
+             the service's EndpointSlices (or its Endpoints if it has no EndpointSlices)
+  Ports:     Each service port's targetPort, resolved against the container ports of the pods
+             matching the selector",
+        )
+    },
+    vec!["service-check", "svccheck"],
+    noop_complete!(),
+    no_named_complete!(),
+    |_matches, env, writer| {
+        env.apply_to_selection(
+            writer,
+            Some(&env.click_config.range_separator),
+            |obj, writer| service_check(obj, env, writer),
+        )
+    }
+);
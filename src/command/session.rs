@@ -0,0 +1,210 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, Command as ClapCommand};
+use rustyline::completion::Pair as RustlinePair;
+
+use crate::{
+    command::command_def::{exec_match, start_clap, Cmd},
+    command::portforwards::start_port_forward,
+    completer,
+    config::{Session, LAST_SESSION},
+    env::Env,
+    error::ClickError,
+    output::ClickWriter,
+};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+pub const SESSION_ACTIONS: [&str; 4] = ["save", "load", "list", "delete"];
+
+/// Save the current state of env as the named session. Returns the path it was saved to
+pub fn save_session(env: &Env, name: &str) -> Result<PathBuf, ClickError> {
+    let path = Session::path_for(env.click_config_dir(), name)?;
+    env.get_session().save_to_file(&path)?;
+    Ok(path)
+}
+
+/// Load the named session into env, restarting any port forwards it had
+pub fn load_session(env: &mut Env, name: &str, writer: &mut ClickWriter) -> Result<(), ClickError> {
+    let path = Session::path_for(env.click_config_dir(), name)?;
+    if !path.exists() {
+        return Err(ClickError::CommandError(format!(
+            "No saved session named {name}"
+        )));
+    }
+    let session = Session::from_file(&path)?;
+    let port_forwards = env.restore_session(session);
+    // one port forward failing shouldn't stop the rest from being restarted
+    let mut failed = vec![];
+    for pf in port_forwards.into_iter() {
+        let running = env
+            .get_port_forwards()
+            .any(|cur| cur.pod == pf.pod && cur.namespace == pf.namespace && cur.ports == pf.ports);
+        if !running {
+            clickwrite!(writer, "Restarting port forward to {}: ", pf.pod);
+            let pod = pf.pod.clone();
+            if let Err(e) = start_port_forward(env, pf.pod, pf.namespace, pf.ports, writer) {
+                clickwriteln!(writer, "{e}");
+                failed.push(pod);
+            }
+        }
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(ClickError::CommandError(format!(
+            "Loaded session {name}, but couldn't restart {} port forward(s), to: {}",
+            failed.len(),
+            failed.join(", ")
+        )))
+    }
+}
+
+command!(
+    SessionCmd,
+    "session",
+    "Save, load, or list click sessions",
+    |clap: ClapCommand<'static>| {
+        clap.arg(
+            Arg::new("action")
+                .help("Action to take")
+                .required(true)
+                .value_parser(SESSION_ACTIONS)
+                .index(1),
+        )
+        .arg(
+            Arg::new("name")
+                .help("The name of the session (default: last)")
+                .required(false)
+                .index(2),
+        )
+        .after_help(
+            "A session is the current context, namespace, selected object or range, the objects \
+from the last list command, and any active port forwards. Sessions are saved in the \
+click_sessions directory next to click.config.
+
+The session named 'last' is saved when click exits, and restored on start, if \
+'set restore_session true' has been run.
+
+Examples:
+  # Save the current session as 'debugging'
+  session save debugging
+
+  # Pick it back up later
+  session load debugging
+
+  # Show all saved sessions
+  session list",
+        )
+    },
+    vec!["session"],
+    vec![&completer::sessionaction_values_completer],
+    no_named_complete!(),
+    |matches, env, writer| {
+        let action = matches.get_one::<String>("action").unwrap(); // safe, required
+        let name = matches
+            .get_one::<String>("name")
+            .map(|s| s.as_str())
+            .unwrap_or(LAST_SESSION);
+        match action.as_str() {
+            "save" => {
+                let path = save_session(env, name)?;
+                clickwriteln!(writer, "Saved session to {}", path.display());
+            }
+            "load" => {
+                load_session(env, name, writer)?;
+                clickwriteln!(writer, "Loaded session {name}");
+            }
+            "list" => {
+                let sessions = Session::list(env.click_config_dir())?;
+                if sessions.is_empty() {
+                    clickwriteln!(writer, "No saved sessions");
+                }
+                for session in sessions.iter() {
+                    clickwriteln!(writer, "{session}");
+                }
+            }
+            "delete" => {
+                let path = Session::path_for(env.click_config_dir(), name)?;
+                std::fs::remove_file(&path).map_err(|e| {
+                    ClickError::CommandError(format!("Could not delete session {name}: {e}"))
+                })?;
+                clickwriteln!(writer, "Deleted session {name}");
+            }
+            _ => unreachable!(), // clap validates action
+        }
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{get_config_from_kubefile_test_conf, ClickConfig, SavedPortForward};
+    use crate::env::ObjectSelection;
+
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_load_session_restarts_every_port_forward() {
+        let dir = TempDir::new("click-session").unwrap();
+        let click_config = ClickConfig {
+            kubectl_binary: Some("/nonexistent/kubectl".to_string()),
+            ..ClickConfig::default()
+        };
+        let mut env = Env::new(
+            get_config_from_kubefile_test_conf(),
+            click_config,
+            dir.path().join("click.config"),
+        );
+        env.context = Some(
+            env.config
+                .get_context("insecure_context", &env.click_config)
+                .unwrap(),
+        );
+        let port_forward = |pod: &str| SavedPortForward {
+            pod: pod.to_string(),
+            namespace: "default".to_string(),
+            ports: vec!["8080".to_string()],
+        };
+        let session = Session {
+            context: None,
+            namespace: Some("default".to_string()),
+            selection: ObjectSelection::None,
+            last_objs: None,
+            last_table: None,
+            port_forwards: vec![port_forward("web-1"), port_forward("web-2")],
+        };
+        session
+            .save_to_file(&Session::path_for(dir.path(), "pfs").unwrap())
+            .unwrap();
+
+        let mut writer = ClickWriter::with_buffer(vec![], false);
+        let err = load_session(&mut env, "pfs", &mut writer).unwrap_err();
+        assert!(err.to_string().contains("web-1, web-2"), "{err}");
+        let output = String::from_utf8(writer.finish_output().unwrap()).unwrap();
+        // both were tried, even though the first failed
+        assert_eq!(
+            output.matches("Could not find kubectl").count(),
+            2,
+            "{output}"
+        );
+        assert!(output.contains("Restarting port forward to web-2"));
+        assert_eq!(env.namespace.as_deref(), Some("default"));
+    }
+}
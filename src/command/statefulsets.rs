@@ -27,7 +27,6 @@ use crate::{
 
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::Write;
 
 lazy_static! {
     static ref SS_EXTRACTORS: HashMap<String, Extractor<apps_api::StatefulSet>> = {
@@ -120,6 +119,7 @@ fn ss_ready(statefulset: &apps_api::StatefulSet) -> Option<CellSpec<'_>> {
 list_command!(
     StatefulSets,
     "statefulsets",
+    "StatefulSet",
     "Get statefulsets (in current namespace if set)",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
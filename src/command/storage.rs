@@ -27,7 +27,6 @@ use crate::{
 
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::Write;
 
 lazy_static! {
     static ref SC_EXTRACTORS: HashMap<String, Extractor<api_storage::StorageClass>> = {
@@ -68,6 +67,7 @@ fn sc_provisioner(sc: &api_storage::StorageClass) -> Option<CellSpec<'_>> {
 list_command!(
     StorageClasses,
     "storageclasses",
+    "StorageClass",
     "Get storage classes in the current context",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
@@ -27,7 +27,6 @@ use crate::{
 
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::Write;
 
 lazy_static! {
     static ref PV_EXTRACTORS: HashMap<String, Extractor<api::PersistentVolume>> = {
@@ -159,6 +158,7 @@ fn volume_reason(volume: &api::PersistentVolume) -> Option<CellSpec<'_>> {
 list_command!(
     PersistentVolumes,
     "persistentvolumes",
+    "PersistentVolume",
     "Get persistent volumes in current context",
     super::COL_FLAGS,
     super::EXTRA_COL_FLAGS,
@@ -14,16 +14,24 @@
 
 use crate::command::command_def::Cmd;
 use crate::completer::ClickHelper;
-use crate::error::ClickError;
+use crate::config::LAST_SESSION;
+use crate::error::{ClickError, ErrorClass};
 use crate::kobj::KObj;
 use crate::output::ClickWriter;
-use crate::parser::{try_parse_csl, try_parse_range, Parser};
+use crate::parser::{
+    split_chain, substitute_vars, try_parse_csl, try_parse_range, ChainOp, Parser, VarRef,
+};
+use crate::script::Script;
+use crate::table::{filter_table, print_filled_table, RowFilter};
 use crate::values::val_str;
 
 use rustyline::config as rustyconfig;
 use rustyline::error::ReadlineError;
 use rustyline::history::DefaultHistory;
-use rustyline::Editor;
+use rustyline::{
+    ConditionalEventHandler, Editor, Event, EventContext, EventHandler, KeyEvent, RepeatCount,
+};
+use serde_json::{json, Value};
 
 use crate::env::Env;
 
@@ -32,6 +40,11 @@ use std::io::Write;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+// guard against scripts that source themselves
+const MAX_SCRIPT_DEPTH: usize = 16;
 
 /// Things the can come after a | or > char in input
 #[derive(Debug, PartialEq)]
@@ -88,23 +101,60 @@ fn build_parser_expr(line: &str, range: Range<usize>) -> Result<(&str, RightExpr
 }
 
 pub fn alias_expand_line(env: &Env, line: &str) -> String {
-    let expa = env.try_expand_alias(line, None);
-    let mut alias_stack = vec![expa];
-    #[allow(clippy::while_let_loop)] // needed due to borrow restrictions
+    // names of the aliases expanded so far. As with bash, a word that's identical to an alias
+    // already being expanded isn't expanded again, to avoid infinite expansion
+    let mut expanded_aliases: Vec<String> = vec![];
+    // the text that follows each expansion, in the order it was found
+    let mut rests: Vec<String> = vec![];
+    let mut cur = line.to_string();
     loop {
-        let expa = match alias_stack.last().unwrap().expansion {
-            Some(prev) => {
-                // previous thing expanded an alias, so try and expand that too
-                env.try_expand_alias(prev.expanded.as_str(), Some(prev.alias.as_str()))
+        let (expanded, rest) = {
+            let prev = expanded_aliases.last().cloned();
+            let expa = env.try_expand_alias(&cur, prev.as_deref());
+            match expa.expansion {
+                Some(alias) if !expanded_aliases.contains(&alias.alias) => {
+                    expanded_aliases.push(alias.alias.clone());
+                    if alias.is_parameterized() {
+                        // args after the alias are consumed by its parameters
+                        let (expanded, rest) = alias.expand_params(expa.rest);
+                        (expanded, rest.to_string())
+                    } else {
+                        (alias.expanded.clone(), expa.rest.to_string())
+                    }
+                }
+                _ => break,
             }
-            None => break,
         };
-        alias_stack.push(expa);
+        rests.push(rest);
+        cur = expanded;
     }
-    // At this point, all the "real" stuff is in the chain of "rest" memebers of the
-    // alias_stack, let's gather them up
-    let rests: Vec<&str> = alias_stack.iter().rev().map(|ea| ea.rest).collect();
-    rests.concat()
+    // At this point, the fully expanded command is in cur, and it's followed by the rest of each
+    // line that was expanded, innermost first
+    rests.push(cur);
+    rests.iter().rev().map(|s| s.as_str()).collect()
+}
+
+/// Split any `| where EXPR` stages off the front of right. These are handled by click rather than
+/// the shell. Returns the where expressions, and whatever follows the last of them.
+fn take_where_stages(mut right: RightExpr<'_>) -> Result<(Vec<String>, RightExpr<'_>), ClickError> {
+    let mut stages = vec![];
+    while let RightExpr::Pipe(cmd) = right {
+        let expr = match cmd.trim_start().strip_prefix("where") {
+            Some(expr) if expr.is_empty() || expr.starts_with(char::is_whitespace) => expr,
+            _ => break,
+        };
+        let (expr, next) = parse_line(expr)?;
+        let args: Vec<String> = Parser::new(expr).map(|x| x.2).collect();
+        let expr = args.join(" ");
+        if expr.trim().is_empty() {
+            return Err(ClickError::ParseErr(
+                "where needs a regex or column expression".to_string(),
+            ));
+        }
+        stages.push(expr);
+        right = next;
+    }
+    Ok((stages, right))
 }
 
 fn parse_line(line: &str) -> Result<(&str, RightExpr), ClickError> {
@@ -118,6 +168,29 @@ fn parse_line(line: &str) -> Result<(&str, RightExpr), ClickError> {
     Ok((line, RightExpr::None))
 }
 
+/// Set when the pick key is pressed on an empty line, so the repl runs pick
+static PICK_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Runs pick when Alt-p is pressed on an empty line
+struct PickKeyHandler;
+
+impl ConditionalEventHandler for PickKeyHandler {
+    fn handle(
+        &self,
+        _evt: &Event,
+        _n: RepeatCount,
+        _positive: bool,
+        ctx: &EventContext,
+    ) -> Option<rustyline::Cmd> {
+        if ctx.line().is_empty() {
+            PICK_REQUESTED.store(true, Ordering::SeqCst);
+            Some(rustyline::Cmd::AcceptLine)
+        } else {
+            None
+        }
+    }
+}
+
 // see comment on ClickCompleter::new for why a raw pointer is needed
 fn get_editor(
     config: rustyconfig::Config,
@@ -134,8 +207,13 @@ fn get_editor(
             "pipes",
             "redirection",
             "ranges",
+            "prompt",
         ],
     )));
+    rl.bind_sequence(
+        KeyEvent::alt('p'),
+        EventHandler::Conditional(Box::new(PickKeyHandler)),
+    );
     rl.load_history(hist_path).unwrap_or_default();
     rl
 }
@@ -145,6 +223,9 @@ pub struct CommandProcessor {
     rl: Editor<ClickHelper, DefaultHistory>,
     hist_path: PathBuf,
     commands: Vec<Box<dyn Cmd>>,
+    last_error: Option<ErrorClass>,
+    json_errors: bool,
+    script_depth: usize,
 }
 
 impl CommandProcessor {
@@ -157,6 +238,9 @@ impl CommandProcessor {
             rl,
             hist_path,
             commands,
+            last_error: None,
+            json_errors: false,
+            script_depth: 0,
         }
     }
 
@@ -173,30 +257,49 @@ impl CommandProcessor {
             rl,
             hist_path,
             commands,
+            last_error: None,
+            json_errors: false,
+            script_depth: 0,
         }
     }
 
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+
+    pub fn env_mut(&mut self) -> &mut Env {
+        Rc::get_mut(&mut self.env).unwrap()
+    }
+
     fn get_command_vec() -> Vec<Box<dyn Cmd>> {
         let commands: Vec<Box<dyn Cmd>> = vec![
             Box::new(crate::command::alias::Alias::new()),
             Box::new(crate::command::alias::Unalias::new()),
+            Box::new(crate::command::audit::Audit::new()),
             Box::new(crate::command::click::As::new()),
             Box::new(crate::command::click::Clear::new()),
             Box::new(crate::command::click::Context::new()),
             Box::new(crate::command::click::Contexts::new()),
+            Box::new(crate::command::click::Dashboard::new()),
             Box::new(crate::command::click::EnvCmd::new()),
             Box::new(crate::command::click::Quit::new()),
             Box::new(crate::command::click::Range::new()),
             Box::new(crate::command::click::Last::new()),
+            Box::new(crate::command::click::Pick::new()),
+            Box::new(crate::command::click::Let::new()),
+            Box::new(crate::command::click::Select::new()),
             Box::new(crate::command::click::SetCmd::new()),
+            Box::new(crate::command::click::Source::new()),
             Box::new(crate::command::click::UnSetCmd::new()),
             Box::new(crate::command::click::UtcCmd::new()),
+            Box::new(crate::command::configmaps::ConfigMap::new()),
             Box::new(crate::command::configmaps::ConfigMaps::new()),
             Box::new(crate::command::copy::Copy::new()),
             Box::new(crate::command::cronjobs::CronJobs::new()),
             Box::new(crate::command::crds::Crd::new()),
             Box::new(crate::command::daemonsets::DaemonSets::new()),
             Box::new(crate::command::delete::Delete::new()),
+            Box::new(crate::command::delete::RemoveFinalizers::new()),
             Box::new(crate::command::deployments::Deployments::new()),
             Box::new(crate::command::describe::Describe::new()),
             Box::new(crate::command::events::Events::new()),
@@ -205,14 +308,19 @@ impl CommandProcessor {
             Box::new(crate::command::logs::Logs::new()),
             Box::new(crate::command::namespaces::Namespace::new()),
             Box::new(crate::command::namespaces::Namespaces::new()),
+            Box::new(crate::command::namespaces::NamespaceCheck::new()),
             Box::new(crate::command::nodes::Nodes::new()),
             Box::new(crate::command::pods::Containers::new()),
             Box::new(crate::command::pods::Pods::new()),
+            Box::new(crate::command::pods::PodsOf::new()),
             Box::new(crate::command::portforwards::PortForward::new()),
             Box::new(crate::command::portforwards::PortForwards::new()),
             Box::new(crate::command::replicasets::ReplicaSets::new()),
+            Box::new(crate::command::secrets::Secret::new()),
             Box::new(crate::command::secrets::Secrets::new()),
             Box::new(crate::command::services::Services::new()),
+            Box::new(crate::command::services::ServiceCheck::new()),
+            Box::new(crate::command::session::SessionCmd::new()),
             Box::new(crate::command::statefulsets::StatefulSets::new()),
             Box::new(crate::command::storage::StorageClasses::new()),
             Box::new(crate::command::volumes::PersistentVolumes::new()),
@@ -229,6 +337,7 @@ impl CommandProcessor {
                 self.rl = get_editor(self.env.get_rustyline_conf(), &self.hist_path);
                 Rc::get_mut(&mut self.env).unwrap().need_new_editor = false;
             }
+            Rc::get_mut(&mut self.env).unwrap().set_prompt();
 
             // we set and unset the pointer to the env in the helper here so the get_mut below works
             let helper_env = Some(self.env.clone());
@@ -241,7 +350,18 @@ impl CommandProcessor {
             }
             match readline {
                 Ok(line) => {
-                    self.process_line(line.as_str(), writer);
+                    let start = Instant::now();
+                    if PICK_REQUESTED.swap(false, Ordering::SeqCst) {
+                        self.process_line("pick", writer);
+                    } else if line.trim().is_empty() {
+                        continue;
+                    } else {
+                        self.process_line(line.as_str(), writer);
+                    }
+                    let succeeded = self.last_error.is_none();
+                    Rc::get_mut(&mut self.env)
+                        .unwrap()
+                        .set_last_status(succeeded, start.elapsed());
                 }
                 Err(ReadlineError::Interrupted) => {} // don't exit on Ctrl-C
                 Err(ReadlineError::Eof) => {
@@ -256,42 +376,258 @@ impl CommandProcessor {
         }
         let env = Rc::get_mut(&mut self.env).unwrap();
         env.save_click_config();
+        if env.click_config.restore_session {
+            if let Err(e) = crate::command::session::save_session(env, LAST_SESSION) {
+                println!("Couldn't save session: {e}");
+            }
+        }
         if let Err(e) = self.rl.save_history(self.hist_path.as_path()) {
             println!("Couldn't save command history: {e}");
         }
         env.stop_all_forwards();
     }
 
-    /// Process the line.  Returns the result of finish_output on the writer
-    pub fn process_line(&mut self, line: &str, mut writer: ClickWriter) -> Option<Vec<u8>> {
-        if line.is_empty() {
-            return writer.finish_output();
+    /// Print errors to stderr as json, rather than as text to the output
+    pub fn set_json_errors(&mut self, json_errors: bool) {
+        self.json_errors = json_errors;
+    }
+
+    /// Run the commands in line without adding anything to the history. This is used for --exec.
+    /// Returns the class of the first error, if any command failed (see execute_chain)
+    pub fn run_commands(&mut self, line: &str) -> Result<(), ErrorClass> {
+        self.execute_chain(line, ClickWriter::new());
+        match self.last_error {
+            Some(class) => Err(class),
+            None => Ok(()),
         }
-        let mut first_non_whitespace = 0;
-        for c in line.chars() {
-            if !c.is_whitespace() {
+    }
+
+    /// Run the commands in line, collecting their output rather than printing it. Returns the
+    /// output, and whether the commands succeeded
+    pub fn run_captured(&mut self, line: &str) -> (String, bool) {
+        let output = self
+            .execute_chain(line, ClickWriter::with_buffer(vec![], false))
+            .unwrap_or_default();
+        (
+            String::from_utf8_lossy(&output).into_owned(),
+            self.last_error.is_none(),
+        )
+    }
+
+    /// Open the dashboard, listing objects with list_command
+    pub fn run_dashboard(&mut self, list_command: &str) -> Result<(), ErrorClass> {
+        self.env_mut().set_dashboard_open(true);
+        let result = crate::tui::run_dashboard(self, list_command);
+        self.env_mut().set_dashboard_open(false);
+        if let Err(e) = result {
+            self.report_error("dashboard", &e, &mut ClickWriter::new());
+        }
+        match self.last_error {
+            Some(class) => Err(class),
+            None => Ok(()),
+        }
+    }
+
+    /// Run all the lines in script. Returns the class of the first error, if any line failed
+    pub fn run_script(&mut self, script: Script) -> Result<(), ErrorClass> {
+        if self.script_depth >= MAX_SCRIPT_DEPTH {
+            let msg = format!(
+                "Not running {}: scripts nested more than {} deep",
+                script.name, MAX_SCRIPT_DEPTH
+            );
+            if !self.record_error(&script.name, ErrorClass::Usage, &msg, None) {
+                println!("{msg}");
+            }
+            return Err(ErrorClass::Usage);
+        }
+        self.script_depth += 1;
+        let mut res = Ok(());
+        for (num, line) in script.lines.iter() {
+            if self.env.quit {
                 break;
             }
-            first_non_whitespace += 1;
+            self.execute_chain(line, ClickWriter::new());
+            if let Some(class) = self.last_error {
+                if res.is_ok() {
+                    res = Err(class);
+                }
+                if script.stop_on_error {
+                    println!("Stopping {} after error at line {}", script.name, num);
+                    break;
+                }
+            }
+        }
+        self.script_depth -= 1;
+        self.last_error = res.err();
+        res
+    }
+
+    /// Record that line failed with an error of the specified class. If json errors are enabled,
+    /// the error is printed to stderr as json and true is returned. Otherwise the caller needs to
+    /// print the error.
+    fn record_error(
+        &mut self,
+        line: &str,
+        class: ErrorClass,
+        message: &str,
+        details: Option<&Value>,
+    ) -> bool {
+        self.last_error = Some(class);
+        if self.json_errors {
+            let mut err = json!({
+                "error": class.as_str(),
+                "exitCode": class.exit_code(),
+                "command": line,
+                "message": message,
+            });
+            if let Some(details) = details {
+                err["details"] = details.clone();
+            }
+            eprintln!("{err}");
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Record and print an error returned while running line
+    pub fn report_error(&mut self, line: &str, err: &ClickError, writer: &mut ClickWriter) {
+        let details = match err {
+            ClickError::Reqwest(_, Some(val)) => Some(val),
+            _ => None,
+        };
+        if self.record_error(line, err.class(), &err.to_string(), details) {
+            return;
+        }
+        match details {
+            Some(val) => {
+                let reason = val_str("/reason", val, "no reason given");
+                let msg = val_str("/message", val, "no message returned");
+                clickwriteln!(
+                    writer,
+                    "Error executing request. Reason: {}, Message: {}",
+                    reason,
+                    msg
+                )
+            }
+            None => clickwriteln!(writer, "{}", err),
+        };
+    }
+
+    /// Process the line.  Returns the result of finish_output on the writer
+    pub fn process_line(&mut self, line: &str, writer: ClickWriter) -> Option<Vec<u8>> {
+        if line.is_empty() {
+            self.last_error = None;
+            return writer.finish_output();
         }
-        let lstr = if first_non_whitespace == 0 {
+        if !line.starts_with(char::is_whitespace) {
             // bash semantics: don't add to history if start with space
             if let Err(e) = self.rl.add_history_entry(line) {
                 println!("Couldn't write history entry: {}", e);
             }
-            line
-        } else {
-            &line[first_non_whitespace..]
+        }
+        self.execute_chain(line, writer)
+    }
+
+    /// Execute a chain of commands separated by ;, && or ||. Like in a shell, a command after &&
+    /// only runs if the one before it succeeded, and one after || only runs if it failed. Pipes
+    /// and redirects apply to each command separately. Returns the output of all the writers
+    /// (which is only Some in tests), and sets last_error to the first failure of a ; separated
+    /// part of the chain, so "a; b" fails if either does, but "a || b" succeeds if b does.
+    fn execute_chain(&mut self, line: &str, writer: ClickWriter) -> Option<Vec<u8>> {
+        let chain = match split_chain(line) {
+            Ok(chain) => chain,
+            Err(e) => {
+                if !self.record_error(line, e.class(), &e.to_string(), None) {
+                    println!("{e}");
+                }
+                return writer.finish_output();
+            }
+        };
+        if chain.len() == 1 {
+            return self.execute_line(chain[0].1, writer);
+        }
+
+        let buffered = writer.is_buffer();
+        let mut writer = Some(writer);
+        let mut output: Option<Vec<u8>> = None;
+        let mut first_error = None;
+        // the result of the last command that was run
+        let mut status: Option<ErrorClass> = None;
+        for (op, cmd) in chain.into_iter() {
+            if self.env.quit {
+                break;
+            }
+            let run = match op {
+                ChainOp::Then => {
+                    first_error = first_error.or(status);
+                    status = None;
+                    true
+                }
+                ChainOp::And => status.is_none(),
+                ChainOp::Or => status.is_some(),
+            };
+            if !run {
+                continue;
+            }
+            let cmd_writer = writer.take().unwrap_or_else(|| {
+                if buffered {
+                    ClickWriter::with_buffer(vec![], false)
+                } else {
+                    ClickWriter::new()
+                }
+            });
+            if let Some(out) = self.execute_line(cmd, cmd_writer) {
+                output.get_or_insert_with(Vec::new).extend(out);
+            }
+            status = self.last_error;
+        }
+        if let Some(writer) = writer {
+            // nothing was run, so nothing wrote to the writer
+            output = writer.finish_output();
+        }
+        self.last_error = first_error.or(status);
+        output
+    }
+
+    /// Execute the line without touching history. Returns the result of finish_output on the
+    /// writer, and sets last_error if the line didn't run successfully
+    fn execute_line(&mut self, line: &str, mut writer: ClickWriter) -> Option<Vec<u8>> {
+        self.last_error = None;
+        let lstr = line.trim_start();
+        if lstr.is_empty() {
+            return writer.finish_output();
+        }
+        let substituted = match self.substitute_line(lstr) {
+            Ok(substituted) => substituted,
+            Err(e) => {
+                self.report_error(lstr, &e, &mut writer);
+                return writer.finish_output();
+            }
         };
-        let expanded_line = alias_expand_line(&self.env, lstr);
-        match parse_line(&expanded_line) {
-            Ok((left, right)) => {
+        let mut expanded_line = alias_expand_line(&self.env, &substituted);
+        if expanded_line != substituted {
+            // substitute any variables used in the alias
+            expanded_line = match self.substitute_line(&expanded_line) {
+                Ok(substituted) => substituted,
+                Err(e) => {
+                    self.report_error(lstr, &e, &mut writer);
+                    return writer.finish_output();
+                }
+            };
+        }
+        match parse_line(&expanded_line).and_then(|(left, right)| {
+            take_where_stages(right).map(|(stages, right)| (left, stages, right))
+        }) {
+            Ok((left, where_stages, right)) => {
                 // set up output
                 match right {
                     RightExpr::None => {} // do nothing
                     RightExpr::Pipe(cmd) => {
                         if let Err(e) = writer.setup_pipe(cmd) {
-                            println!("{e}");
+                            if !self.record_error(lstr, e.class(), &e.to_string(), None) {
+                                println!("{e}");
+                            }
                             return writer.finish_output();
                         }
                     }
@@ -300,7 +636,10 @@ impl CommandProcessor {
                             writer.set_output_file(out_file);
                         }
                         Err(ref e) => {
-                            println!("Can't open output file: {e}");
+                            let msg = format!("Can't open output file: {e}");
+                            if !self.record_error(lstr, ErrorClass::General, &msg, None) {
+                                println!("{msg}");
+                            }
                             return writer.finish_output();
                         }
                     },
@@ -310,7 +649,10 @@ impl CommandProcessor {
                                 writer.set_output_file(out_file);
                             }
                             Err(ref e) => {
-                                println!("Can't open output file: {e}");
+                                let msg = format!("Can't open output file: {e}");
+                                if !self.record_error(lstr, ErrorClass::General, &msg, None) {
+                                    println!("{msg}");
+                                }
                                 return writer.finish_output();
                             }
                         }
@@ -319,6 +661,14 @@ impl CommandProcessor {
                 let parts_vec: Vec<String> = Parser::new(left).map(|x| x.2).collect();
                 let mut parts = parts_vec.iter().map(|s| &**s);
                 let env = Rc::get_mut(&mut self.env).unwrap();
+                // with where stages the command's own output is discarded, and the filtered
+                // version of the table it produced is printed instead
+                let mut list_writer = None;
+                let mut prev_list = None;
+                if !where_stages.is_empty() {
+                    list_writer = Some(ClickWriter::with_buffer(vec![], false));
+                    prev_list = env.take_last_objs();
+                }
                 if let Some(cmdstr) = parts.next() {
                     // There was something typed
                     if let Ok(num) = (cmdstr as &str).parse::<usize>() {
@@ -350,43 +700,117 @@ impl CommandProcessor {
                         }
                     } else if let Some(cmd) = self.commands.iter().find(|&c| c.is(cmdstr)) {
                         // found a matching command
-                        if let Err(e) = cmd.exec(env, &mut parts, &mut writer) {
-                            match e {
-                                ClickError::Reqwest(_, Some(val)) => {
-                                    let reason = val_str("/reason", &val, "no reason given");
-                                    let msg = val_str("/message", &val, "no message returned");
-                                    clickwriteln!(
-                                        writer,
-                                        "Error executing request. Reason: {}, Message: {}",
-                                        reason,
-                                        msg
-                                    )
-                                }
-                                _ => clickwriteln!(writer, "{}", e),
-                            };
+                        let out = list_writer.as_mut().unwrap_or(&mut writer);
+                        if let Err(e) = cmd.exec(env, &mut parts, out) {
+                            self.report_error(lstr, &e, &mut writer);
                         }
                     } else if cmdstr == "help" {
-                        self.show_help(&mut parts, &mut writer);
+                        let out = list_writer.as_mut().unwrap_or(&mut writer);
+                        self.show_help(&mut parts, out);
                     } else {
-                        clickwriteln!(writer, "Unknown command");
+                        let msg = format!("Unknown command {cmdstr}");
+                        if !self.record_error(lstr, ErrorClass::Usage, &msg, None) {
+                            clickwriteln!(writer, "Unknown command");
+                        }
                     }
                 }
+                if !where_stages.is_empty() {
+                    self.filter_last_table(lstr, &where_stages, prev_list, &mut writer);
+                }
 
                 // reset output
-                writer.finish_output()
+                let output = writer.finish_output();
+                let script = Rc::get_mut(&mut self.env).unwrap().take_script();
+                if let Some(script) = script {
+                    // run_script sets last_error, so the source line fails if the script did
+                    let _ = self.run_script(script);
+                }
+                let dashboard = Rc::get_mut(&mut self.env).unwrap().take_dashboard_request();
+                if let Some(list_command) = dashboard {
+                    let _ = self.run_dashboard(&list_command);
+                }
+                output
             }
             Err(err) => {
-                println!("{err}");
+                if !self.record_error(lstr, err.class(), &err.to_string(), None) {
+                    println!("{err}");
+                }
                 None
             }
         }
     }
 
+    /// Apply where_stages to the table the command just printed, and print the result. The
+    /// filtered objects become the last list, so ranges select from what was printed. If the
+    /// command didn't produce a table, prev_list is restored and it's an error.
+    fn filter_last_table(
+        &mut self,
+        line: &str,
+        where_stages: &[String],
+        prev_list: Option<(Vec<KObj>, Option<comfy_table::Table>)>,
+        writer: &mut ClickWriter,
+    ) {
+        let env = Rc::get_mut(&mut self.env).unwrap();
+        if self.last_error.is_some() {
+            // the command already reported its failure
+            if env.get_last_objs().is_none() {
+                if let Some((objs, table)) = prev_list {
+                    env.set_last_objs(objs, table);
+                }
+            }
+            return;
+        }
+        let filtered = match env.take_last_objs() {
+            Some((objs, Some(table))) => where_stages
+                .iter()
+                .map(|expr| RowFilter::new(expr, &table))
+                .collect::<Result<Vec<RowFilter>, ClickError>>()
+                .map(|filters| filter_table(&table, &objs, &filters)),
+            _ => Err(ClickError::CommandError(
+                "where can only filter the output of commands that list objects".to_string(),
+            )),
+        };
+        match filtered {
+            Ok((mut table, objs)) => {
+                print_filled_table(&mut table, writer);
+                env.set_last_objs(objs, Some(table));
+            }
+            Err(e) => {
+                if let Some((objs, table)) = prev_list {
+                    env.set_last_objs(objs, table);
+                }
+                self.report_error(line, &e, writer);
+            }
+        }
+    }
+
+    /// Substitute variables and the output of $(command)s into line
+    fn substitute_line(&mut self, line: &str) -> Result<String, ClickError> {
+        substitute_vars(line, |var| match var {
+            VarRef::Name(name) if self.env.has_var(name) => self.env.get_var(name).map(Some),
+            VarRef::Name(_) => Ok(None),
+            VarRef::Capture(cmd) => self.capture_output(cmd).map(Some),
+        })
+    }
+
+    /// Run cmd and return its output, with any trailing newlines removed
+    fn capture_output(&mut self, cmd: &str) -> Result<String, ClickError> {
+        let (output, ok) = self.run_captured(cmd);
+        let output = output.trim_end_matches('\n');
+        if !ok {
+            Err(ClickError::CommandError(format!(
+                "Command in $({cmd}) failed: {output}"
+            )))
+        } else {
+            Ok(output.to_string())
+        }
+    }
+
     fn show_help(&mut self, parts: &mut dyn Iterator<Item = &str>, writer: &mut ClickWriter) {
         // help isn't a command as it needs access to the commands vec
         if let Some(hcmd) = parts.next() {
             if let Some(cmd) = self.commands.iter().find(|&c| c.is(hcmd)) {
-                cmd.write_help(writer);
+                cmd.write_help(&self.env, writer);
             } else {
                 match hcmd {
                     // match for meta topics (add new topics to the ClickHelper above!)
@@ -402,6 +826,9 @@ impl CommandProcessor {
                     "ranges" => {
                         clickwriteln!(writer, "{}", RANGEHELP);
                     }
+                    "prompt" => {
+                        clickwriteln!(writer, "{}", PROMPTHELP);
+                    }
                     _ => {
                         if let Some(alias) = self.env.get_alias(hcmd) {
                             clickwriteln!(writer, "{} is an alias for '{}'", hcmd, alias.expanded);
@@ -440,6 +867,11 @@ impl CommandProcessor {
                 "  edit_mode           Available edit_mode values for \
                  the 'set' command, and what they mean"
             );
+            clickwriteln!(
+                writer,
+                "  prompt              Changing what the prompt shows with \
+                 'set prompt'"
+            );
             clickwriteln!(
                 writer,
                 "  ranges              Selecting and operating on multiple \
@@ -464,7 +896,25 @@ Examples:\n\
  # Save logs to logs.txt:\n\
  logs my-cont > /tmp/logs.txt\n\n\
  # Append log lines that contain \"foo bar\" to logs.txt\n\
- logs the-cont | grep \"foo bar\" >> /tmp/logs.txt";
+ logs the-cont | grep \"foo bar\" >> /tmp/logs.txt\n\n\
+The output of commands that list objects can be filtered with '| where EXPR' before any shell \
+pipe. EXPR is either a regex matched against every column, or a column name, an operator (=, !=, \
+~, !~, <, <=, > or >=) and a value. Columns that hold numbers or ages are compared numerically. \
+Quote expressions that contain spaces, < or >. The filtered rows are renumbered, so ranges select \
+from what was printed.\n
+Examples:\n\
+ # list crashing pods and select all of them\n\
+ pods | where CrashLoop; 0..\n\n\
+ # running pods that have restarted more than 3 times\n\
+ pods | where status=Running | where 'restarts > 3'\n\n\
+Commands can be chained with ';', '&&' and '||'. Commands after ';' always run, commands after \
+'&&' only run if the previous command succeeded, and commands after '||' only run if it failed. \
+Pipes and redirects apply only to the command they follow.\n
+Examples:\n\
+ # switch namespace, select the first dns pod, and get its logs\n\
+ ns kube-system; pods -l k8s-app=kube-dns; 0; logs\n\n\
+ # delete the selected pod, then list the remaining pods if that worked\n\
+ delete && pods";
 
 static COMPLETIONHELP: &str = "There are two completion types: list or circular.
 - list: complete the next full match (like in Vim by default) (do: set completion list)
@@ -477,6 +927,34 @@ associated editor.
 - 'vi' Hit ESC while editing to edit the line using common vi keybindings (do: 'set edit_mode vi')
 - 'emacs' Use standard readline/bash/emacs keybindings (do: 'set edit_mode emacs')";
 
+static PROMPTHELP: &str = "The prompt is set with 'set prompt TEMPLATE' (or 'unset prompt' to go \
+back to the default). The template is text with these placeholders:
+  {context}     the current context
+  {namespace}   the current namespace
+  {selection}   the selected object, or the range of selected objects
+  {type}        the type of the selected object (Pod, Node, ...), or 'range'
+  {user}        the user being impersonated
+  {version}     the server version of the cluster
+  {forwards}    the number of active port forwards
+  {status}      'ok' or 'failed' for the last command
+  {duration}    how long the last command took
+  {time}        the current time
+
+A placeholder can be styled with {name:style}, where style is a list of colors and attributes as \
+in the theme section of the config, or names of styles from the theme, like {user:danger reverse}. \
+Text in parentheses is only shown if every placeholder in it has a value. Use \\ before a {, }, ( \
+or ) to include it literally.
+
+Examples:
+ # the default prompt
+ set prompt '[{context}] [{namespace}] [{selection}] > '
+
+ # a compact prompt that makes impersonation obvious
+ set prompt '{context}:{namespace}( as {user:danger reverse}) {selection}> '
+
+ # show the time, and how the last command went
+ set prompt '{time} ({status} in {duration}) [{context}] > '";
+
 // TODO: Something better than raw escapes maybe?
 static RANGEHELP: &str = "\u{001b}[33;1mRANGES\u{001b}[0m
 Ranges are used to operate on more than one object at a time.
@@ -508,15 +986,29 @@ You can specify a list of items to select like: '1,3,12' to select items 1, 3, a
 Note that if you want to include spaces, you'll need to quote the string like:
 \"1, 3,  12\"
 
+\u{001b}[32mSelecting by Name or Status\u{001b}[0m
+The 'select' command builds a range without needing to read off numbers:
+select /regex/                    # objects whose names match the regex
+select --status CrashLoopBackOff  # objects with that value in the Status column
+select --where 'restarts > 3'     # objects whose row matches a column expression
+select --all                      # everything in the list
+
 \u{001b}[33;1mPRINTING THE CURRENT RANGE\u{001b}[0m
 The 'range' command will print out a table of objects in the current range. This is useful
 to verify your commands will operate on the objects you expect.
 
+\u{001b}[33;1mCHANGING THE CURRENT RANGE\u{001b}[0m
+'range add', 'range remove' and 'range intersect' change the current range. They take indexes,
+ranges and comma separated lists from the last list of objects, or /regex/s to match names:
+range add 5          # add item 5 to the range
+range remove 2..4    # remove items 2 and 3
+range intersect /api-/  # keep only objects with api- in their name
+
 \u{001b}[33;1mCOMMANDS ON RANGES\u{001b}[0m
 Once you have selected a range, you can run any of the following commands which will operate on each
 item in the range in turn:
-
-containers, describe, delete, events, exec, logs
+configmap, containers, describe, delete, events, exec, logs, namespace-check, pods-of,
+remove-finalizers, secret, service-check
 
 \u{001b}[33;1mRANGE SEPARATOR\u{001b}[0m
 When printing output for the above commands over a range, Click will print a header for each item.
@@ -579,7 +1071,7 @@ mod tests {
             "testcmd"
         }
 
-        fn write_help(&self, writer: &mut ClickWriter) {
+        fn write_help(&self, _env: &Env, writer: &mut ClickWriter) {
             clickwriteln!(writer, "HELP");
         }
 
@@ -656,6 +1148,7 @@ mod tests {
 Other help topics (type 'help [TOPIC]' for details)
   completion          Available completion_type values for the 'set' command, and what they mean
   edit_mode           Available edit_mode values for the 'set' command, and what they mean
+  prompt              Changing what the prompt shows with 'set prompt'
   ranges              Selecting and operating on multiple objects at once
   shell               Redirecting and piping click output to shell commands\n"
                 .as_bytes()
@@ -669,6 +1162,8 @@ Other help topics (type 'help [TOPIC]' for details)
         let writer = ClickWriter::with_buffer(buf, false);
         let res = p.process_line("blah", writer).unwrap();
         assert_eq!(res, "Unknown command\n".as_bytes());
+        assert_eq!(p.last_error, Some(ErrorClass::Usage));
+        assert_eq!(p.run_commands("testcmd; blah"), Err(ErrorClass::Usage));
     }
 
     #[test]
@@ -842,6 +1337,143 @@ Other help topics (type 'help [TOPIC]' for details)
         dir.close().unwrap();
     }
 
+    #[test]
+    fn chaining() {
+        let mut p = get_processor();
+        let mut run = |line: &str| {
+            let writer = ClickWriter::with_buffer(vec![], false);
+            let res = p.process_line(line, writer).unwrap();
+            (String::from_utf8(res).unwrap(), p.last_error)
+        };
+
+        assert_eq!(
+            run("testcmd a; testcmd b"),
+            ("Called with aCalled with b".to_string(), None)
+        );
+        assert_eq!(
+            run("blah && testcmd a"),
+            ("Unknown command\n".to_string(), Some(ErrorClass::Usage))
+        );
+        assert_eq!(
+            run("blah || testcmd a"),
+            ("Unknown command\nCalled with a".to_string(), None)
+        );
+        assert_eq!(
+            run("testcmd a || testcmd b && testcmd c"),
+            ("Called with aCalled with c".to_string(), None)
+        );
+        assert_eq!(
+            run("blah; testcmd a"),
+            (
+                "Unknown command\nCalled with a".to_string(),
+                Some(ErrorClass::Usage)
+            )
+        );
+        assert_eq!(
+            run("testcmd 'a;b' && testcmd \"c || d\""),
+            ("Called with a;bCalled with c || d".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn variables() {
+        let mut p = get_processor();
+        p.env_mut().set_var("x", "a b".to_string()).unwrap();
+        let mut run = |line: &str| {
+            let writer = ClickWriter::with_buffer(vec![], false);
+            let res = p.process_line(line, writer).unwrap();
+            (String::from_utf8(res).unwrap(), p.last_error)
+        };
+
+        assert_eq!(run("testcmd $x"), ("Called with a b".to_string(), None));
+        assert_eq!(run("testcmd '$x'"), ("Called with $x".to_string(), None));
+        assert_eq!(
+            run("testcmd $(testcmd foo)"),
+            ("Called with Called with foo".to_string(), None)
+        );
+        // unknown variables are passed through, for the shell after a pipe to substitute
+        assert_eq!(
+            run("testcmd $nope"),
+            ("Called with $nope".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn where_stages() {
+        let (stages, right) = take_where_stages(RightExpr::Pipe(" where CrashLoop")).unwrap();
+        assert_eq!(stages, vec!["CrashLoop".to_string()]);
+        assert_eq!(right, RightExpr::None);
+
+        let (_, right) = parse_line("pods | where 'restarts > 3' | where web | grep x").unwrap();
+        let (stages, right) = take_where_stages(right).unwrap();
+        assert_eq!(stages, vec!["restarts > 3".to_string(), "web".to_string()]);
+        assert_eq!(right, RightExpr::Pipe(" grep x"));
+
+        let (_, right) = parse_line("pods | where status=Running > /tmp/out").unwrap();
+        let (stages, right) = take_where_stages(right).unwrap();
+        assert_eq!(stages, vec!["status=Running".to_string()]);
+        assert_eq!(right, RightExpr::Redir("/tmp/out"));
+
+        // not a where stage
+        let (stages, right) = take_where_stages(RightExpr::Pipe(" wherever")).unwrap();
+        assert!(stages.is_empty());
+        assert_eq!(right, RightExpr::Pipe(" wherever"));
+
+        assert!(take_where_stages(RightExpr::Pipe(" where ")).is_err());
+    }
+
+    #[test]
+    fn where_needs_a_list() {
+        let mut p = get_processor();
+        p.env_mut().set_last_objs(vec![make_node_kobj("ns1")], None);
+        let writer = ClickWriter::with_buffer(vec![], false);
+        let res = p.process_line("testcmd | where foo", writer).unwrap();
+        assert_eq!(
+            String::from_utf8(res).unwrap(),
+            "Error running command: where can only filter the output of commands that list objects\n"
+        );
+        assert_eq!(p.last_error, Some(ErrorClass::General));
+        // the previous list is still there
+        assert_eq!(p.env.item_at(0).unwrap().name, "ns1");
+    }
+
+    #[test]
+    fn run_script() {
+        let dir = tempdir::TempDir::new("click_test_dir").unwrap();
+        let out_path = dir.path().join("script_out.txt");
+        let out = out_path.to_str().unwrap();
+        let contents = format!("# a comment\ntestcmd a >> {out}\n\nblah\ntestcmd b >> {out}\n");
+
+        let mut p = get_processor();
+        assert_eq!(
+            p.run_script(Script::from_contents("test", &contents, true)),
+            Err(ErrorClass::Usage)
+        );
+        assert_eq!(std::fs::read_to_string(&out_path).unwrap(), "Called with a");
+
+        std::fs::remove_file(&out_path).unwrap();
+        assert_eq!(
+            p.run_script(Script::from_contents("test", &contents, false)),
+            Err(ErrorClass::Usage)
+        );
+        assert_eq!(
+            std::fs::read_to_string(&out_path).unwrap(),
+            "Called with aCalled with b"
+        );
+
+        std::fs::remove_file(&out_path).unwrap();
+        assert_eq!(
+            p.run_commands(&format!("testcmd a >> {out}; testcmd b >> {out}")),
+            Ok(())
+        );
+        assert_eq!(
+            std::fs::read_to_string(&out_path).unwrap(),
+            "Called with aCalled with b"
+        );
+
+        dir.close().unwrap();
+    }
+
     #[test]
     fn unexpected_chars() {
         let p = parse_line("test || this");
@@ -923,10 +1555,30 @@ Other help topics (type 'help [TOPIC]' for details)
             alias: "z".to_string(),
             expanded: "x arg".to_string(),
         };
+        let restarts = Alias {
+            alias: "restarts".to_string(),
+            expanded: "pods --sort restarts -r ${1:-.} $2".to_string(),
+        };
+        let param_chain = Alias {
+            alias: "r".to_string(),
+            expanded: "restarts $1 -n".to_string(),
+        };
+        let loop_a = Alias {
+            alias: "la".to_string(),
+            expanded: "lb $1".to_string(),
+        };
+        let loop_b = Alias {
+            alias: "lb".to_string(),
+            expanded: "la".to_string(),
+        };
         cc.aliases.push(pn_alias);
         cc.aliases.push(x_alias);
         cc.aliases.push(x_chain);
         cc.aliases.push(x_chain_arg);
+        cc.aliases.push(restarts);
+        cc.aliases.push(param_chain);
+        cc.aliases.push(loop_a);
+        cc.aliases.push(loop_b);
         let env = Env::new(get_test_config(), cc, PathBuf::from("/tmp/click.config"));
 
         assert_eq!(alias_expand_line(&env, "pn"), "pods --sort node");
@@ -957,5 +1609,28 @@ Other help topics (type 'help [TOPIC]' for details)
         assert_eq!(alias_expand_line(&env, "y x"), "xpand x");
 
         assert_eq!(alias_expand_line(&env, "z x"), "xpand arg x");
+
+        assert_eq!(
+            alias_expand_line(&env, "restarts nginx"),
+            "pods --sort restarts -r nginx "
+        );
+
+        assert_eq!(
+            alias_expand_line(&env, "restarts nginx -R | grep x"),
+            "pods --sort restarts -r nginx -R | grep x"
+        );
+
+        assert_eq!(
+            alias_expand_line(&env, "restarts"),
+            "pods --sort restarts -r . "
+        );
+
+        assert_eq!(
+            alias_expand_line(&env, "r web"),
+            "pods --sort restarts -r web -n"
+        );
+
+        // mutually recursive aliases stop expanding once they'd repeat
+        assert_eq!(alias_expand_line(&env, "la foo"), "la foo");
     }
 }
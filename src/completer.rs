@@ -22,7 +22,7 @@ use rustyline::{
 };
 
 use crate::command::command_def::Cmd;
-use crate::env::Env;
+use crate::env::{Env, ObjectSelection};
 use crate::kobj::ObjType;
 
 use std::rc::Rc;
@@ -54,11 +54,12 @@ fn get_command_completion_strings(commands: &[Box<dyn Cmd>], env: Option<&Rc<Env
         v.push(cmd.get_name().to_string());
     }
     if let Some(env) = env.as_ref() {
-        for alias in env.click_config.aliases.iter() {
+        for alias in env.click_config.aliases.iter().chain(env.context_aliases()) {
             v.push(alias.alias.to_string());
         }
     }
     v.sort_unstable();
+    v.dedup();
     v
 }
 
@@ -264,30 +265,91 @@ pub fn context_complete(prefix: &str, env: &Env) -> Vec<Pair> {
     v
 }
 
+/// Turn the names that start with prefix into completions
+fn complete_names(names: Vec<String>, prefix: &str) -> Vec<Pair> {
+    names
+        .into_iter()
+        .filter_map(|name| {
+            name.strip_prefix(prefix).map(|rest| Pair {
+                replacement: rest.to_string(),
+                display: name.clone(),
+            })
+        })
+        .collect()
+}
+
 pub fn namespace_completer(prefix: &str, env: &Env) -> Vec<Pair> {
-    let (request, _response_body) = api::Namespace::list_namespace(Default::default()).unwrap();
-    match env.run_on_context::<_, List<api::Namespace>>(|c| {
-        c.execute_list(env.get_impersonate_user(), request)
-    }) {
-        Ok(nslist) => nslist
+    let names = env.cached_completions("namespaces", |env| {
+        let (request, _response_body) = api::Namespace::list_namespace(Default::default())?;
+        let nslist = env.run_on_context::<_, List<api::Namespace>>(|c| {
+            c.execute_list(env.get_impersonate_user(), request)
+        })?;
+        Ok(nslist
             .items
             .into_iter()
-            .filter_map(|ns| {
-                ns.metadata.name.and_then(|name| {
-                    if let Some(rep) = name.strip_prefix(prefix) {
-                        let replacement = rep.to_string();
-                        Some(Pair {
-                            display: name,
-                            replacement,
-                        })
-                    } else {
-                        None
-                    }
-                })
-            })
-            .collect(),
-        Err(_) => vec![],
-    }
+            .filter_map(|ns| ns.metadata.name)
+            .collect())
+    });
+    complete_names(names, prefix)
+}
+
+/// Complete the names of pods in the current namespace (or all namespaces if none is set)
+pub fn pod_completer(prefix: &str, env: &Env) -> Vec<Pair> {
+    let key = format!("pods/{}", env.namespace.as_deref().unwrap_or(""));
+    let names = env.cached_completions(&key, |env| {
+        let (request, _response_body) = match &env.namespace {
+            Some(ns) => api::Pod::list_namespaced_pod(ns, Default::default())?,
+            None => api::Pod::list_pod_for_all_namespaces(Default::default())?,
+        };
+        let podlist = env.run_on_context::<_, List<api::Pod>>(|c| {
+            c.execute_list(env.get_impersonate_user(), request)
+        })?;
+        Ok(podlist
+            .items
+            .into_iter()
+            .filter_map(|pod| pod.metadata.name)
+            .collect())
+    });
+    complete_names(names, prefix)
+}
+
+/// Complete the names of resources found via api discovery, like the ones defined by crds
+pub fn crd_resource_completer(prefix: &str, env: &Env) -> Vec<Pair> {
+    let names = env.cached_completions("api-resources", crate::crd::get_api_resource_names);
+    complete_names(names, prefix)
+}
+
+/// Complete the keys of the selected secret
+pub fn secret_key_completer(prefix: &str, env: &Env) -> Vec<Pair> {
+    let obj = match env.current_selection() {
+        ObjectSelection::Single(obj) if obj.is(ObjType::Secret) => obj,
+        _ => return vec![],
+    };
+    let key = format!(
+        "secret-keys/{}/{}",
+        obj.namespace.as_deref().unwrap_or(""),
+        obj.name()
+    );
+    let names = env.cached_completions(&key, |env| {
+        let secret = crate::command::secrets::read_secret(obj, env)?;
+        Ok(secret
+            .data
+            .map(|data| data.into_keys().collect())
+            .unwrap_or_default())
+    });
+    complete_names(names, prefix)
+}
+
+/// Complete the names of the objects in the last list
+pub fn last_objs_completer(prefix: &str, env: &Env) -> Vec<Pair> {
+    let mut names: Vec<String> = env
+        .get_last_objs()
+        .map(|objs| objs.iter().map(|obj| obj.name().to_string()).collect())
+        .unwrap_or_default();
+    // objects in different namespaces can have the same name
+    names.sort_unstable();
+    names.dedup();
+    complete_names(names, prefix)
 }
 
 pub fn container_completer(prefix: &str, env: &Env) -> Vec<Pair> {
@@ -334,3 +396,23 @@ possible_values_completer!(
     portforwardaction_values_completer,
     ["list", "output", "stop"]
 );
+
+possible_values_completer!(
+    configmapaction_values_completer,
+    crate::command::configmaps::CONFIGMAP_ACTIONS
+);
+
+possible_values_completer!(
+    secretaction_values_completer,
+    crate::command::secrets::SECRET_ACTIONS
+);
+
+possible_values_completer!(
+    deletekind_values_completer,
+    crate::command::delete::collection_kinds()
+);
+
+possible_values_completer!(
+    sessionaction_values_completer,
+    crate::command::session::SESSION_ACTIONS
+);
@@ -16,11 +16,13 @@
 use atomicwrites::{AllowOverwrite, AtomicFile};
 use rustyline::config as rustyconfig;
 
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fs::File;
 use std::io::Read;
 
 use crate::error::ClickError;
+use crate::parser::{quote_arg, Parser};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 pub struct Alias {
@@ -28,6 +30,157 @@ pub struct Alias {
     pub expanded: String,
 }
 
+/// The parts of an alias expansion, split up so the parameters can be filled in
+#[derive(Debug, PartialEq)]
+enum AliasPiece<'a> {
+    Text(&'a str),
+    /// $N or ${N:-default}
+    Arg {
+        index: usize,
+        default: Option<&'a str>,
+    },
+    /// $@
+    AllArgs,
+}
+
+// try and parse the parameter at the start of s, which starts with a $. Returns the piece and how
+// many bytes it used
+fn parse_alias_param(s: &str) -> Option<(AliasPiece<'_>, usize)> {
+    let rest = &s[1..];
+    if rest.starts_with('@') {
+        return Some((AliasPiece::AllArgs, 2));
+    }
+    if let Some(braced) = rest.strip_prefix('{') {
+        let end = braced.find('}')?;
+        let inner = &braced[..end];
+        let (num, default) = match inner.split_once(":-") {
+            Some((num, default)) => (num, Some(default)),
+            None => (inner, None),
+        };
+        let index = num.parse::<usize>().ok().filter(|i| *i > 0)?;
+        // ${ + inner + }
+        return Some((AliasPiece::Arg { index, default }, end + 3));
+    }
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let index = rest[..digits].parse::<usize>().ok().filter(|i| *i > 0)?;
+    Some((
+        AliasPiece::Arg {
+            index,
+            default: None,
+        },
+        digits + 1,
+    ))
+}
+
+impl Alias {
+    fn pieces(&self) -> Vec<AliasPiece<'_>> {
+        let mut pieces = vec![];
+        let mut text_start = 0;
+        let mut pos = 0;
+        while let Some(off) = self.expanded[pos..].find('$') {
+            let dollar = pos + off;
+            match parse_alias_param(&self.expanded[dollar..]) {
+                Some((piece, len)) => {
+                    if dollar > text_start {
+                        pieces.push(AliasPiece::Text(&self.expanded[text_start..dollar]));
+                    }
+                    pieces.push(piece);
+                    pos = dollar + len;
+                    text_start = pos;
+                }
+                None => pos = dollar + 1,
+            }
+        }
+        if text_start < self.expanded.len() {
+            pieces.push(AliasPiece::Text(&self.expanded[text_start..]));
+        }
+        pieces
+    }
+
+    /// Get a description of each parameter this alias uses, like "$1" or "$2 (default: pods)"
+    pub fn params(&self) -> Vec<String> {
+        let mut params: Vec<String> = vec![];
+        for piece in self.pieces() {
+            let param = match piece {
+                AliasPiece::Text(_) => continue,
+                AliasPiece::Arg {
+                    index,
+                    default: None,
+                } => format!("${index}"),
+                AliasPiece::Arg {
+                    index,
+                    default: Some(default),
+                } => format!("${index} (default: {default})"),
+                AliasPiece::AllArgs => "$@".to_string(),
+            };
+            if !params.contains(&param) {
+                params.push(param);
+            }
+        }
+        params
+    }
+
+    pub fn is_parameterized(&self) -> bool {
+        self.pieces()
+            .iter()
+            .any(|p| !matches!(p, AliasPiece::Text(_)))
+    }
+
+    /// Fill in the parameters of this alias with the args at the start of rest. Args stop at the
+    /// first | or >, so pipes and redirects still apply to the expanded command. Args that aren't
+    /// used by a $N parameter are added to the end, unless the alias uses $@. Returns the expanded
+    /// string and what's left of rest.
+    pub fn expand_params<'a>(&self, rest: &'a str) -> (String, &'a str) {
+        let mut args = vec![];
+        let mut tail = "";
+        for (range, sep, arg) in Parser::new(rest) {
+            let is_sep = sep == '|' || sep == '>';
+            if !is_sep || !arg.is_empty() {
+                args.push(arg);
+            }
+            if is_sep {
+                tail = &rest[range.end..];
+                break;
+            }
+        }
+
+        let pieces = self.pieces();
+        let mut expanded = String::new();
+        let mut max_index = 0;
+        let mut all_used = false;
+        for piece in pieces.iter() {
+            match piece {
+                AliasPiece::Text(text) => expanded.push_str(text),
+                AliasPiece::Arg { index, default } => {
+                    max_index = max_index.max(*index);
+                    match (args.get(index - 1), default) {
+                        (Some(arg), _) => expanded.push_str(&quote_arg(arg)),
+                        (None, Some(default)) => expanded.push_str(default),
+                        (None, None) => {}
+                    }
+                }
+                AliasPiece::AllArgs => {
+                    all_used = true;
+                    let quoted: Vec<String> = args.iter().map(|a| quote_arg(a)).collect();
+                    expanded.push_str(&quoted.join(" "));
+                }
+            }
+        }
+        if !all_used {
+            for arg in args.iter().skip(max_index) {
+                expanded.push(' ');
+                expanded.push_str(&quote_arg(arg));
+            }
+        }
+        if !tail.is_empty() {
+            expanded.push(' ');
+        }
+        (expanded, tail)
+    }
+}
+
 #[derive(Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub enum EditMode {
     #[default]
@@ -119,6 +272,101 @@ pub struct ClickConfig {
 
     #[serde(default = "default_describe_include_events")]
     pub describe_include_events: bool,
+
+    #[serde(default)]
+    pub restore_session: bool,
+
+    pub prompt: Option<String>,
+
+    /// Settings that apply when a particular context is active, keyed by context name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub contexts: BTreeMap<String, ContextSettings>,
+
+    /// Extra columns for list commands, keyed by resource kind (like Pod) or its plural (pods)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub columns: BTreeMap<String, Vec<ColumnConfig>>,
+
+    #[serde(default, skip_serializing_if = "ThemeConfig::is_empty")]
+    pub theme: ThemeConfig,
+}
+
+/// How careful click is with commands that change the cluster (delete, edit, exec, etc.)
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SafetyLevel {
+    /// Commands run as usual
+    #[default]
+    Normal,
+    /// Commands ask for confirmation before changing anything
+    Confirm,
+    /// Commands that change the cluster are refused
+    ReadOnly,
+}
+
+impl fmt::Display for SafetyLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                SafetyLevel::Normal => "normal",
+                SafetyLevel::Confirm => "confirm",
+                SafetyLevel::ReadOnly => "read-only",
+            }
+        )
+    }
+}
+
+/// Settings for one context, from the contexts section of the config
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ContextSettings {
+    /// The namespace to switch to when switching to this context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// The style of the context in the prompt, overriding the prompt_context style of the theme
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Aliases that are only defined in this context. These take precedence over global aliases
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<Alias>,
+    #[serde(default)]
+    pub safety: SafetyLevel,
+    /// If true, commands that change the cluster require typing the context name to confirm
+    #[serde(default)]
+    pub protected: bool,
+}
+
+/// A column defined in the columns section of the config
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ColumnConfig {
+    pub name: String,
+    /// A JSON pointer like /spec/nodeName, or a JSONPath expression like .spec.nodeName
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub colors: Vec<ColorRule>,
+}
+
+/// Color a column's cell with style when its text matches the regex in value
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ColorRule {
+    pub value: String,
+    pub style: String,
+}
+
+/// The theme section of the config. base is one of the built-in themes, and each entry in styles
+/// overrides one style of it, like `Pod: "cyan bold"`
+#[derive(Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ThemeConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub styles: BTreeMap<String, String>,
+}
+
+impl ThemeConfig {
+    pub fn is_empty(&self) -> bool {
+        self.base.is_none() && self.styles.is_empty()
+    }
 }
 
 impl Default for ClickConfig {
@@ -136,6 +384,11 @@ impl Default for ClickConfig {
             connect_timeout_secs: default_connect_timeout(),
             read_timeout_secs: default_read_timeout(),
             describe_include_events: true,
+            restore_session: false,
+            prompt: None,
+            contexts: BTreeMap::new(),
+            columns: BTreeMap::new(),
+            theme: ThemeConfig::default(),
         }
     }
 }
@@ -193,7 +446,18 @@ editmode: Vi
 completiontype: List
 aliases:
   - alias: pn
-    expanded: pods --sort node";
+    expanded: pods --sort node
+contexts:
+  prod:
+    namespace: web
+    color: red bold
+    safety: read-only
+    aliases:
+      - alias: p
+        expanded: pods -l app=web
+  dev:
+    safety: confirm
+    protected: true";
 
     pub fn get_parsed_test_click_config() -> ClickConfig {
         ClickConfig::from_reader(TEST_CONFIG.as_bytes()).unwrap()
@@ -218,6 +482,16 @@ aliases:
         assert_eq!(a.expanded, "pods --sort node");
         assert_eq!(config.connect_timeout_secs, default_connect_timeout());
         assert_eq!(config.read_timeout_secs, default_read_timeout());
+        let prod = &config.contexts["prod"];
+        assert_eq!(prod.namespace, Some("web".to_owned()));
+        assert_eq!(prod.color, Some("red bold".to_owned()));
+        assert_eq!(prod.safety, SafetyLevel::ReadOnly);
+        assert_eq!(prod.aliases[0].alias, "p");
+        let dev = &config.contexts["dev"];
+        assert_eq!(dev.safety, SafetyLevel::Confirm);
+        assert_eq!(dev.namespace, None);
+        assert!(dev.protected);
+        assert!(!prod.protected);
     }
 
     #[test]
@@ -229,6 +503,62 @@ aliases:
         assert_eq!(config.read_timeout_secs, default_read_timeout());
         assert_eq!(config.connect_timeout_secs, default_connect_timeout());
         assert_eq!(config.range_separator, default_range_sep());
+        assert!(!config.restore_session);
+    }
+
+    #[test]
+    fn test_alias_params() {
+        let alias = |expanded: &str| Alias {
+            alias: "a".to_string(),
+            expanded: expanded.to_string(),
+        };
+
+        let plain = alias("pods --sort node");
+        assert!(!plain.is_parameterized());
+        assert!(plain.params().is_empty());
+
+        let restarts = alias("pods --sort restarts -r $1");
+        assert!(restarts.is_parameterized());
+        assert_eq!(restarts.params(), vec!["$1"]);
+        assert_eq!(
+            restarts.expand_params(" nginx"),
+            ("pods --sort restarts -r nginx".to_string(), "")
+        );
+        // unused args go on the end, and pipes aren't consumed
+        assert_eq!(
+            restarts.expand_params(" nginx -n | grep foo"),
+            (
+                "pods --sort restarts -r nginx -n ".to_string(),
+                "| grep foo"
+            )
+        );
+        // args with spaces get quoted
+        assert_eq!(
+            restarts.expand_params(" \"a b\" 'it'\\''s'"),
+            ("pods --sort restarts -r 'a b' 'it'\\''s'".to_string(), "")
+        );
+        assert_eq!(
+            restarts.expand_params(""),
+            ("pods --sort restarts -r ".to_string(), "")
+        );
+
+        let defaults = alias("logs ${2:--t 100} $1 $1 $ $0 ${x}");
+        assert_eq!(defaults.params(), vec!["$2 (default: -t 100)", "$1"]);
+        assert_eq!(
+            defaults.expand_params(" c1"),
+            ("logs -t 100 c1 c1 $ $0 ${x}".to_string(), "")
+        );
+        assert_eq!(
+            defaults.expand_params(" c1 -f"),
+            ("logs -f c1 c1 $ $0 ${x}".to_string(), "")
+        );
+
+        let all = alias("exec $@ --");
+        assert_eq!(all.params(), vec!["$@"]);
+        assert_eq!(
+            all.expand_params(" ls -l > out"),
+            ("exec ls -l -- ".to_string(), "> out")
+        );
     }
 
     #[test]
@@ -432,7 +432,7 @@ bW1EDp3zdHSo1TRJ6V6e6bR64eVaH4QwnNOfpSXY
         }
     }
 
-    fn get_config_from_kubefile_test_conf() -> Config {
+    pub fn get_config_from_kubefile_test_conf() -> Config {
         let kube_config = crate::config::kubefile::tests::get_parsed_test_config();
         Config::from_configs(vec![kube_config], "test".to_string()).unwrap() // ok, in test
     }
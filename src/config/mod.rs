@@ -15,17 +15,23 @@
 mod click;
 mod kube;
 mod kubefile;
+mod session;
 
 pub use self::click::default_range_sep;
 pub use self::click::Alias;
 pub use self::click::ClickConfig;
+pub use self::click::ColumnConfig;
 pub use self::click::CompletionType;
 pub use self::click::EditMode;
+pub use self::click::ThemeConfig;
+pub use self::click::{ContextSettings, SafetyLevel};
 
 #[cfg(test)]
-pub use self::kube::tests::get_test_config;
+pub use self::kube::tests::{get_config_from_kubefile_test_conf, get_test_config};
 pub use self::kube::Config;
 
 pub use self::kubefile::AuthProvider;
 pub use self::kubefile::ContextConf;
 pub use self::kubefile::{ExecAuth, ExecProvider};
+
+pub use self::session::{SavedPortForward, SavedTable, Session, LAST_SESSION};
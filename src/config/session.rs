@@ -0,0 +1,208 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Saved click sessions, so a session can be picked up again after click exits
+
+use atomicwrites::{AllowOverwrite, AtomicFile};
+use comfy_table::{Cell, Table};
+
+use crate::env::ObjectSelection;
+use crate::error::ClickError;
+use crate::kobj::KObj;
+use crate::table::UTF8_TABLE_STYLE;
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// The name of the session that's saved on exit, and restored on start if `restore_session` is set
+pub const LAST_SESSION: &str = "last";
+
+const SESSION_DIR: &str = "click_sessions";
+const SESSION_EXT: &str = "yaml";
+
+fn default_selection() -> ObjectSelection {
+    ObjectSelection::None
+}
+
+/// The contents of a table, without any styling
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct SavedTable {
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl SavedTable {
+    pub fn from_table(table: &Table) -> SavedTable {
+        SavedTable {
+            header: table
+                .header()
+                .map(|h| h.cell_iter().map(|c| c.content()).collect())
+                .unwrap_or_default(),
+            rows: table
+                .row_iter()
+                .map(|r| r.cell_iter().map(|c| c.content()).collect())
+                .collect(),
+        }
+    }
+
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.load_preset(UTF8_TABLE_STYLE);
+        table.set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+        table.set_header(&self.header);
+        for row in self.rows.iter() {
+            table.add_row(row.iter().map(Cell::new).collect::<Vec<Cell>>());
+        }
+        table
+    }
+}
+
+/// A port forward that was running when the session was saved
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct SavedPortForward {
+    pub pod: String,
+    pub namespace: String,
+    pub ports: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Session {
+    pub context: Option<String>,
+    pub namespace: Option<String>,
+    #[serde(default = "default_selection")]
+    pub selection: ObjectSelection,
+    pub last_objs: Option<Vec<KObj>>,
+    pub last_table: Option<SavedTable>,
+    #[serde(default = "Vec::new")]
+    pub port_forwards: Vec<SavedPortForward>,
+}
+
+impl Session {
+    /// Get the path a session with the specified name is saved to
+    pub fn path_for(conf_dir: &Path, name: &str) -> Result<PathBuf, ClickError> {
+        if name.is_empty() || name.contains(std::path::is_separator) || name.starts_with('.') {
+            return Err(ClickError::CommandError(format!(
+                "Invalid session name: {name}"
+            )));
+        }
+        let mut path = conf_dir.join(SESSION_DIR);
+        path.push(format!("{name}.{SESSION_EXT}"));
+        Ok(path)
+    }
+
+    /// List the names of all saved sessions
+    pub fn list(conf_dir: &Path) -> Result<Vec<String>, ClickError> {
+        let dir = conf_dir.join(SESSION_DIR);
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut names = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) == Some(SESSION_EXT) {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    names.push(stem.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn from_reader<R>(r: R) -> Result<Session, ClickError>
+    where
+        R: Read,
+    {
+        serde_yaml::from_reader(r).map_err(ClickError::from)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Session, ClickError> {
+        let f = File::open(path)?;
+        Session::from_reader(f)
+    }
+
+    /// Save this session to specified path, creating the session dir if needed
+    pub fn save_to_file(&self, path: &Path) -> Result<(), ClickError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let af = AtomicFile::new(path, AllowOverwrite);
+        af.write(|f| serde_yaml::to_writer(f, &self)).map_err(|e| {
+            ClickError::ConfigFileError(format!("Failed to write session file: {e}"))
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kobj::ObjType;
+
+    fn test_session() -> Session {
+        let pod = KObj {
+            name: "pod1".to_string(),
+            namespace: Some("ns".to_string()),
+            typ: ObjType::Pod {
+                containers: vec!["c1".to_string()],
+            },
+        };
+        let mut table = Table::new();
+        table.set_header(vec!["####", "Name"]);
+        table.add_row(vec!["0", "pod1"]);
+        Session {
+            context: Some("ctx".to_string()),
+            namespace: Some("ns".to_string()),
+            selection: ObjectSelection::Single(pod.clone()),
+            last_objs: Some(vec![pod]),
+            last_table: Some(SavedTable::from_table(&table)),
+            port_forwards: vec![SavedPortForward {
+                pod: "pod1".to_string(),
+                namespace: "ns".to_string(),
+                ports: vec!["8080:80".to_string()],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_session_roundtrip() {
+        let session = test_session();
+        let yaml = serde_yaml::to_string(&session).unwrap();
+        let parsed = Session::from_reader(yaml.as_bytes()).unwrap();
+        assert_eq!(parsed, session);
+        let table = parsed.last_table.unwrap();
+        assert_eq!(table.header, vec!["####", "Name"]);
+        assert_eq!(table.rows, vec![vec!["0", "pod1"]]);
+    }
+
+    #[test]
+    fn test_minimal_session() {
+        let session = Session::from_reader("context: ctx\nnamespace: null".as_bytes()).unwrap();
+        assert_eq!(session.context, Some("ctx".to_string()));
+        assert_eq!(session.selection, ObjectSelection::None);
+        assert!(session.port_forwards.is_empty());
+    }
+
+    #[test]
+    fn test_session_path() {
+        let dir = Path::new("/tmp/kube");
+        assert_eq!(
+            Session::path_for(dir, "debug").unwrap(),
+            PathBuf::from("/tmp/kube/click_sessions/debug.yaml")
+        );
+        assert!(Session::path_for(dir, "../debug").is_err());
+        assert!(Session::path_for(dir, "").is_err());
+    }
+}
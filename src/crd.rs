@@ -15,14 +15,146 @@
 // code to deal with discovering and quering endpoints created by crds
 
 use k8s_openapi::{
-    apimachinery::pkg::apis::meta::v1::{APIGroup, APIResourceList},
-    http::{Request, StatusCode},
-    GetAPIVersionsResponse, RequestError, Response, ResponseBody, ResponseError,
+    apimachinery::pkg::apis::meta::v1::{APIGroup, APIResource, APIResourceList},
+    http::{header, HeaderValue, Request, StatusCode},
+    DeleteOptional, DeleteResponse, GetAPIVersionsResponse, RequestError, Response, ResponseBody,
+    ResponseError,
 };
 
-use crate::{env::Env, error::ClickError};
+use crate::{
+    env::Env,
+    error::{ClickError, ErrorClass},
+};
+
+/// How to reach a kind of resource found via discovery
+pub struct ApiResourceDesc {
+    pub group_version: String,
+    /// The plural name used in urls
+    pub name: String,
+    pub kind: String,
+    pub namespaced: bool,
+    /// Can a whole collection of these be deleted in one request
+    pub deletecollection: bool,
+}
+
+impl ApiResourceDesc {
+    fn from_resource(group_version: &str, resource: &APIResource) -> ApiResourceDesc {
+        ApiResourceDesc {
+            group_version: group_version.to_string(),
+            name: resource.name.clone(),
+            kind: resource.kind.clone(),
+            namespaced: resource.namespaced,
+            deletecollection: resource.verbs.iter().any(|v| v == "deletecollection"),
+        }
+    }
+
+    /// The url for the collection of these resources, in namespace if they're namespaced
+    pub fn url(&self, namespace: Option<&str>) -> String {
+        let prefix = api_prefix(&self.group_version);
+        match namespace {
+            Some(ns) if self.namespaced => format!(
+                "{prefix}/{}/namespaces/{ns}/{}",
+                self.group_version, self.name
+            ),
+            _ => format!("{prefix}/{}/{}", self.group_version, self.name),
+        }
+    }
+}
+
+// the core api (group version "v1") is under /api, everything else is under /apis
+fn api_prefix(group_version: &str) -> &'static str {
+    if group_version.contains('/') {
+        "/apis"
+    } else {
+        "/api"
+    }
+}
+
+// does resource match name, which can be its plural or singular name, or its kind
+fn resource_matches(resource: &APIResource, name: &str) -> bool {
+    resource.name == name
+        || resource.singular_name == name
+        || resource.kind.eq_ignore_ascii_case(name)
+}
+
+// get the resources in group_version, or None if the server didn't return them
+fn get_group_resources(
+    env: &Env,
+    group_version: &str,
+) -> Result<Option<Vec<APIResource>>, ClickError> {
+    let (group_req, _) = get_api_group_resources(group_version)?;
+    match env.run_on_context::<_, GetAPIGroupResourcesResponse>(|c| {
+        c.read(env.get_impersonate_user(), group_req)
+    }) {
+        Ok(GetAPIGroupResourcesResponse::Ok(resp)) => Ok(Some(resp.resources)),
+        Ok(GetAPIGroupResourcesResponse::Other(_)) => Ok(None),
+        Err(e) if e.class() == ErrorClass::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// If the server knows about resources named `name` in any api group (i.e. not the core api),
+/// return the description we can use to access them. Otherwise, return None
+pub fn find_resource_desc(env: &Env, name: &str) -> Result<Option<ApiResourceDesc>, ClickError> {
+    for group in get_api_groups(env)?.iter() {
+        let version = match group.preferred_version.as_ref() {
+            Some(pv) => Some(pv.group_version.as_str()),
+            None => group.versions.first().map(|v| v.group_version.as_str()),
+        };
+        if let Some(group_version) = version {
+            let resources = get_group_resources(env, group_version)?.unwrap_or_default();
+            if let Some(resource) = resources.iter().find(|r| resource_matches(r, name)) {
+                return Ok(Some(ApiResourceDesc::from_resource(
+                    group_version,
+                    resource,
+                )));
+            }
+        }
+    }
+    Ok(None)
+}
 
-pub fn get_api_groups(env: &mut Env) -> Result<Vec<APIGroup>, ClickError> {
+/// Find the resource named `name` in group_version, as for find_resource_desc
+pub fn find_resource_in(
+    env: &Env,
+    group_version: &str,
+    name: &str,
+) -> Result<Option<ApiResourceDesc>, ClickError> {
+    let resources = get_group_resources(env, group_version)?.unwrap_or_default();
+    Ok(resources
+        .iter()
+        .filter(|r| !r.name.contains('/'))
+        .find(|r| resource_matches(r, name))
+        .map(|r| ApiResourceDesc::from_resource(group_version, r)))
+}
+
+/// Get every kind of namespaced resource that can be listed, in the core api and the preferred
+/// version of every api group
+pub fn get_namespaced_resources(env: &Env) -> Result<Vec<ApiResourceDesc>, ClickError> {
+    let mut group_versions = vec!["v1".to_string()];
+    for group in get_api_groups(env)?.into_iter() {
+        match group.preferred_version {
+            Some(pv) => group_versions.push(pv.group_version),
+            None => {
+                group_versions.extend(group.versions.into_iter().next().map(|v| v.group_version))
+            }
+        }
+    }
+    let mut descs = vec![];
+    for group_version in group_versions.iter() {
+        let resources = get_group_resources(env, group_version)?.unwrap_or_default();
+        descs.extend(
+            resources
+                .iter()
+                .filter(|r| r.namespaced && !r.name.contains('/'))
+                .filter(|r| r.verbs.iter().any(|v| v == "list"))
+                .map(|r| ApiResourceDesc::from_resource(group_version, r)),
+        );
+    }
+    Ok(descs)
+}
+
+pub fn get_api_groups(env: &Env) -> Result<Vec<APIGroup>, ClickError> {
     let (request, _) = k8s_openapi::get_api_versions()?;
     match env.run_on_context::<_, GetAPIVersionsResponse>(|c| {
         c.read(env.get_impersonate_user(), request)
@@ -34,6 +166,32 @@ pub fn get_api_groups(env: &mut Env) -> Result<Vec<APIGroup>, ClickError> {
     }
 }
 
+/// Get the names of all the resources the server knows about in api groups (i.e. not the core
+/// api), like the ones defined by crds
+pub fn get_api_resource_names(env: &Env) -> Result<Vec<String>, ClickError> {
+    let mut names = vec![];
+    for group in get_api_groups(env)?.iter() {
+        let version = match group.preferred_version.as_ref() {
+            Some(pv) => Some(pv.group_version.as_str()),
+            None => group.versions.first().map(|v| v.group_version.as_str()),
+        };
+        if let Some(group_version) = version {
+            if let Some(resources) = get_group_resources(env, group_version)? {
+                // names with a / are subresources, like pods/log
+                names.extend(
+                    resources
+                        .into_iter()
+                        .map(|resource| resource.name)
+                        .filter(|name| !name.contains('/')),
+                );
+            }
+        }
+    }
+    names.sort_unstable();
+    names.dedup();
+    Ok(names)
+}
+
 #[allow(clippy::type_complexity)] // type from k8s_openapi
 pub fn get_api_group_resources(
     group_version: &str,
@@ -44,7 +202,7 @@ pub fn get_api_group_resources(
     ),
     RequestError,
 > {
-    let url = format!("/apis/{group_version}");
+    let url = format!("{}/{group_version}", api_prefix(group_version));
     let request = Request::get(url);
     let body = vec![];
     match request.body(body) {
@@ -99,7 +257,10 @@ pub fn read_namespaced_resource(
     ),
     RequestError,
 > {
-    let url = format!("/apis/{group_version}/namespaces/{namespace}/{_type}/{name}");
+    let url = format!(
+        "{}/{group_version}/namespaces/{namespace}/{_type}/{name}",
+        api_prefix(group_version)
+    );
     let request = Request::get(url);
     let body = vec![];
     match request.body(body) {
@@ -108,6 +269,70 @@ pub fn read_namespaced_resource(
     }
 }
 
+/// Build a request to delete the object at url, which should be the url of a single object, or of a
+/// collection with a labelSelector to delete everything it selects
+#[allow(clippy::type_complexity)] // type from k8s_openapi
+pub fn delete_resource(
+    url: String,
+    optional: DeleteOptional,
+) -> Result<
+    (
+        Request<Vec<u8>>,
+        fn(k8s_openapi::http::StatusCode) -> ResponseBody<DeleteResponse<serde_json::Value>>,
+    ),
+    RequestError,
+> {
+    let request = Request::delete(url).header(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    let body = serde_json::to_vec(&optional).map_err(RequestError::Json)?;
+    match request.body(body) {
+        Ok(request) => Ok((request, ResponseBody::new)),
+        Err(err) => Err(RequestError::Http(err)),
+    }
+}
+
+/// Build a request to apply a json merge patch to the object at url
+#[allow(clippy::type_complexity)] // type from k8s_openapi
+pub fn merge_patch_resource(
+    url: String,
+    patch: &serde_json::Value,
+) -> Result<
+    (
+        Request<Vec<u8>>,
+        fn(k8s_openapi::http::StatusCode) -> ResponseBody<ReadResourceValueResponse>,
+    ),
+    RequestError,
+> {
+    let request = Request::patch(url).header(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/merge-patch+json"),
+    );
+    let body = serde_json::to_vec(patch).map_err(RequestError::Json)?;
+    match request.body(body) {
+        Ok(request) => Ok((request, ResponseBody::new)),
+        Err(err) => Err(RequestError::Http(err)),
+    }
+}
+
+/// Build a request to get the resources at url, as json
+#[allow(clippy::type_complexity)] // type from k8s_openapi
+pub fn read_resource_url(
+    url: String,
+) -> Result<
+    (
+        Request<Vec<u8>>,
+        fn(k8s_openapi::http::StatusCode) -> ResponseBody<ReadResourceValueResponse>,
+    ),
+    RequestError,
+> {
+    match Request::get(url).body(vec![]) {
+        Ok(request) => Ok((request, ResponseBody::new)),
+        Err(err) => Err(RequestError::Http(err)),
+    }
+}
+
 #[derive(Debug)]
 pub enum ReadResourceValueResponse {
     Ok(serde_json::Value),
@@ -140,3 +365,32 @@ impl Response for ReadResourceValueResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desc_url() {
+        let desc = ApiResourceDesc {
+            group_version: "cert-manager.io/v1".to_string(),
+            name: "certificates".to_string(),
+            kind: "Certificate".to_string(),
+            namespaced: true,
+            deletecollection: true,
+        };
+        assert_eq!(
+            desc.url(Some("web")),
+            "/apis/cert-manager.io/v1/namespaces/web/certificates"
+        );
+        assert_eq!(desc.url(None), "/apis/cert-manager.io/v1/certificates");
+        let core = ApiResourceDesc {
+            group_version: "v1".to_string(),
+            name: "nodes".to_string(),
+            kind: "Node".to_string(),
+            namespaced: false,
+            deletecollection: true,
+        };
+        assert_eq!(core.url(Some("web")), "/api/v1/nodes");
+    }
+}
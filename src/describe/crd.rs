@@ -28,14 +28,11 @@ pub fn crd_describe(
 ) -> Result<(), ClickError> {
     //let ns = self.namespace.as_ref().unwrap();
     let (request, _) = crate::crd::read_namespaced_resource(name, namespace, _type, group_version)?;
-    match env
-        .run_on_context(|c| {
-            c.read::<crate::crd::ReadResourceValueResponse>(env.get_impersonate_user(), request)
-        })
-        .unwrap()
-    {
+    match env.run_on_context(|c| {
+        c.read::<crate::crd::ReadResourceValueResponse>(env.get_impersonate_user(), request)
+    })? {
         crate::crd::ReadResourceValueResponse::Ok(t) => {
-            if !super::maybe_full_describe_output(matches, &t, writer) {
+            if !super::maybe_full_describe_output(matches, &t, &env.styles, writer) {
                 clickwriteln!(writer, "{} {}", _type, super::NOTSUPPORTED);
             }
         }
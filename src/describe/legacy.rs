@@ -16,13 +16,13 @@
 //!  information for supported kubernetes object types
 
 use crate::error::ClickError;
+use crate::styles::Styles;
 use crate::values::{val_str, val_str_opt, val_u64};
 
 use base64::engine::{general_purpose::STANDARD, Engine};
 use chrono::offset::Local;
 use chrono::offset::Utc;
 use chrono::DateTime;
-use crossterm::style::Stylize;
 use k8s_openapi::api::{apps::v1 as api_apps, core::v1 as api};
 use serde_json::Value;
 
@@ -143,9 +143,11 @@ where
 /// Utility function for describe to print out value
 pub fn describe_format_pod(
     pod: &api::Pod,
+    styles: &Styles,
     table: &mut comfy_table::Table,
 ) -> Result<(), ClickError> {
     let v = serde_json::value::to_value(pod).unwrap();
+    let phase = pod_phase_with(styles);
     let fields = vec![
         (
             "Name:",
@@ -180,7 +182,7 @@ pub fn describe_format_pod(
             "Status:",
             DescItem::CustomFunc {
                 path: None,
-                func: &pod_phase,
+                func: &phase,
                 default: "<No Phase>",
             },
         ),
@@ -355,15 +357,19 @@ fn get_volume_str(v: &Value) -> Cow<str> {
     buf.into()
 }
 
-fn pod_phase(v: &Value) -> Cow<str> {
-    // TODO: How to get an env in here for the colors
+// pod_phase as a CustomFunc
+fn pod_phase_with(styles: &Styles) -> impl Fn(&Value) -> Cow<str> + '_ {
+    move |v| pod_phase(v, styles)
+}
+
+fn pod_phase<'a>(v: &'a Value, styles: &Styles) -> Cow<'a, str> {
     let phase_str = val_str("/status/phase", v, "<No Phase>");
-    match &*phase_str {
-        "Pending" | "Unknown" => phase_str.yellow().to_string().into(),
-        "Running" | "Succeeded" => phase_str.green().to_string().into(),
-        "Failed" => phase_str.red().to_string().into(),
-        _ => phase_str.yellow().to_string().into(),
-    }
+    let styled = match &*phase_str {
+        "Running" | "Succeeded" => styles.success(&phase_str),
+        "Failed" => styles.danger(&phase_str),
+        _ => styles.warning(&phase_str),
+    };
+    styled.to_string().into()
 }
 
 /// Utility function for describe to print out value
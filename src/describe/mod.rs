@@ -13,7 +13,7 @@
 // limitations under the License.
 
 /// This module contains code for handling how click describes various k8s objects
-use crate::{command::keyval_string, error::ClickError, output::ClickWriter};
+use crate::{command::keyval_string, error::ClickError, output::ClickWriter, styles::Styles};
 use chrono::Local;
 use clap::ArgMatches;
 use k8s_openapi::{apimachinery::pkg::apis::meta::v1::ObjectMeta, Metadata, Resource};
@@ -29,13 +29,14 @@ pub static NOTSUPPORTED: &str = "not supported without -j or -y yet\n";
 pub fn maybe_full_describe_output<T: ?Sized>(
     matches: &ArgMatches,
     value: &T,
+    styles: &Styles,
     writer: &mut ClickWriter,
 ) -> bool
 where
     T: Serialize,
 {
     if matches.contains_id("json") {
-        writer.pretty_color_json(value).unwrap_or(());
+        writer.pretty_color_json(value, styles).unwrap_or(());
         true
     } else if matches.contains_id("yaml") {
         writer.print_yaml(value).unwrap_or(());
@@ -17,7 +17,7 @@
 use crate::{
     command::keyval_string,
     env::Env,
-    error::ClickError,
+    error::{ClickError, ErrorClass},
     output::ClickWriter,
     values::{val_str, val_u64},
 };
@@ -37,11 +37,11 @@ pub fn service_describe(
 ) -> Result<(), ClickError> {
     let (request, _) =
         api::Endpoints::read_namespaced_endpoints(name, namespace, Default::default()).unwrap();
-    let epval = match env
-        .run_on_context(|c| c.read(env.get_impersonate_user(), request))
-        .unwrap()
-    {
-        api::ReadNamespacedEndpointsResponse::Ok(resp) => serde_json::value::to_value(resp).ok(),
+    let epval = match env.run_on_context(|c| c.read(env.get_impersonate_user(), request)) {
+        Ok(api::ReadNamespacedEndpointsResponse::Ok(resp)) => {
+            serde_json::value::to_value(resp).ok()
+        }
+        Err(e) if e.class() != ErrorClass::NotFound => return Err(e),
         _ => {
             clickwriteln!(writer, "Error fetching endpoints");
             None
@@ -50,12 +50,9 @@ pub fn service_describe(
 
     let (request, _) =
         api::Service::read_namespaced_service(name, namespace, Default::default()).unwrap();
-    match env
-        .run_on_context(|c| c.read(env.get_impersonate_user(), request))
-        .unwrap()
-    {
+    match env.run_on_context(|c| c.read(env.get_impersonate_user(), request))? {
         api::ReadNamespacedServiceResponse::Ok(service) => {
-            if !super::maybe_full_describe_output(matches, &service, writer) {
+            if !super::maybe_full_describe_output(matches, &service, &env.styles, writer) {
                 super::describe_metadata(&service, table)?;
                 let val = serde_json::value::to_value(&service).unwrap();
                 describe_format_service(&service, val, epval, table);
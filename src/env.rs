@@ -12,30 +12,53 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::config::{self, Alias, ClickConfig, Config};
+use crate::audit::AUDIT_FILE;
+use crate::config::{
+    self, Alias, ClickConfig, Config, ContextSettings, SafetyLevel, SavedPortForward, SavedTable,
+    Session,
+};
 use crate::error::ClickError;
 use crate::kobj::{KObj, ObjType};
 use crate::output::ClickWriter;
+use crate::parser::{try_parse_csl, try_parse_range};
+use crate::prompt::{format_command_duration, PromptTemplate, PromptValue, DEFAULT_PROMPT};
+use crate::script::Script;
 use crate::styles::Styles;
 
+use crossterm::style::ContentStyle;
 use rustyline::config as rustyconfig;
 use strfmt::strfmt;
 use tempdir::TempDir;
 
+use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Child;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 // TODO: Maybe make less of this pub
 
+/// Variables that are always defined, and can't be set with let
+pub const BUILTIN_VARS: [&str; 4] = ["ctx", "ns", "name", "namespace"];
+
+/// How long names fetched for tab completion are reused before being fetched again
+const COMPLETION_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Names fetched for tab completion, and when they were fetched
+type CachedNames = (Instant, Vec<String>);
+
+/// What cached completion names were fetched for: (context, impersonated user, what was listed)
+type CompletionKey = (String, Option<String>, String);
+
 /// An ongoing port forward
 pub struct PortForward {
     pub child: Child,
     pub pod: String,
+    pub namespace: String,
     pub ports: Vec<String>,
     pub output: Arc<Mutex<String>>,
 }
@@ -46,7 +69,7 @@ pub struct ExpandedAlias<'a> {
     pub rest: &'a str,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum ObjectSelection {
     Single(KObj),
     Range(Vec<KObj>),
@@ -69,9 +92,23 @@ pub struct Env {
     pub ctrlcbool: Arc<AtomicBool>,
     port_forwards: Vec<PortForward>,
     pub prompt: String,
+    prompt_template: PromptTemplate,
+    /// The server version of each context, fetched the first time the prompt needs it
+    server_versions: HashMap<String, String>,
+    /// If the last command succeeded, and how long it took
+    last_status: Option<(bool, Duration)>,
     range_str: Option<String>,
     pub tempdir: std::io::Result<TempDir>,
     impersonate_user: Option<String>,
+    script: Option<Script>,
+    dashboard: Option<String>,
+    /// If the dashboard is running, so it isn't opened again from inside itself
+    dashboard_open: bool,
+    vars: BTreeMap<String, String>,
+    /// If true, commands that change the cluster are refused in every context (--read-only)
+    read_only: bool,
+    /// Names used for tab completion
+    completion_cache: RefCell<HashMap<CompletionKey, CachedNames>>,
 }
 
 lazy_static! {
@@ -90,12 +127,19 @@ impl Env {
     pub fn new(config: Config, click_config: ClickConfig, click_config_path: PathBuf) -> Env {
         let namespace = click_config.namespace.clone();
         let context = click_config.context.clone();
-        let styles = Styles::new();
-        let nones = (
-            styles.prompt_context("none"),
-            styles.prompt_namespace("none"),
-            styles.prompt_select_none("none"),
-        );
+        let styles = Styles::with_theme(&click_config.theme).unwrap_or_else(|e| {
+            println!("[WARN] Invalid theme in click config, using the default theme. {e}");
+            Styles::new()
+        });
+        let prompt_template = click_config
+            .prompt
+            .as_deref()
+            .map(|prompt| PromptTemplate::parse(prompt, &styles))
+            .unwrap_or_else(|| PromptTemplate::parse(DEFAULT_PROMPT, &styles))
+            .unwrap_or_else(|e| {
+                println!("[WARN] Invalid prompt in click config, using the default prompt. {e}");
+                PromptTemplate::parse(DEFAULT_PROMPT, &styles).unwrap()
+            });
         let mut env = Env {
             config,
             click_config,
@@ -110,12 +154,22 @@ impl Env {
             last_table: None,
             ctrlcbool: CTC_BOOL.clone(),
             port_forwards: Vec::new(),
-            prompt: format!("[{}] [{}] [{}] > ", nones.0, nones.1, nones.2,),
+            prompt: String::new(),
+            prompt_template,
+            server_versions: HashMap::new(),
+            last_status: None,
             range_str: None,
             tempdir: TempDir::new("click"),
             impersonate_user: None,
+            script: None,
+            dashboard: None,
+            dashboard_open: false,
+            vars: BTreeMap::new(),
+            read_only: false,
+            completion_cache: RefCell::new(HashMap::new()),
         };
-        env.set_context(context.as_deref());
+        env.load_context(context.as_deref());
+        env.set_prompt();
         env
     }
 
@@ -131,6 +185,149 @@ impl Env {
             .unwrap();
     }
 
+    /// Queue a script to be run by the command processor once the current command finishes
+    pub fn queue_script(&mut self, script: Script) {
+        self.script = Some(script);
+    }
+
+    pub fn take_script(&mut self) -> Option<Script> {
+        self.script.take()
+    }
+
+    /// Ask the command processor to open the dashboard, listing objects with list_command, once
+    /// the current command finishes
+    pub fn request_dashboard(&mut self, list_command: String) -> Result<(), ClickError> {
+        if self.dashboard_open {
+            return Err(ClickError::CommandError(
+                "The dashboard is already open".to_string(),
+            ));
+        }
+        self.dashboard = Some(list_command);
+        Ok(())
+    }
+
+    pub fn set_dashboard_open(&mut self, open: bool) {
+        self.dashboard_open = open;
+    }
+
+    pub fn take_dashboard_request(&mut self) -> Option<String> {
+        self.dashboard.take()
+    }
+
+    /// Set a variable, which can then be used as $name in commands
+    pub fn set_var(&mut self, name: &str, value: String) -> Result<(), ClickError> {
+        if BUILTIN_VARS.contains(&name) {
+            return Err(ClickError::CommandError(format!(
+                "{name} is a built-in variable and can't be set"
+            )));
+        }
+        let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(ClickError::CommandError(format!(
+                "Invalid variable name: {name}. Names must start with a letter or _, and only \
+                 contain letters, numbers, and _"
+            )));
+        }
+        self.vars.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    pub fn unset_var(&mut self, name: &str) -> bool {
+        self.vars.remove(name).is_some()
+    }
+
+    pub fn get_vars(&self) -> &BTreeMap<String, String> {
+        &self.vars
+    }
+
+    fn selected_obj_for_var(&self, name: &str) -> Result<&KObj, ClickError> {
+        match self.current_selection {
+            ObjectSelection::Single(ref obj) => Ok(obj),
+            ObjectSelection::Range(_) => Err(ClickError::CommandError(format!(
+                "${name} needs a single object selected, but a range is selected"
+            ))),
+            ObjectSelection::None => Err(ClickError::CommandError(format!(
+                "${name} needs an object selected"
+            ))),
+        }
+    }
+
+    /// Is name a variable, either a built-in one or one that was set
+    pub fn has_var(&self, name: &str) -> bool {
+        BUILTIN_VARS.contains(&name) || self.vars.contains_key(name)
+    }
+
+    /// Get the value of a variable, either a built-in one or one that was set
+    pub fn get_var(&self, name: &str) -> Result<String, ClickError> {
+        match name {
+            "ctx" => self
+                .context
+                .as_ref()
+                .map(|c| c.name.clone())
+                .ok_or_else(|| ClickError::CommandError("$ctx used, but no context set".into())),
+            "ns" => self
+                .namespace
+                .clone()
+                .ok_or_else(|| ClickError::CommandError("$ns used, but no namespace set".into())),
+            "name" => self
+                .selected_obj_for_var(name)
+                .map(|obj| obj.name().to_string()),
+            "namespace" => self
+                .selected_obj_for_var(name)
+                .map(|obj| obj.namespace.clone().unwrap_or_default()),
+            _ => self
+                .vars
+                .get(name)
+                .cloned()
+                .ok_or_else(|| ClickError::CommandError(format!("Unknown variable ${name}"))),
+        }
+    }
+
+    /// The directory click.config lives in, which is where other click state is stored too
+    pub fn click_config_dir(&self) -> &Path {
+        self.click_config_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+    }
+
+    /// Capture the current state of this env as a Session that can be saved
+    pub fn get_session(&self) -> Session {
+        Session {
+            context: self.context.as_ref().map(|c| c.name.clone()),
+            namespace: self.namespace.clone(),
+            selection: self.current_selection.clone(),
+            last_objs: self.last_objs.clone(),
+            last_table: self.last_table.as_ref().map(SavedTable::from_table),
+            port_forwards: self
+                .port_forwards
+                .iter()
+                .map(|pf| SavedPortForward {
+                    pod: pf.pod.clone(),
+                    namespace: pf.namespace.clone(),
+                    ports: pf.ports.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Restore the state saved in session. Port forwards can't be restored by the env, so they are
+    /// returned for the caller to restart
+    pub fn restore_session(&mut self, session: Session) -> Vec<SavedPortForward> {
+        if session.context.is_some() {
+            self.load_context(session.context.as_deref());
+        }
+        self.namespace = session.namespace;
+        self.last_objs = session.last_objs;
+        self.last_table = session.last_table.map(|t| t.to_table());
+        match session.selection {
+            ObjectSelection::Single(obj) => self.select_objs(vec![obj]),
+            ObjectSelection::Range(range) => self.set_range(range),
+            ObjectSelection::None => self.clear_current(),
+        }
+        session.port_forwards
+    }
+
     pub fn set_impersonate_user(&mut self, user: Option<String>) {
         self.impersonate_user = user;
     }
@@ -139,29 +336,115 @@ impl Env {
         self.impersonate_user.as_deref()
     }
 
-    // sets the prompt string based on current settings
-    fn set_prompt(&mut self) {
-        self.prompt = format!(
-            "[{}] [{}] [{}] > ",
-            if let Some(ref c) = self.context {
-                self.styles.prompt_context(c.name.as_str())
-            } else {
-                self.styles.prompt_context("none")
+    /// Set the prompt template, or go back to the default one if template is None
+    pub fn set_prompt_template(&mut self, template: Option<&str>) -> Result<(), ClickError> {
+        self.prompt_template =
+            PromptTemplate::parse(template.unwrap_or(DEFAULT_PROMPT), &self.styles)?;
+        self.click_config.prompt = template.map(|t| t.to_string());
+        self.set_prompt();
+        Ok(())
+    }
+
+    /// Record how the last command went, for the status and duration placeholders of the prompt
+    pub fn set_last_status(&mut self, succeeded: bool, duration: Duration) {
+        self.last_status = Some((succeeded, duration));
+    }
+
+    fn server_version(&mut self) -> String {
+        let context = match self.context {
+            Some(ref c) => c.name.clone(),
+            None => return String::new(),
+        };
+        if !self.server_versions.contains_key(&context) {
+            let version = k8s_openapi::get_code_version()
+                .map_err(ClickError::from)
+                .and_then(|(request, _)| {
+                    self.run_on_context(|c| {
+                        c.read::<k8s_openapi::GetCodeVersionResponse>(
+                            self.get_impersonate_user(),
+                            request,
+                        )
+                    })
+                });
+            let version = match version {
+                Ok(k8s_openapi::GetCodeVersionResponse::Ok(info)) => info.git_version,
+                _ => String::new(),
+            };
+            self.server_versions.insert(context.clone(), version);
+        }
+        self.server_versions[&context].clone()
+    }
+
+    fn prompt_value(&self, name: &str, version: &str) -> PromptValue {
+        match name {
+            "context" => match self.context {
+                Some(ref c) => PromptValue::new(&c.name, self.context_style()),
+                None => PromptValue::new("none", self.styles.get("prompt_context")),
             },
-            if let Some(ref n) = self.namespace {
-                self.styles.prompt_namespace(n.as_str())
-            } else {
-                self.styles.prompt_namespace("none")
+            "namespace" => PromptValue::new(
+                self.namespace.as_deref().unwrap_or("none"),
+                self.styles.get("prompt_namespace"),
+            ),
+            "selection" => match self.current_selection {
+                ObjectSelection::Single(ref obj) => {
+                    PromptValue::new(&obj.name, self.styles.get(obj.type_str()))
+                }
+                ObjectSelection::Range(_) => PromptValue::new(
+                    self.range_str.as_deref().unwrap_or_default(),
+                    self.styles.get("prompt_range"),
+                ),
+                ObjectSelection::None => {
+                    PromptValue::new("none", self.styles.get("prompt_select_none"))
+                }
             },
-            match self.current_selection {
-                ObjectSelection::Single(ref obj) =>
-                    self.styles.prompt_object(obj.name.as_str(), obj.type_str()),
-                ObjectSelection::Range(_) => self
-                    .styles
-                    .prompt_range(self.range_str.as_ref().unwrap().as_str()),
-                ObjectSelection::None => self.styles.prompt_select_none("none"),
-            }
-        );
+            "type" => match self.current_selection {
+                ObjectSelection::Single(ref obj) => {
+                    PromptValue::new(obj.type_str(), self.styles.get(obj.type_str()))
+                }
+                ObjectSelection::Range(_) => {
+                    PromptValue::new("range", self.styles.get("prompt_range"))
+                }
+                ObjectSelection::None => PromptValue::empty(),
+            },
+            "user" => PromptValue::new(
+                self.impersonate_user.as_deref().unwrap_or_default(),
+                self.styles.get("danger"),
+            ),
+            "version" => PromptValue::new(version, ContentStyle::new()),
+            "forwards" => match self.port_forwards.len() {
+                0 => PromptValue::empty(),
+                n => PromptValue::new(n.to_string(), ContentStyle::new()),
+            },
+            "status" => match self.last_status {
+                Some((true, _)) => PromptValue::new("ok", self.styles.get("success")),
+                Some((false, _)) => PromptValue::new("failed", self.styles.get("danger")),
+                None => PromptValue::empty(),
+            },
+            "duration" => match self.last_status {
+                Some((_, duration)) => {
+                    PromptValue::new(format_command_duration(duration), ContentStyle::new())
+                }
+                None => PromptValue::empty(),
+            },
+            "time" => PromptValue::new(
+                chrono::Local::now().format("%H:%M:%S").to_string(),
+                ContentStyle::new(),
+            ),
+            _ => PromptValue::empty(),
+        }
+    }
+
+    /// Set the prompt string based on current settings. The command processor calls this before
+    /// each prompt, so the time and the status of the last command are up to date.
+    pub fn set_prompt(&mut self) {
+        let version = if self.prompt_template.uses("version") {
+            self.server_version()
+        } else {
+            String::new()
+        };
+        self.prompt = self
+            .prompt_template
+            .render(|name| self.prompt_value(name, &version));
     }
 
     pub fn get_rustyline_conf(&self) -> rustyconfig::Config {
@@ -172,10 +455,24 @@ impl Env {
         &self.config.contexts
     }
 
+    /// Switch to the specified context, and to the namespace configured for it in the contexts
+    /// section of the click config, if any
     pub fn set_context(&mut self, ctx: Option<&str>) {
+        self.load_context(ctx);
+        if self.context.is_some() {
+            if let Some(ns) = self.context_settings().and_then(|s| s.namespace.clone()) {
+                self.set_namespace(Some(&ns));
+            }
+        }
+    }
+
+    fn load_context(&mut self, ctx: Option<&str>) {
         if let Some(cname) = ctx {
             self.context = match self.config.get_context(cname, &self.click_config) {
-                Ok(context) => Some(context),
+                Ok(mut context) => {
+                    context.set_audit_log(self.click_config_dir().join(AUDIT_FILE));
+                    Some(context)
+                }
                 Err(e) => {
                     println!(
                         "[WARN] Couldn't find/load context {cname}, now no current context. \
@@ -189,6 +486,124 @@ impl Env {
         }
     }
 
+    /// The settings from the contexts section of the click config for the current context
+    pub fn context_settings(&self) -> Option<&ContextSettings> {
+        self.context
+            .as_ref()
+            .and_then(|c| self.click_config.contexts.get(&c.name))
+    }
+
+    /// Aliases that are only defined for the current context
+    pub fn context_aliases(&self) -> &[Alias] {
+        self.context_settings()
+            .map(|s| s.aliases.as_slice())
+            .unwrap_or(&[])
+    }
+
+    // the style for the context in the prompt, either as configured for the context or from the
+    // theme
+    fn context_style(&self) -> ContentStyle {
+        match self.context_settings().and_then(|s| s.color.as_ref()) {
+            Some(spec) => match self.styles.resolve(spec) {
+                Ok(style) => style,
+                Err(_) => self.styles.get("prompt_context"),
+            },
+            None => self.styles.get("prompt_context"),
+        }
+    }
+
+    pub fn safety_level(&self) -> SafetyLevel {
+        self.context_settings()
+            .map(|s| s.safety)
+            .unwrap_or_default()
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Return an error if commands that change the cluster aren't allowed in the current context.
+    /// `action` describes what was going to happen, like "delete pod foo"
+    pub fn ensure_writable(&self, action: &str) -> Result<(), ClickError> {
+        if self.read_only {
+            return Err(ClickError::CommandError(format!(
+                "Refusing to {action}: click is in read-only mode (--read-only)"
+            )));
+        }
+        if self.safety_level() == SafetyLevel::ReadOnly {
+            return Err(ClickError::CommandError(format!(
+                "Refusing to {action}: context {} is read-only",
+                self.context
+                    .as_ref()
+                    .map(|c| c.name.as_str())
+                    .unwrap_or("none")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Is the current context marked protected in the click config
+    pub fn is_protected(&self) -> bool {
+        self.context_settings()
+            .map(|s| s.protected)
+            .unwrap_or(false)
+    }
+
+    /// Ask the user to type the name of the current context to confirm `action`. Returns true if
+    /// they typed it correctly
+    pub fn confirm_protected(&self, action: &str, writer: &mut ClickWriter) -> bool {
+        let name = self
+            .context
+            .as_ref()
+            .map(|c| c.name.as_str())
+            .unwrap_or("none");
+        clickwrite!(
+            writer,
+            "Context {name} is protected. Type the context name to {action}: "
+        );
+        io::stdout().flush().expect("Could not flush stdout");
+        let mut conf = String::new();
+        if io::stdin().read_line(&mut conf).is_ok() && conf.trim() == name {
+            true
+        } else {
+            clickwriteln!(writer, "Context name didn't match");
+            false
+        }
+    }
+
+    /// Check if a command that changes the cluster should go ahead. This errors if click is in
+    /// read-only mode or the current context is read-only, asks the user to type the context name
+    /// if it's protected, and asks the user to confirm if the context's safety level is confirm.
+    /// Returns Ok(false) if the user declined.
+    pub fn check_mutation(
+        &self,
+        action: &str,
+        writer: &mut ClickWriter,
+    ) -> Result<bool, ClickError> {
+        self.ensure_writable(action)?;
+        if self.is_protected() {
+            return Ok(self.confirm_protected(action, writer));
+        }
+        if self.safety_level() == SafetyLevel::Confirm {
+            clickwrite!(
+                writer,
+                "Context {} requires confirmation. Really {action} [y/N]? ",
+                self.context
+                    .as_ref()
+                    .map(|c| c.name.as_str())
+                    .unwrap_or("none")
+            );
+            io::stdout().flush().expect("Could not flush stdout");
+            let mut conf = String::new();
+            if io::stdin().read_line(&mut conf).is_ok() {
+                let conf = conf.trim().to_lowercase();
+                return Ok(conf == "y" || conf == "yes");
+            }
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
     // a lowercase RFC 1123 label must consist of lower case alphanumeric characters or '-', and must start and end with an alphanumeric character. Max length is 63.
     fn validate_rfc_1123_label(label: &str) -> bool {
         if label.is_empty() || label.len() > 63 {
@@ -254,10 +669,15 @@ impl Env {
             .position(|a| a.alias == *alias)
     }
 
-    // return the alias struct for the specified alias
+    // return the alias struct for the specified alias, preferring aliases for the current context
     pub fn get_alias(&self, alias: &str) -> Option<&Alias> {
-        self.alias_position(alias)
-            .and_then(|p| self.click_config.aliases.get(p))
+        self.context_aliases()
+            .iter()
+            .find(|a| a.alias == *alias)
+            .or_else(|| {
+                self.alias_position(alias)
+                    .and_then(|p| self.click_config.aliases.get(p))
+            })
     }
 
     pub fn add_alias(&mut self, alias: Alias) {
@@ -286,6 +706,13 @@ impl Env {
         self.last_table = table;
     }
 
+    /// Remove and return the last list of objects and its table, so a caller can tell whether a
+    /// command produced a new list
+    pub fn take_last_objs(&mut self) -> Option<(Vec<KObj>, Option<comfy_table::Table>)> {
+        let table = self.last_table.take();
+        self.last_objs.take().map(|objs| (objs, table))
+    }
+
     pub fn clear_last_objs(&mut self) {
         self.last_objs = None;
         self.last_table = None;
@@ -295,6 +722,10 @@ impl Env {
         self.last_table.as_ref()
     }
 
+    pub fn get_last_objs(&self) -> Option<&Vec<KObj>> {
+        self.last_objs.as_ref()
+    }
+
     pub fn clear_current(&mut self) {
         self.current_selection = ObjectSelection::None;
         self.range_str = None;
@@ -331,6 +762,48 @@ impl Env {
         self.set_prompt();
     }
 
+    /// The currently selected objects: the current range, a single object, or nothing
+    pub fn selected_objs(&self) -> Vec<KObj> {
+        match &self.current_selection {
+            ObjectSelection::Single(obj) => vec![obj.clone()],
+            ObjectSelection::Range(range) => range.clone(),
+            ObjectSelection::None => vec![],
+        }
+    }
+
+    /// Get the objects from the last list that spec refers to. spec can be an index, a range like
+    /// 1..3, or a comma separated list of indexes. Returns None if spec isn't any of those.
+    pub fn items_at_spec(&self, spec: &str) -> Option<Vec<KObj>> {
+        if let Ok(num) = spec.parse::<usize>() {
+            Some(self.item_at(num).cloned().into_iter().collect())
+        } else if let Some(range) = try_parse_range(spec) {
+            let mut objs = vec![];
+            for i in range {
+                match self.item_at(i) {
+                    Some(obj) => objs.push(obj.clone()),
+                    None => break,
+                }
+            }
+            Some(objs)
+        } else {
+            try_parse_csl(spec).map(|csl| csl.filter_map(|i| self.item_at(i).cloned()).collect())
+        }
+    }
+
+    /// Select the specified objects. A single object becomes the current object, more than one
+    /// becomes the current range, and an empty vec clears the selection.
+    pub fn select_objs(&mut self, mut objs: Vec<KObj>) {
+        match objs.len() {
+            0 => self.clear_current(),
+            1 => {
+                self.current_selection = ObjectSelection::Single(objs.remove(0));
+                self.range_str = None;
+                self.set_prompt();
+            }
+            _ => self.set_range(objs),
+        }
+    }
+
     pub fn current_pod(&self) -> Option<&KObj> {
         match self.current_selection {
             ObjectSelection::Single(ref obj) => match obj.typ {
@@ -456,6 +929,43 @@ impl Env {
         }
     }
 
+    /// Record an action that changes the cluster but doesn't go through the context (like exec via
+    /// kubectl) in the audit log
+    pub fn audit(&self, method: &str, path: &str, outcome: String) {
+        if let Some(context) = self.context.as_ref() {
+            context.audit(method, path, self.get_impersonate_user(), false, outcome);
+        }
+    }
+
+    /// Get names to complete for key in the current context, as the current impersonated user.
+    /// Names are fetched with fetch and then cached for COMPLETION_CACHE_TTL, so completion stays
+    /// fast and repeated tabs don't each make an API request. Errors just mean there's nothing to
+    /// complete.
+    pub fn cached_completions<F>(&self, key: &str, fetch: F) -> Vec<String>
+    where
+        F: FnOnce(&Env) -> Result<Vec<String>, ClickError>,
+    {
+        let context = match self.context {
+            Some(ref c) => c.name.clone(),
+            None => return vec![],
+        };
+        let cache_key = (context, self.impersonate_user.clone(), key.to_string());
+        if let Some((fetched, names)) = self.completion_cache.borrow().get(&cache_key) {
+            if fetched.elapsed() < COMPLETION_CACHE_TTL {
+                return names.clone();
+            }
+        }
+        match fetch(self) {
+            Ok(names) => {
+                self.completion_cache
+                    .borrow_mut()
+                    .insert(cache_key, (Instant::now(), names.clone()));
+                names
+            }
+            Err(_) => vec![],
+        }
+    }
+
     /// Add a new task for the env to keep track of
     pub fn add_port_forward(&mut self, pf: PortForward) {
         self.port_forwards.push(pf);
@@ -501,7 +1011,8 @@ impl Env {
         // this means an alias maps to itself, and we want to stop expanding
         // to avoid an infinite loop
         if prev_word.filter(|pw| *pw == word).is_none() {
-            for alias in self.click_config.aliases.iter() {
+            let aliases = self.context_aliases().iter();
+            for alias in aliases.chain(self.click_config.aliases.iter()) {
                 if word == alias.alias.as_str() {
                     return ExpandedAlias {
                         expansion: Some(alias),
@@ -556,6 +1067,7 @@ impl fmt::Display for Env {
   kubectl Binary: {}
   Range Separator: {}
   Describe Shows Events: {}
+  Restore Session: {}
 }}",
             if let Some(ref c) = self.context {
                 self.styles.config_val(c.name.as_str())
@@ -593,6 +1105,8 @@ impl fmt::Display for Env {
                     .to_string()
                     .as_str()
             ),
+            self.styles
+                .config_val(self.click_config.restore_session.to_string().as_str()),
         )
     }
 }
@@ -600,7 +1114,123 @@ impl fmt::Display for Env {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::get_test_config;
+    use crate::config::{get_config_from_kubefile_test_conf, get_test_config};
+
+    #[test]
+    fn test_vars() {
+        let mut env = Env::new(
+            get_test_config(),
+            ClickConfig::default(),
+            PathBuf::from("/tmp/click.config"),
+        );
+        assert!(env.set_var("ns", "x".to_string()).is_err());
+        assert!(env.set_var("1x", "x".to_string()).is_err());
+        env.set_var("pod_name", "web-1".to_string()).unwrap();
+        assert_eq!(env.get_var("pod_name").unwrap(), "web-1");
+        assert!(env.get_var("nope").is_err());
+        assert!(env.unset_var("pod_name"));
+        assert!(!env.unset_var("pod_name"));
+
+        env.set_namespace(Some("kube-system"));
+        assert_eq!(env.get_var("ns").unwrap(), "kube-system");
+        assert!(env.get_var("name").is_err());
+        env.select_objs(vec![KObj {
+            name: "web-1".to_string(),
+            namespace: Some("default".to_string()),
+            typ: ObjType::Node,
+        }]);
+        assert_eq!(env.get_var("name").unwrap(), "web-1");
+        assert_eq!(env.get_var("namespace").unwrap(), "default");
+    }
+
+    #[test]
+    fn test_items_at_spec() {
+        let mut env = Env::new(
+            get_test_config(),
+            ClickConfig::default(),
+            PathBuf::from("/tmp/click.config"),
+        );
+        let objs: Vec<KObj> = (0..5)
+            .map(|i| KObj {
+                name: format!("node-{i}"),
+                namespace: None,
+                typ: ObjType::Node,
+            })
+            .collect();
+        env.set_last_objs(objs, None);
+        let names = |spec: &str| {
+            env.items_at_spec(spec).map(|objs| {
+                objs.into_iter()
+                    .map(|obj| obj.name)
+                    .collect::<Vec<String>>()
+            })
+        };
+        assert_eq!(names("2"), Some(vec!["node-2".to_string()]));
+        assert_eq!(names("9"), Some(vec![]));
+        assert_eq!(
+            names("3.."),
+            Some(vec!["node-3".to_string(), "node-4".to_string()])
+        );
+        assert_eq!(
+            names("0,4"),
+            Some(vec!["node-0".to_string(), "node-4".to_string()])
+        );
+        assert_eq!(names("/node/"), None);
+    }
+
+    #[test]
+    fn test_cached_completions() {
+        let mut env = Env::new(
+            get_config_from_kubefile_test_conf(),
+            ClickConfig::default(),
+            PathBuf::from("/tmp/click.config"),
+        );
+        let fetched = |name: &str| {
+            let name = name.to_string();
+            move |_: &Env| Ok(vec![name])
+        };
+
+        // no context, nothing to complete
+        assert!(env.cached_completions("pods", fetched("web-1")).is_empty());
+
+        env.context = Some(
+            env.config
+                .get_context("insecure_context", &env.click_config)
+                .unwrap(),
+        );
+        assert_eq!(
+            env.cached_completions("pods", fetched("web-1")),
+            vec!["web-1"]
+        );
+        assert_eq!(
+            env.cached_completions("pods", fetched("web-2")),
+            vec!["web-1"]
+        );
+        assert_eq!(
+            env.cached_completions("nodes", fetched("node-1")),
+            vec!["node-1"]
+        );
+
+        env.set_impersonate_user(Some("admin".to_string()));
+        assert_eq!(
+            env.cached_completions("pods", fetched("web-3")),
+            vec!["web-3"]
+        );
+        env.set_impersonate_user(None);
+        assert_eq!(
+            env.cached_completions("pods", fetched("web-4")),
+            vec!["web-1"]
+        );
+
+        // expire the cached pods
+        for (fetched, _) in env.completion_cache.borrow_mut().values_mut() {
+            *fetched -= COMPLETION_CACHE_TTL;
+        }
+        assert_eq!(
+            env.cached_completions("pods", fetched("web-5")),
+            vec!["web-5"]
+        );
+    }
 
     #[test]
     fn try_expand_alias() {
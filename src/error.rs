@@ -83,6 +83,8 @@ pub enum ClickError {
     Pem(pem::PemError),
     Reqwest(reqwest::Error, Option<Value>),
     UrlParse(url::ParseError),
+    /// The server answered with a status we weren't expecting, and a description of the response
+    UnexpectedStatus(u16, String),
 }
 
 impl fmt::Display for ClickError {
@@ -121,6 +123,64 @@ impl fmt::Display for ClickError {
             ClickError::Pem(ref err) => write!(f, "Pem error: {err}"),
             ClickError::Reqwest(ref err, _) => write!(f, "Reqwest error: {err}"),
             ClickError::UrlParse(ref err) => write!(f, "Error parsing url: {err}"),
+            ClickError::UnexpectedStatus(status, ref s) => {
+                write!(f, "Got unexpected status {status}: {s}")
+            }
+        }
+    }
+}
+
+/// Broad classes of errors, used to pick an exit code when click isn't interactive
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorClass {
+    General,
+    Usage,
+    Auth,
+    NotFound,
+}
+
+impl ErrorClass {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorClass::General => 1,
+            ErrorClass::Usage => 2,
+            ErrorClass::Auth => 3,
+            ErrorClass::NotFound => 4,
+        }
+    }
+
+    /// The class of a failed request that got status back
+    pub fn from_status(status: u16) -> ErrorClass {
+        match status {
+            401 | 403 => ErrorClass::Auth,
+            404 => ErrorClass::NotFound,
+            _ => ErrorClass::General,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorClass::General => "error",
+            ErrorClass::Usage => "usage",
+            ErrorClass::Auth => "auth",
+            ErrorClass::NotFound => "not_found",
+        }
+    }
+}
+
+impl ClickError {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            ClickError::Clap(_) => ErrorClass::Usage,
+            ClickError::Kube(ClickErrNo::Unauthorized)
+            | ClickError::Kube(ClickErrNo::NoTokenAvailable)
+            | ClickError::Kube(ClickErrNo::InvalidUser) => ErrorClass::Auth,
+            ClickError::Reqwest(err, _) => err
+                .status()
+                .map(|s| ErrorClass::from_status(s.as_u16()))
+                .unwrap_or(ErrorClass::General),
+            ClickError::UnexpectedStatus(status, _) => ErrorClass::from_status(*status),
+            _ => ErrorClass::General,
         }
     }
 }
@@ -143,6 +203,7 @@ impl error::Error for ClickError {
             ClickError::Pem(ref err) => Some(err),
             ClickError::Reqwest(ref err, _) => Some(err),
             ClickError::UrlParse(ref err) => Some(err),
+            ClickError::UnexpectedStatus(..) => None,
         }
     }
 }
@@ -208,3 +269,25 @@ impl From<url::ParseError> for ClickError {
         ClickError::UrlParse(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class() {
+        let status = |code: u16| ClickError::UnexpectedStatus(code, "x".to_string()).class();
+        assert_eq!(status(403), ErrorClass::Auth);
+        assert_eq!(status(401), ErrorClass::Auth);
+        assert_eq!(status(404), ErrorClass::NotFound);
+        assert_eq!(status(500), ErrorClass::General);
+        assert_eq!(
+            ClickError::ParseErr("bad".to_string()).class(),
+            ErrorClass::General
+        );
+        assert_eq!(
+            ClickError::Kube(ClickErrNo::Unauthorized).class(),
+            ErrorClass::Auth
+        );
+    }
+}
@@ -24,13 +24,15 @@ use yasna::models::ObjectIdentifier;
 use std::cell::RefCell;
 use std::fmt::Debug;
 use std::fs::File;
-use std::io::Read;
+use std::io::{stderr, Read, Write};
 use std::path::PathBuf;
 use std::time::Duration;
 
 use crate::{
+    audit::{self, AuditEntry},
     config::{AuthProvider, ExecAuth, ExecProvider},
     error::{ClickErrNo, ClickError},
+    values::val_str,
 };
 
 #[derive(Clone)]
@@ -175,6 +177,7 @@ pub struct Context {
     impersonate_user: Option<String>,
     connect_timeout_secs: u32,
     read_timeout_secs: u32,
+    audit_log: Option<PathBuf>,
 }
 
 impl Context {
@@ -213,6 +216,61 @@ impl Context {
             impersonate_user,
             connect_timeout_secs,
             read_timeout_secs,
+            audit_log: None,
+        }
+    }
+
+    /// Record every request that changes the cluster in the audit log at path
+    pub fn set_audit_log(&mut self, path: PathBuf) {
+        self.audit_log = Some(path);
+    }
+
+    /// Add an entry to the audit log, if there is one. This is done for every request made with
+    /// this context that changes something, and can also be called for actions that don't go
+    /// through the context, like exec or port-forward via kubectl
+    pub fn audit(
+        &self,
+        method: &str,
+        path: &str,
+        impersonate_user: Option<&str>,
+        dry_run: bool,
+        outcome: String,
+    ) {
+        if let Some(log) = self.audit_log.as_ref() {
+            let user = impersonate_user.or(self.impersonate_user.as_deref());
+            let entry = AuditEntry::new(&self.name, method, path, user, dry_run, outcome);
+            if let Err(e) = audit::append(log, &entry) {
+                writeln!(
+                    stderr(),
+                    "[WARN] Could not write to audit log {}: {e}",
+                    log.display()
+                )
+                .unwrap_or(());
+            }
+        }
+    }
+
+    // the method, path, and whether it's a dry run, for requests that should be audited
+    fn audit_info(parts: &http::request::Parts, body: &[u8]) -> Option<(String, String, bool)> {
+        if !audit::is_mutating(&parts.method) {
+            return None;
+        }
+        let dry_run = audit::is_dry_run(parts.uri.query(), body);
+        Some((parts.method.to_string(), parts.uri.to_string(), dry_run))
+    }
+
+    fn audit_response(
+        &self,
+        info: Option<(String, String, bool)>,
+        impersonate_user: Option<&str>,
+        resp: &Result<reqwest::blocking::Response, reqwest::Error>,
+    ) {
+        if let Some((method, path, dry_run)) = info {
+            let outcome = match resp {
+                Ok(resp) => resp.status().to_string(),
+                Err(e) => format!("error: {e}"),
+            };
+            self.audit(&method, &path, impersonate_user, dry_run, outcome);
         }
     }
 
@@ -310,6 +368,7 @@ impl Context {
         k8sreq: http::Request<Vec<u8>>,
     ) -> Result<http::Response<Bytes>, ClickError> {
         let (parts, body) = k8sreq.into_parts();
+        let audit_info = Context::audit_info(&parts, &body);
 
         let url = self.endpoint.join(&parts.uri.to_string())?;
 
@@ -329,6 +388,8 @@ impl Context {
             http::method::Method::GET => self.client.borrow().get(url),
             http::method::Method::POST => self.client.borrow().post(url),
             http::method::Method::DELETE => self.client.borrow().delete(url),
+            http::method::Method::PATCH => self.client.borrow().patch(url),
+            http::method::Method::PUT => self.client.borrow().put(url),
             _ => unimplemented!(),
         };
 
@@ -360,7 +421,9 @@ impl Context {
             },
             None => req,
         };
-        let resp = req.send()?;
+        let resp = req.send();
+        self.audit_response(audit_info, impersonate_user, &resp);
+        let resp = resp?;
         let stat = resp.status();
         let bytes = resp.bytes()?;
 
@@ -379,6 +442,7 @@ impl Context {
         timeout: Option<Duration>,
     ) -> Result<reqwest::blocking::Response, ClickError> {
         let (parts, body) = k8sreq.into_parts();
+        let audit_info = Context::audit_info(&parts, &body);
 
         let url = self.endpoint.join(&parts.uri.to_string())?;
 
@@ -390,6 +454,8 @@ impl Context {
             http::method::Method::GET => self.log_client.borrow().get(url),
             http::method::Method::POST => self.log_client.borrow().post(url),
             http::method::Method::DELETE => self.log_client.borrow().delete(url),
+            http::method::Method::PATCH => self.log_client.borrow().patch(url),
+            http::method::Method::PUT => self.log_client.borrow().put(url),
             _ => unimplemented!(),
         };
 
@@ -427,7 +493,9 @@ impl Context {
             None => req, // log_client above already has a super long timeout
         };
 
-        let resp = req.send()?;
+        let resp = req.send();
+        self.audit_response(audit_info, impersonate_user, &resp);
+        let resp = resp?;
 
         if resp.status().is_success() {
             Ok(resp)
@@ -448,6 +516,13 @@ impl Context {
     ) -> Result<T, ClickError> {
         let response = self.execute(impersonate_user, k8sreq)?;
         let status_code: http::StatusCode = response.status();
+        if status_code == http::StatusCode::NOT_FOUND {
+            // an error rather than an Other response, so it's reported (and exits) as not found
+            let message = serde_json::from_slice::<serde_json::Value>(response.body())
+                .map(|status| val_str("/message", &status, "Not found").into_owned())
+                .unwrap_or_else(|_| "Not found".to_string());
+            return Err(ClickError::UnexpectedStatus(status_code.as_u16(), message));
+        }
         match k8s_openapi::Response::try_from_parts(status_code, response.body()) {
             Ok((res, _)) => Ok(res),
             // Need more response data. We're blocking, so this is a hard error
@@ -474,9 +549,9 @@ impl Context {
                     if status_code == http::StatusCode::UNAUTHORIZED {
                         return Err(ClickError::Kube(ClickErrNo::Unauthorized));
                     } else {
-                        return Err(ClickError::ParseErr(
-                            // TODO maybe a special error type for this
-                            format!("Got unexpected status {status_code} {other:?}"),
+                        return Err(ClickError::UnexpectedStatus(
+                            status_code.as_u16(),
+                            format!("{other:?}"),
                         ));
                     }
                 }
@@ -486,3 +561,56 @@ impl Context {
         Ok(res_list)
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::error::ErrorClass;
+
+    use k8s_openapi::api::core::v1 as api;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    /// Respond to every request made to the returned url with status (like "200 OK") and body
+    pub fn serve(status: &'static str, body: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok() && line.trim_end() != "" {
+                    line.clear();
+                }
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_read_not_found() {
+        let status = serde_json::json!({
+            "kind": "Status",
+            "apiVersion": "v1",
+            "status": "Failure",
+            "message": "pods \"web-1\" not found",
+            "reason": "NotFound",
+            "code": 404,
+        });
+        let url = serve("404 Not Found", status.to_string());
+        let context = Context::new("test", url.parse().unwrap(), None, None, None, 5, 5);
+        let (request, _) =
+            api::Pod::read_namespaced_pod("web-1", "default", Default::default()).unwrap();
+        let err = context
+            .read::<api::ReadNamespacedPodResponse>(None, request)
+            .unwrap_err();
+        assert_eq!(err.class(), ErrorClass::NotFound);
+        assert!(err.to_string().contains("pods \"web-1\" not found"));
+    }
+}
@@ -27,7 +27,7 @@ use serde_json::Value;
 
 use std::io::Write;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum ObjType {
     Pod {
         containers: Vec<String>,
@@ -54,7 +54,7 @@ pub enum ObjType {
 }
 
 /// An object we can have as a "current" thing
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct KObj {
     pub name: String,
     pub namespace: Option<String>,
@@ -132,10 +132,10 @@ impl KObj {
                 let (request, _) = $read_func(&self.name, Default::default())?;
                 match env
                     .run_on_context(|c| c.read::<$resp_typ>(env.get_impersonate_user(), request))
-                    .unwrap()
+                    ?
                 {
                     $resp_ok(t) => {
-                        if !describe::maybe_full_describe_output(matches, &t, writer) {
+                        if !describe::maybe_full_describe_output(matches, &t, &env.styles, writer) {
                             $(
                                 $desc_func(&t, &mut table)?;
                             )*
@@ -153,10 +153,10 @@ impl KObj {
                         let (request, _) = $read_func(&self.name, ns, Default::default())?;
                         match env
                             .run_on_context(|c| c.read::<$resp_typ>(env.get_impersonate_user(), request))
-                            .unwrap()
+                            ?
                         {
                             $resp_ok(t) => {
-                                if !describe::maybe_full_describe_output(matches, &t, writer) {
+                                if !describe::maybe_full_describe_output(matches, &t, &env.styles, writer) {
                                     $(
                                         $desc_func(&t, &mut table)?;
                                     )*
@@ -241,7 +241,7 @@ impl KObj {
                     api::Pod::read_namespaced_pod,
                     api::ReadNamespacedPodResponse,
                     api::ReadNamespacedPodResponse::Ok,
-                    describe::legacy::describe_format_pod
+                    |pod, table| describe::legacy::describe_format_pod(pod, &env.styles, table)
                 );
             }
             ObjType::ReplicaSet => {
@@ -57,6 +57,9 @@ extern crate yasna;
 mod command;
 #[macro_use]
 mod completer;
+mod audit;
+mod cert;
+mod columns;
 mod command_processor;
 mod config;
 mod crd;
@@ -67,8 +70,12 @@ mod k8s;
 mod k8s_table;
 mod kobj;
 mod parser;
+mod picker;
+mod prompt;
+mod script;
 mod styles;
 mod table;
+mod tui;
 mod values;
 
 #[cfg(test)]
@@ -76,11 +83,14 @@ mod duct_mock;
 
 use clap::{Arg, Command as ClapCommand};
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use crate::command::session::load_session;
 use crate::command_processor::CommandProcessor;
-use crate::config::{ClickConfig, Config};
+use crate::config::{ClickConfig, Config, Session, LAST_SESSION};
 use crate::env::Env;
+use crate::error::ErrorClass;
+use crate::script::{Script, CLICKRC};
 
 use crate::output::ClickWriter;
 
@@ -103,9 +113,41 @@ fn main() {
             Arg::new("exec")
                 .long("exec")
                 .value_name("COMMAND")
-                .help("Execute the specified command then exit")
+                .help(
+                    "Execute the specified command then exit. Separate multiple commands with ';'",
+                )
+                .conflicts_with("script")
                 .takes_value(true),
         )
+        .arg(
+            Arg::new("script")
+                .long("script")
+                .value_name("FILE")
+                .help("Run the commands in the specified file then exit")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("tui")
+                .long("tui")
+                .conflicts_with_all(&["exec", "script"])
+                .help("Open the full screen dashboard (see 'help dashboard') instead of the repl"),
+        )
+        .arg(
+            Arg::new("stop_on_error")
+                .long("stop-on-error")
+                .requires("script")
+                .help("Stop running the script after the first command that fails"),
+        )
+        .arg(
+            Arg::new("json_errors")
+                .long("json-errors")
+                .help("Print errors to stderr as json, one object per line"),
+        )
+        .arg(
+            Arg::new("read_only")
+                .long("read-only")
+                .help("Refuse to run commands that change the cluster, like delete, edit and exec"),
+        )
         .arg(
             Arg::new("context")
                 .short('C')
@@ -171,26 +213,67 @@ fn main() {
         Ok(c) => c,
         Err(e) => {
             println!("Could not load kubernetes config. Cannot continue.  Error was: {e}");
-            return;
+            std::process::exit(e.class().exit_code());
         }
     };
 
-    let mut hist_path = conf_dir;
+    let mut hist_path = conf_dir.clone();
     hist_path.push("click.history");
 
+    let mut rc_path = conf_dir.clone();
+    rc_path.push(CLICKRC);
+
     let mut env = Env::new(config, click_conf, click_path);
+    let exec = matches.get_one::<String>("exec").map(|s| s.as_str());
+    let script = matches.get_one::<String>("script").map(|s| s.as_str());
+    if env.click_config.restore_session && exec.is_none() && script.is_none() {
+        let mut writer = ClickWriter::new();
+        let session_path = Session::path_for(env.click_config_dir(), LAST_SESSION);
+        if matches!(session_path, Ok(ref path) if path.exists()) {
+            if let Err(e) = load_session(&mut env, LAST_SESSION, &mut writer) {
+                println!("Could not restore last session: {e}");
+            }
+        }
+    }
+    let mut processor = CommandProcessor::new(env, hist_path);
+    processor.set_json_errors(matches.contains_id("json_errors"));
+    processor
+        .env_mut()
+        .set_read_only(matches.contains_id("read_only"));
+    if rc_path.exists() {
+        match Script::from_file(&rc_path, false) {
+            Ok(rc) => {
+                // errors are already reported, and shouldn't stop click from starting
+                let _ = processor.run_script(rc);
+            }
+            Err(e) => processor.report_error(CLICKRC, &e, &mut ClickWriter::new()),
+        }
+    }
+    // command line args override anything done in clickrc
     if let Some(context) = matches.get_one::<String>("context").map(|s| s.as_str()) {
-        env.set_context(Some(context));
+        processor.env_mut().set_context(Some(context));
     }
     if let Some(namespace) = matches.get_one::<String>("namespace").map(|s| s.as_str()) {
-        env.set_namespace(Some(namespace));
+        processor.env_mut().set_namespace(Some(namespace));
     }
 
-    let mut processor = CommandProcessor::new(env, hist_path);
-    if let Some(command) = matches.get_one::<String>("exec").map(|s| s.as_str()) {
-        let writer = ClickWriter::new();
-        processor.process_line(command, writer);
+    let res = if let Some(command) = exec {
+        processor.run_commands(command)
+    } else if let Some(file) = script {
+        match Script::from_file(Path::new(file), matches.contains_id("stop_on_error")) {
+            Ok(script) => processor.run_script(script),
+            Err(e) => {
+                processor.report_error(file, &e, &mut ClickWriter::new());
+                Err(ErrorClass::Usage)
+            }
+        }
+    } else if matches.contains_id("tui") {
+        processor.run_dashboard("pods")
     } else {
         processor.run_repl();
+        Ok(())
+    };
+    if let Err(class) = res {
+        std::process::exit(class.exit_code());
     }
 }
@@ -14,7 +14,9 @@
 
 /// Module to handle writing data to stdout, and/or copying/writing it
 /// to files etc
-use crossterm::style::{Attribute, Color, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::style::{
+    Attribute, ContentStyle, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
 use duct::Handle;
 use duct_sh::sh_dangerous;
 use os_pipe::{pipe, PipeWriter};
@@ -27,6 +29,7 @@ use std::io;
 use std::io::{Stdout, Write};
 
 use crate::error::ClickError;
+use crate::styles::Styles;
 
 /// Ignore write errors (for now) TODO: What to do with them?
 macro_rules! clickwrite {
@@ -78,7 +81,6 @@ impl PipeProc {
 
 enum WriterOutput {
     Stdout(Stdout),
-    #[allow(dead_code)] // used in test
     Buffer(Vec<u8>),
     File(File),
     Pipe(Box<PipeProc>),
@@ -86,28 +88,41 @@ enum WriterOutput {
 
 pub struct ClickWriter {
     output: WriterOutput,
+    // if output is being collected in a buffer, also collect the output of any pipe rather than
+    // printing it
+    capture: bool,
 }
 
 impl ClickWriter {
     pub fn new() -> ClickWriter {
         ClickWriter {
             output: WriterOutput::Stdout(std::io::stdout()),
+            capture: false,
         }
     }
 
-    #[allow(dead_code)] // used in test
     pub fn with_buffer(buffer: Vec<u8>, _do_color: bool) -> ClickWriter {
         ClickWriter {
             output: WriterOutput::Buffer(buffer),
+            capture: true,
         }
     }
 
+    /// True if this writer is collecting its output in a buffer, rather than writing it out
+    pub fn is_buffer(&self) -> bool {
+        self.capture
+    }
+
     pub fn set_output_file(&mut self, file: File) {
         self.output = WriterOutput::File(file);
     }
 
     pub fn setup_pipe(&mut self, cmd: &str) -> Result<(), ClickError> {
-        let expr = sh_dangerous(cmd);
+        let expr = if self.capture {
+            sh_dangerous(cmd).stdout_capture()
+        } else {
+            sh_dangerous(cmd)
+        };
         let (pipe_read, pipe_write) = pipe()?;
         let handle = expr.stdin_file(pipe_read).start()?;
         self.output = WriterOutput::Pipe(Box::new(PipeProc {
@@ -121,6 +136,9 @@ impl ClickWriter {
         match self.output {
             WriterOutput::Pipe(pipe_proc) => {
                 match pipe_proc.finish() {
+                    Ok(out) if self.capture => {
+                        return Some(out.into_bytes());
+                    }
                     Ok(out) => {
                         print!("{out}");
                     }
@@ -135,12 +153,18 @@ impl ClickWriter {
         }
     }
 
-    pub fn pretty_color_json<T: ?Sized>(&mut self, value: &T) -> Result<(), JsonError>
+    pub fn pretty_color_json<T: ?Sized>(
+        &mut self,
+        value: &T,
+        styles: &Styles,
+    ) -> Result<(), JsonError>
     where
         T: Serialize,
     {
         if let WriterOutput::Stdout(_) = self.output {
-            let mut ser = Serializer::with_formatter(self, PrettyColorFormatter::new());
+            let formatter =
+                PrettyColorFormatter::new(styles.get("json_key"), styles.get("json_string"));
+            let mut ser = Serializer::with_formatter(self, formatter);
             value.serialize(&mut ser)
         } else {
             // don't do color if we're piping/redirecting
@@ -186,18 +210,40 @@ pub struct PrettyColorFormatter<'a> {
     pretty: PrettyFormatter<'a>,
     invalue: bool,
     iskey: bool,
+    key_style: ContentStyle,
+    string_style: ContentStyle,
 }
 
 impl<'a> PrettyColorFormatter<'a> {
-    pub fn new() -> PrettyColorFormatter<'a> {
+    pub fn new(key_style: ContentStyle, string_style: ContentStyle) -> PrettyColorFormatter<'a> {
         PrettyColorFormatter {
             pretty: PrettyFormatter::new(),
             invalue: false,
             iskey: false,
+            key_style,
+            string_style,
         }
     }
 }
 
+fn begin_style<W: ?Sized + Write>(writer: &mut W, style: &ContentStyle) {
+    if let Some(fg) = style.foreground_color {
+        write!(writer, "{}", SetForegroundColor(fg)).unwrap_or(());
+    }
+    if let Some(bg) = style.background_color {
+        write!(writer, "{}", SetBackgroundColor(bg)).unwrap_or(());
+    }
+    for attr in Attribute::iterator().filter(|attr| style.attributes.has(*attr)) {
+        write!(writer, "{}", SetAttribute(attr)).unwrap_or(());
+    }
+}
+
+fn end_style<W: ?Sized + Write>(writer: &mut W, style: &ContentStyle) {
+    if *style != ContentStyle::new() {
+        write!(writer, "{}{ResetColor}", SetAttribute(Attribute::Reset)).unwrap_or(());
+    }
+}
+
 impl<'a> Formatter for PrettyColorFormatter<'a> {
     fn write_null<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
     where
@@ -288,7 +334,7 @@ impl<'a> Formatter for PrettyColorFormatter<'a> {
         W: Write,
     {
         if self.invalue && !self.iskey {
-            write!(writer, "{}", SetForegroundColor(Color::Green)).unwrap_or(());
+            begin_style(writer, &self.string_style);
         }
         self.pretty.begin_string(writer)
     }
@@ -299,7 +345,7 @@ impl<'a> Formatter for PrettyColorFormatter<'a> {
     {
         let r = self.pretty.end_string(writer);
         if self.invalue && !self.iskey {
-            write!(writer, "{ResetColor}").unwrap_or(());
+            end_style(writer, &self.string_style);
         }
         r
     }
@@ -370,8 +416,7 @@ impl<'a> Formatter for PrettyColorFormatter<'a> {
     {
         self.iskey = true;
         let r = self.pretty.begin_object_key(writer, first);
-        write!(writer, "{}", SetForegroundColor(Color::Blue)).unwrap_or(());
-        write!(writer, "{}", SetAttribute(Attribute::Bold)).unwrap_or(());
+        begin_style(writer, &self.key_style);
         r
     }
 
@@ -381,7 +426,7 @@ impl<'a> Formatter for PrettyColorFormatter<'a> {
     {
         let r = self.pretty.end_object_key(writer);
         self.iskey = false;
-        write!(writer, "{ResetColor}").unwrap_or(());
+        end_style(writer, &self.key_style);
         r
     }
 
@@ -17,6 +17,8 @@
 // This is take and modified from:
 // https://github.com/klemens/cmdline-parser/blob/master/src/unix.rs
 
+use crate::error::ClickError;
+
 use std::iter::Peekable;
 use std::ops::Range;
 use std::str::CharIndices;
@@ -180,10 +182,330 @@ pub fn try_parse_csl(line: &str) -> Option<Box<dyn Iterator<Item = usize>>> {
     Some(Box::new(ret.into_iter()))
 }
 
+/// Quote an argument so that it's parsed back as the same, single, argument
+pub fn quote_arg(arg: &str) -> String {
+    let needs_quotes = arg.is_empty()
+        || arg.chars().any(|c| {
+            c.is_whitespace() || matches!(c, '\'' | '"' | '\\' | '|' | '>' | ';' | '&' | '$')
+        });
+    if needs_quotes {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    } else {
+        arg.to_string()
+    }
+}
+
+/// Something in a line that gets substituted by substitute_vars
+#[derive(Debug, PartialEq)]
+pub enum VarRef<'a> {
+    /// $name or ${name}
+    Name(&'a str),
+    /// $(command), replaced with the output of command
+    Capture(&'a str),
+}
+
+fn is_var_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// find the index of the ) that closes a $(. s should start just after the $(
+fn find_capture_end(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut escaped = false;
+    let mut quote: Option<char> = None;
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match (quote, c) {
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => escaped = true,
+            (Some(_), _) => {}
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') if depth == 0 => return Some(i),
+            (None, ')') => depth -= 1,
+            (None, _) => {}
+        }
+    }
+    None
+}
+
+/// Substitute variables ($name or ${name}) and command output ($(command)) in line, using lookup
+/// to get their values. Like in a shell, nothing is substituted inside single quotes or after a \.
+/// Substituted values are always a single argument, and are quoted so that running this again on
+/// the result doesn't substitute anything new. A $ that isn't followed by a valid variable name is
+/// left alone, so alias parameters like $1 and $@ are untouched. A variable that lookup returns
+/// None for is also left as it was. Nothing after the first unquoted | or > is substituted, since
+/// that's passed to the shell, which will substitute its own variables.
+pub fn substitute_vars<F>(line: &str, mut lookup: F) -> Result<String, ClickError>
+where
+    F: FnMut(VarRef) -> Result<Option<String>, ClickError>,
+{
+    let mut out = String::with_capacity(line.len());
+    let mut quote: Option<char> = None;
+    let mut i = 0;
+    while let Some(c) = line[i..].chars().next() {
+        match (quote, c) {
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => {
+                // keep the escape, and whatever it's escaping, for the Parser to handle
+                out.push(c);
+                i += 1;
+                if let Some(next) = line[i..].chars().next() {
+                    out.push(next);
+                    i += next.len_utf8();
+                }
+                continue;
+            }
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (None, '|') | (None, '>') => {
+                out.push_str(&line[i..]);
+                return Ok(out);
+            }
+            (_, '$') => {
+                let rest = &line[i + 1..];
+                let (var, len) = if let Some(inner) = rest.strip_prefix('(') {
+                    let end = find_capture_end(inner).ok_or_else(|| {
+                        ClickError::ParseErr(format!("Parse error at {i}: unterminated $("))
+                    })?;
+                    // $( + command + )
+                    (Some(VarRef::Capture(&inner[..end])), end + 3)
+                } else if let Some(inner) = rest.strip_prefix('{') {
+                    match inner.find('}') {
+                        Some(end) if is_var_name(&inner[..end]) => {
+                            (Some(VarRef::Name(&inner[..end])), end + 3)
+                        }
+                        _ => (None, 1),
+                    }
+                } else {
+                    let end = rest
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                        .unwrap_or(rest.len());
+                    if is_var_name(&rest[..end]) {
+                        (Some(VarRef::Name(&rest[..end])), end + 1)
+                    } else {
+                        (None, 1)
+                    }
+                };
+                match var.map(&mut lookup).transpose()?.flatten() {
+                    Some(val) => {
+                        if quote.is_some() && !val.contains(['"', '\\', '$']) {
+                            out.push_str(&val);
+                        } else if quote.is_some() {
+                            // end the double quotes so the value can be single quoted
+                            out.push('"');
+                            out.push_str(&quote_arg(&val));
+                            out.push('"');
+                        } else {
+                            out.push_str(&quote_arg(&val));
+                        }
+                    }
+                    None => out.push_str(&line[i..i + len]),
+                }
+                i += len;
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+        i += c.len_utf8();
+    }
+    Ok(out)
+}
+
+/// How a command in a chain is joined to the one before it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChainOp {
+    /// ; (or the first command), always run
+    Then,
+    /// &&, only run if the previous command succeeded
+    And,
+    /// ||, only run if the previous command failed
+    Or,
+}
+
+/// Split a line into a chain of commands at each ;, && and || that isn't quoted or escaped. Each
+/// command is returned with the operator that joins it to the previous one. Empty commands around a
+/// ; are dropped, but && and || need a command on both sides.
+pub fn split_chain(line: &str) -> Result<Vec<(ChainOp, &str)>, ClickError> {
+    // first find where all the separators are: (position, length, op)
+    let mut seps = vec![];
+    let mut escaped = false;
+    let mut quote: Option<char> = None;
+    // how many $( we're inside of, nothing in a $() splits the outer line
+    let mut depth = 0;
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        let next = chars.peek().map(|(_, c)| *c);
+        match (quote, c) {
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => escaped = true,
+            (_, '$') if next == Some('(') => {
+                chars.next();
+                depth += 1;
+            }
+            (_, ')') if depth > 0 => depth -= 1,
+            (Some(_), _) => {}
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (None, _) if depth > 0 => {}
+            (None, ';') => seps.push((i, 1, ChainOp::Then)),
+            (None, '&') if next == Some('&') => {
+                chars.next();
+                seps.push((i, 2, ChainOp::And));
+            }
+            (None, '|') if next == Some('|') => {
+                chars.next();
+                seps.push((i, 2, ChainOp::Or));
+            }
+            (None, _) => {}
+        }
+    }
+    seps.push((line.len(), 0, ChainOp::Then));
+
+    // now build the chain out of what's between the separators
+    let mut chain = vec![];
+    let mut op = ChainOp::Then;
+    let mut start = 0;
+    for (pos, len, next_op) in seps.into_iter() {
+        let cmd = line[start..pos].trim();
+        if cmd.is_empty() {
+            if len > 0 && next_op != ChainOp::Then {
+                return Err(ClickError::ParseErr(format!(
+                    "Parse error at {pos}: unexpected {}",
+                    &line[pos..pos + len]
+                )));
+            }
+            match op {
+                ChainOp::Then => {}
+                ChainOp::And | ChainOp::Or => {
+                    let sep = if op == ChainOp::And { "&&" } else { "||" };
+                    return Err(ClickError::ParseErr(format!(
+                        "Parse error at {pos}: expected a command after {sep}"
+                    )));
+                }
+            }
+        } else {
+            chain.push((op, cmd));
+        }
+        op = next_op;
+        start = pos + len;
+    }
+    Ok(chain)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_substitute_vars() {
+        let lookup = |var: VarRef| match var {
+            VarRef::Name("pod") => Ok(Some("web-1".to_string())),
+            VarRef::Name("spaced") => Ok(Some("a b".to_string())),
+            VarRef::Name("dollar") => Ok(Some("$pod".to_string())),
+            VarRef::Name("fail") => Err(ClickError::CommandError("fail failed".to_string())),
+            VarRef::Name(_) => Ok(None),
+            VarRef::Capture(cmd) => Ok(Some(format!("out of {cmd}"))),
+        };
+        let sub = |line: &str| substitute_vars(line, lookup).unwrap();
+
+        assert_eq!(sub("logs $pod"), "logs web-1");
+        assert_eq!(sub("logs ${pod}-x"), "logs web-1-x");
+        assert_eq!(sub("pods -r $spaced"), "pods -r 'a b'");
+        assert_eq!(sub("echo \"$spaced!\""), "echo \"a b!\"");
+        // nothing happens in single quotes or after an escape
+        assert_eq!(sub("echo '$pod' \\$pod"), "echo '$pod' \\$pod");
+        // alias parameters and lone $s are left alone
+        assert_eq!(sub("logs $1 $@ ${2:-x} $ $"), "logs $1 $@ ${2:-x} $ $");
+        assert_eq!(
+            sub("let x = $(pods | grep \"a)\")"),
+            "let x = 'out of pods | grep \"a)\"'"
+        );
+        // substituting again doesn't change anything
+        let once = sub("echo $dollar \"$dollar\"");
+        assert_eq!(once, "echo '$pod' \"\"'$pod'\"\"");
+        assert_eq!(sub(&once), once);
+
+        assert!(substitute_vars("logs $fail", lookup).is_err());
+        // unknown variables are left for the shell
+        assert_eq!(sub("logs $nope ${nope}"), "logs $nope ${nope}");
+        // nothing after a pipe or redirect is substituted, even in double quotes
+        assert_eq!(
+            sub("logs $pod | grep \"$pod\" $HOME"),
+            "logs web-1 | grep \"$pod\" $HOME"
+        );
+        assert_eq!(sub("pods > $HOME/$pod.txt"), "pods > $HOME/$pod.txt");
+        assert_eq!(sub("echo \"a | $pod\" '>'"), "echo \"a | web-1\" '>'");
+        assert_eq!(
+            substitute_vars("x $(pods", lookup).unwrap_err().to_string(),
+            "Parse Error: Parse error at 2: unterminated $("
+        );
+
+        // $() isn't split up by split_chain
+        assert_eq!(
+            split_chain("let x = $(pods; logs) && logs").unwrap(),
+            vec![
+                (ChainOp::Then, "let x = $(pods; logs)"),
+                (ChainOp::And, "logs")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_chain() {
+        assert_eq!(split_chain("pods").unwrap(), vec![(ChainOp::Then, "pods")]);
+        assert!(split_chain("  ").unwrap().is_empty());
+        assert_eq!(
+            split_chain("ns kube-system; pods -l app=dns ;1;; logs;").unwrap(),
+            vec![
+                (ChainOp::Then, "ns kube-system"),
+                (ChainOp::Then, "pods -l app=dns"),
+                (ChainOp::Then, "1"),
+                (ChainOp::Then, "logs"),
+            ]
+        );
+        assert_eq!(
+            split_chain("delete && pods | grep foo || logs > /tmp/out").unwrap(),
+            vec![
+                (ChainOp::Then, "delete"),
+                (ChainOp::And, "pods | grep foo"),
+                (ChainOp::Or, "logs > /tmp/out"),
+            ]
+        );
+        // quoted and escaped separators aren't split on
+        assert_eq!(
+            split_chain("pods | grep 'a;b' ; echo \"c && d\"; a\\;b & c").unwrap(),
+            vec![
+                (ChainOp::Then, "pods | grep 'a;b'"),
+                (ChainOp::Then, "echo \"c && d\""),
+                (ChainOp::Then, "a\\;b & c"),
+            ]
+        );
+        assert_eq!(
+            split_chain("&& pods").unwrap_err().to_string(),
+            "Parse Error: Parse error at 0: unexpected &&"
+        );
+        assert_eq!(
+            split_chain("pods ||").unwrap_err().to_string(),
+            "Parse Error: Parse error at 7: expected a command after ||"
+        );
+        assert_eq!(
+            split_chain("pods && ; logs").unwrap_err().to_string(),
+            "Parse Error: Parse error at 8: expected a command after &&"
+        );
+    }
+
     #[test]
     fn try_parse_csl_test() {
         let v: Vec<usize> = try_parse_csl("1,2,3").unwrap().collect();
@@ -0,0 +1,348 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An in-terminal fuzzy finder, used by the pick command to choose objects from the last list
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, Clear, ClearType},
+    tty::IsTty,
+};
+
+use crate::tui::{fit, text_lines, TerminalGuard};
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Stdout, Write};
+
+// lines used by the query, the match count, and the header
+const TOP_LINES: u16 = 3;
+// don't show the preview if the terminal is narrower than this
+const MIN_PREVIEW_WIDTH: u16 = 80;
+
+/// Score how well query fuzzy matches text, or None if it doesn't match. Every char of the query
+/// has to appear in text in order (ignoring case). Matches at the start of words and runs of
+/// consecutive matching chars score higher, and gaps between matches lower the score.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().flat_map(|c| c.to_lowercase()).collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last_match: Option<usize> = None;
+    for qc in query.chars().flat_map(|c| c.to_lowercase()) {
+        if qc.is_whitespace() {
+            continue;
+        }
+        let found = pos + text[pos..].iter().position(|&c| c == qc)?;
+        score += 1;
+        match last_match {
+            Some(last) if last + 1 == found => score += 5,
+            Some(last) => score -= ((found - last - 1) as i64).min(5),
+            None => {}
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last_match = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+/// Turn a list of rows into lines with aligned columns, and return the header as a line as well
+pub fn align_rows(header: &[String], rows: &[Vec<String>]) -> (String, Vec<String>) {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in rows.iter() {
+        for (i, cell) in row.iter().enumerate() {
+            let len = cell.chars().count();
+            match widths.get_mut(i) {
+                Some(width) => *width = (*width).max(len),
+                None => widths.push(len),
+            }
+        }
+    }
+    let align = |cells: &[String]| -> String {
+        let line: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| format!("{:width$}", cell, width = widths[i]))
+            .collect();
+        line.join("  ").trim_end().to_string()
+    };
+    (align(header), rows.iter().map(|row| align(row)).collect())
+}
+
+struct Picker<'a, F: FnMut(usize) -> String> {
+    header: &'a str,
+    lines: &'a [String],
+    query: String,
+    /// indexes into lines of the lines that match query, best match first
+    matched: Vec<usize>,
+    /// position of the highlighted line in matched
+    cursor: usize,
+    /// first line of matched that's shown
+    scroll: usize,
+    selected: HashSet<usize>,
+    preview: Option<F>,
+    previews: HashMap<usize, Vec<String>>,
+}
+
+impl<'a, F: FnMut(usize) -> String> Picker<'a, F> {
+    fn update_matches(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter_map(|(i, line)| fuzzy_score(&self.query, line).map(|score| (score, i)))
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        self.matched = scored.into_iter().map(|(_, i)| i).collect();
+        self.cursor = 0;
+        self.scroll = 0;
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        if self.matched.is_empty() {
+            return;
+        }
+        let max = self.matched.len() as isize - 1;
+        self.cursor = (self.cursor as isize + delta).clamp(0, max) as usize;
+    }
+
+    fn toggle_current(&mut self) {
+        if let Some(&index) = self.matched.get(self.cursor) {
+            if !self.selected.remove(&index) {
+                self.selected.insert(index);
+            }
+        }
+    }
+
+    fn preview_lines(&mut self, index: usize) -> &Vec<String> {
+        if !self.previews.contains_key(&index) {
+            let text = match self.preview.as_mut() {
+                Some(preview) => preview(index),
+                None => String::new(),
+            };
+            self.previews.insert(index, text_lines(&text));
+        }
+        &self.previews[&index]
+    }
+
+    fn draw(&mut self, stdout: &mut Stdout) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let show_preview = self.preview.is_some() && width >= MIN_PREVIEW_WIDTH;
+        let list_width = if show_preview { width / 2 } else { width } as usize;
+        let list_height = height.saturating_sub(TOP_LINES) as usize;
+
+        // keep the cursor on screen
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if list_height > 0 && self.cursor >= self.scroll + list_height {
+            self.scroll = self.cursor + 1 - list_height;
+        }
+
+        queue!(stdout, Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        queue!(stdout, Print(fit(&format!("> {}", self.query), list_width)))?;
+        let status = format!(
+            "  {}/{} ({} selected)",
+            self.matched.len(),
+            self.lines.len(),
+            self.selected.len()
+        );
+        queue!(
+            stdout,
+            cursor::MoveTo(0, 1),
+            SetAttribute(Attribute::Dim),
+            Print(fit(&status, list_width)),
+            cursor::MoveTo(0, 2),
+            Print(fit(&format!("  {}", self.header), list_width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+
+        for (row, &index) in self
+            .matched
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(list_height)
+            .map(|(pos, index)| (pos - self.scroll, index))
+        {
+            let marker = if self.selected.contains(&index) {
+                "*"
+            } else {
+                " "
+            };
+            let line = fit(&format!("{marker} {}", self.lines[index]), list_width);
+            queue!(stdout, cursor::MoveTo(0, row as u16 + TOP_LINES))?;
+            if row + self.scroll == self.cursor {
+                queue!(
+                    stdout,
+                    SetAttribute(Attribute::Reverse),
+                    Print(format!("{line:list_width$}")),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                queue!(stdout, Print(line))?;
+            }
+        }
+
+        if show_preview {
+            let preview_col = list_width as u16;
+            let preview_width = (width as usize).saturating_sub(list_width + 2);
+            for row in 0..height {
+                queue!(stdout, cursor::MoveTo(preview_col, row), Print("│"))?;
+            }
+            if let Some(&index) = self.matched.get(self.cursor) {
+                let lines = self.preview_lines(index).clone();
+                for (row, line) in lines.iter().take(height as usize).enumerate() {
+                    queue!(
+                        stdout,
+                        cursor::MoveTo(preview_col + 2, row as u16),
+                        Print(fit(line, preview_width))
+                    )?;
+                }
+            }
+        }
+        stdout.flush()
+    }
+
+    /// Handle a key press. Returns Some(true) to accept, Some(false) to cancel, or None to keep
+    /// going
+    fn handle_key(&mut self, key: KeyEvent) -> Option<bool> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let page = terminal::size()
+            .map(|(_, h)| h.saturating_sub(TOP_LINES).max(1) as isize)
+            .unwrap_or(10);
+        match key.code {
+            KeyCode::Enter => return Some(true),
+            KeyCode::Esc => return Some(false),
+            KeyCode::Char('c') | KeyCode::Char('g') if ctrl => return Some(false),
+            KeyCode::Up => self.move_cursor(-1),
+            KeyCode::Char('p') | KeyCode::Char('k') if ctrl => self.move_cursor(-1),
+            KeyCode::Down => self.move_cursor(1),
+            KeyCode::Char('n') | KeyCode::Char('j') if ctrl => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-page),
+            KeyCode::PageDown => self.move_cursor(page),
+            KeyCode::Tab => {
+                self.toggle_current();
+                self.move_cursor(1);
+            }
+            KeyCode::BackTab => {
+                self.toggle_current();
+                self.move_cursor(-1);
+            }
+            KeyCode::Char('a') if ctrl => {
+                // select everything that matches
+                self.selected.extend(self.matched.iter());
+            }
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.update_matches();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.update_matches();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.update_matches();
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Let the user pick from lines with a fuzzy finder. header is shown above the lines. If preview
+/// is given, it's called with the index of the highlighted line to get text to show beside the
+/// list. Returns the indexes of the picked lines, which are the lines selected with Tab, or the
+/// highlighted line if none were selected. Returns None if the user cancelled.
+pub fn pick<F: FnMut(usize) -> String>(
+    header: &str,
+    lines: &[String],
+    preview: Option<F>,
+) -> io::Result<Option<Vec<usize>>> {
+    if !io::stdout().is_tty() {
+        return Err(io::Error::other(
+            "pick needs a terminal, and output is redirected",
+        ));
+    }
+    let mut picker = Picker {
+        header,
+        lines,
+        query: String::new(),
+        matched: vec![],
+        cursor: 0,
+        scroll: 0,
+        selected: HashSet::new(),
+        preview,
+        previews: HashMap::new(),
+    };
+    picker.update_matches();
+
+    let mut guard = TerminalGuard::new()?;
+    loop {
+        picker.draw(guard.stdout())?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match picker.handle_key(key) {
+                Some(true) => break,
+                Some(false) => return Ok(None),
+                None => {}
+            }
+        }
+    }
+
+    if picker.selected.is_empty() {
+        Ok(picker.matched.get(picker.cursor).map(|&index| vec![index]))
+    } else {
+        let mut picked: Vec<usize> = picker.selected.into_iter().collect();
+        picked.sort_unstable();
+        Ok(Some(picked))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert!(fuzzy_score("xyz", "web-1").is_none());
+        assert!(fuzzy_score("wb1", "web-1").is_some());
+        assert!(fuzzy_score("CRASH", "CrashLoopBackOff").is_some());
+        // in order only
+        assert!(fuzzy_score("1bew", "web-1").is_none());
+        // consecutive and word start matches beat scattered ones
+        assert!(fuzzy_score("api", "api-server") > fuzzy_score("api", "a-pod-in-x"));
+        assert!(fuzzy_score("db", "web db-0") > fuzzy_score("db", "odd-bit"));
+    }
+
+    #[test]
+    fn test_align_rows() {
+        let (header, lines) = align_rows(
+            &["Name".to_string(), "Status".to_string()],
+            &[
+                vec!["web-123".to_string(), "Running".to_string()],
+                vec!["db".to_string(), "Pending".to_string()],
+            ],
+        );
+        assert_eq!(header, "Name     Status");
+        assert_eq!(lines, vec!["web-123  Running", "db       Pending"]);
+    }
+}
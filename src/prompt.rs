@@ -0,0 +1,288 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Prompt templates, set with the prompt option. A template is text with placeholders like
+//! {context} that are replaced each time the prompt is shown.
+
+use crossterm::style::ContentStyle;
+
+use crate::error::ClickError;
+use crate::styles::Styles;
+
+/// The template used if the prompt option isn't set
+pub const DEFAULT_PROMPT: &str = "[{context}] [{namespace}] [{selection}] > ";
+
+/// The placeholders that can be used in a template
+pub const PLACEHOLDERS: [&str; 10] = [
+    "context",
+    "namespace",
+    "selection",
+    "type",
+    "user",
+    "version",
+    "forwards",
+    "status",
+    "duration",
+    "time",
+];
+
+/// The value of a placeholder, and the style to use for it when the template doesn't give one
+pub struct PromptValue {
+    pub text: String,
+    pub style: ContentStyle,
+}
+
+impl PromptValue {
+    pub fn new(text: impl Into<String>, style: ContentStyle) -> PromptValue {
+        PromptValue {
+            text: text.into(),
+            style,
+        }
+    }
+
+    pub fn empty() -> PromptValue {
+        PromptValue::new("", ContentStyle::new())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Piece {
+    Text(String),
+    Placeholder {
+        name: String,
+        style: Option<ContentStyle>,
+    },
+    /// Only shown if every placeholder in it has a value
+    Group(Vec<Piece>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PromptTemplate {
+    pieces: Vec<Piece>,
+}
+
+fn parse_placeholder(spec: &str, styles: &Styles) -> Result<Piece, ClickError> {
+    let (name, style) = match spec.split_once(':') {
+        Some((name, style)) => (name.trim(), Some(style)),
+        None => (spec.trim(), None),
+    };
+    if !PLACEHOLDERS.contains(&name) {
+        return Err(ClickError::CommandError(format!(
+            "Unknown prompt placeholder {{{name}}}, expected one of: {}",
+            PLACEHOLDERS.join(", ")
+        )));
+    }
+    let style = style
+        .map(|style| styles.resolve(style))
+        .transpose()
+        .map_err(|e| ClickError::CommandError(format!("Invalid style for {{{name}}}: {e}")))?;
+    Ok(Piece::Placeholder {
+        name: name.to_string(),
+        style,
+    })
+}
+
+impl PromptTemplate {
+    /// Parse a template. Placeholders are written as {name}, or {name:style} to change how the
+    /// value looks. The style is a list of words as in the theme section of the config, and can
+    /// also use the names of styles from the theme, like {user:danger reverse}. Text in
+    /// parentheses is only shown if every placeholder in it has a value, so "(as {user} )" only
+    /// shows anything when impersonating. Use \ to include a literal {, }, ( or ).
+    pub fn parse(template: &str, styles: &Styles) -> Result<PromptTemplate, ClickError> {
+        // the pieces of each group we're in, the outermost first
+        let mut stack: Vec<Vec<Piece>> = vec![vec![]];
+        let mut text = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => text.push(chars.next().unwrap_or('\\')),
+                '{' => {
+                    let mut spec = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => spec.push(c),
+                            None => {
+                                return Err(ClickError::CommandError(
+                                    "Unclosed { in prompt".to_string(),
+                                ))
+                            }
+                        }
+                    }
+                    let pieces = stack.last_mut().unwrap(); // never empty
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(parse_placeholder(&spec, styles)?);
+                }
+                '(' => {
+                    if !text.is_empty() {
+                        let pieces = stack.last_mut().unwrap();
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    stack.push(vec![]);
+                }
+                ')' => {
+                    if stack.len() < 2 {
+                        return Err(ClickError::CommandError(
+                            "Unmatched ) in prompt, use \\) for a literal )".to_string(),
+                        ));
+                    }
+                    let mut group = stack.pop().unwrap();
+                    if !text.is_empty() {
+                        group.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    stack.last_mut().unwrap().push(Piece::Group(group));
+                }
+                '}' => {
+                    return Err(ClickError::CommandError(
+                        "Unmatched } in prompt, use \\} for a literal }".to_string(),
+                    ))
+                }
+                c => text.push(c),
+            }
+        }
+        if stack.len() > 1 {
+            return Err(ClickError::CommandError("Unclosed ( in prompt".to_string()));
+        }
+        let mut pieces = stack.pop().unwrap();
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        Ok(PromptTemplate { pieces })
+    }
+
+    /// Check if the template uses the named placeholder
+    pub fn uses(&self, name: &str) -> bool {
+        fn uses_in(pieces: &[Piece], name: &str) -> bool {
+            pieces.iter().any(|piece| match piece {
+                Piece::Text(_) => false,
+                Piece::Placeholder { name: n, .. } => n == name,
+                Piece::Group(group) => uses_in(group, name),
+            })
+        }
+        uses_in(&self.pieces, name)
+    }
+
+    /// Render the template, getting the value of each placeholder from value
+    pub fn render<F: Fn(&str) -> PromptValue>(&self, value: F) -> String {
+        // returns the rendered pieces, and false if any placeholder was empty
+        fn render_pieces<F: Fn(&str) -> PromptValue>(
+            pieces: &[Piece],
+            value: &F,
+        ) -> (String, bool) {
+            let mut out = String::new();
+            let mut complete = true;
+            for piece in pieces.iter() {
+                match piece {
+                    Piece::Text(text) => out.push_str(text),
+                    Piece::Placeholder { name, style } => {
+                        let val = value(name);
+                        if val.text.is_empty() {
+                            complete = false;
+                        } else {
+                            let style = style.unwrap_or(val.style);
+                            out.push_str(&style.apply(val.text).to_string());
+                        }
+                    }
+                    Piece::Group(group) => {
+                        let (rendered, group_complete) = render_pieces(group, value);
+                        if group_complete {
+                            out.push_str(&rendered);
+                        }
+                    }
+                }
+            }
+            (out, complete)
+        }
+        render_pieces(&self.pieces, &value).0
+    }
+}
+
+/// Format how long a command took for the prompt, like 250ms, 1.5s or 2m 5s
+pub fn format_command_duration(duration: std::time::Duration) -> String {
+    if duration.as_secs() == 0 {
+        format!("{}ms", duration.as_millis())
+    } else if duration.as_secs() < 10 {
+        format!("{:.1}s", duration.as_secs_f64())
+    } else {
+        match chrono::Duration::from_std(duration) {
+            Ok(d) => crate::command::format_duration(d),
+            Err(_) => format!("{}s", duration.as_secs()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ThemeConfig;
+
+    fn plain_styles() -> Styles {
+        Styles::build(&ThemeConfig::default(), false).unwrap()
+    }
+
+    fn values(name: &str) -> PromptValue {
+        match name {
+            "context" => PromptValue::new("prod", ContentStyle::new()),
+            "namespace" => PromptValue::new("web", ContentStyle::new()),
+            "forwards" => PromptValue::new("2", ContentStyle::new()),
+            _ => PromptValue::empty(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let styles = plain_styles();
+        let render = |t: &str| PromptTemplate::parse(t, &styles).unwrap().render(values);
+        assert_eq!(render(DEFAULT_PROMPT), "[prod] [web] [] > ");
+        assert_eq!(render("{context}/{namespace:red bold}$ "), "prod/web$ ");
+        // groups are hidden if any placeholder in them is empty
+        assert_eq!(
+            render("{context}( as {user})( fwd:{forwards}) > "),
+            "prod fwd:2 > "
+        );
+        assert_eq!(render("((a {user}) b {context})"), " b prod");
+        assert_eq!(render("\\(\\{context\\}\\) >"), "({context}) >");
+    }
+
+    #[test]
+    fn test_render_styles() {
+        let styles = Styles::build(&ThemeConfig::default(), true).unwrap();
+        let template = PromptTemplate::parse("{context:danger reverse}", &styles).unwrap();
+        let style = styles.resolve("danger reverse").unwrap();
+        assert_eq!(template.render(values), style.apply("prod").to_string());
+        assert!(template.uses("context"));
+        assert!(!template.uses("version"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let styles = plain_styles();
+        assert!(PromptTemplate::parse("{cluster}", &styles).is_err());
+        assert!(PromptTemplate::parse("{context:sparkly}", &styles).is_err());
+        assert!(PromptTemplate::parse("{context", &styles).is_err());
+        assert!(PromptTemplate::parse("(as {user}", &styles).is_err());
+        assert!(PromptTemplate::parse("{context})", &styles).is_err());
+        assert!(PromptTemplate::parse("}", &styles).is_err());
+    }
+
+    #[test]
+    fn test_format_command_duration() {
+        use std::time::Duration;
+        assert_eq!(format_command_duration(Duration::from_millis(250)), "250ms");
+        assert_eq!(format_command_duration(Duration::from_millis(1540)), "1.5s");
+        assert_eq!(format_command_duration(Duration::from_secs(125)), "2m 5s");
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Files of click commands, run with --script, the source command, or from clickrc at startup
+
+use crate::error::ClickError;
+
+use std::fs;
+use std::path::Path;
+
+/// The name of the file in the config dir that's run each time click starts
+pub const CLICKRC: &str = "clickrc";
+
+/// A script to run. Each line is a click command, and comments and blank lines have already been
+/// removed.
+#[derive(Debug, PartialEq)]
+pub struct Script {
+    /// Name to use when reporting errors, usually the path of the file
+    pub name: String,
+    /// (line number, command) for each command in the script. Line numbers start at 1
+    pub lines: Vec<(usize, String)>,
+    pub stop_on_error: bool,
+}
+
+impl Script {
+    pub fn from_contents(name: &str, contents: &str, stop_on_error: bool) -> Script {
+        let lines = contents
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    None
+                } else {
+                    Some((i + 1, line.to_string()))
+                }
+            })
+            .collect();
+        Script {
+            name: name.to_string(),
+            lines,
+            stop_on_error,
+        }
+    }
+
+    pub fn from_file(path: &Path, stop_on_error: bool) -> Result<Script, ClickError> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            ClickError::CommandError(format!("Could not read {}: {e}", path.display()))
+        })?;
+        Ok(Script::from_contents(
+            &path.display().to_string(),
+            &contents,
+            stop_on_error,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_from_contents() {
+        let script = Script::from_contents(
+            "test",
+            "# select the context\nctx prod\n\n  # list pods\n  pods\nlogs -f # not a comment\n",
+            true,
+        );
+        assert_eq!(script.name, "test");
+        assert!(script.stop_on_error);
+        assert_eq!(
+            script.lines,
+            vec![
+                (2, "ctx prod".to_string()),
+                (5, "pods".to_string()),
+                (6, "logs -f # not a comment".to_string()),
+            ]
+        );
+    }
+}
@@ -12,118 +12,315 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-/// Module that defines methods to color click output appropriately
-use crossterm::style::{Attribute, Attributes, Color, ContentStyle, StyledContent, Stylize};
+//! Module that defines methods to color click output appropriately. Every style has a name, and
+//! the styles come from one of the built-in themes, with any overrides from the theme section of
+//! the click config applied on top.
+
+use crossterm::style::{Attribute, Color, ContentStyle, StyledContent, Stylize};
+use crossterm::tty::IsTty;
+
+use crate::config::ThemeConfig;
+use crate::error::ClickError;
+
 use std::collections::HashMap;
 
-pub struct Styles {
-    prompt_object_map: HashMap<&'static str, ContentStyle>,
-}
+/// The built-in themes. The first is the default
+pub const THEMES: [&str; 3] = ["dark", "light", "monochrome"];
 
-macro_rules! style {
-    ($style_name:ident, $arg:ident $style_apply:block) => {
-        pub fn $style_name<'a>(&self, $arg: &'a str) -> StyledContent<&'a str> $style_apply
-    };
+// Themes are written the same way as the styles in the theme section of the config, see
+// parse_style. Each theme must define every style, since this is also the list of valid names.
+const DARK_THEME: &[(&str, &str)] = &[
+    ("Pod", "yellow bold"),
+    ("Crd", "blue bold"),
+    ("Node", "blue bold"),
+    ("DaemonSet", "yellow bold"),
+    ("Deployment", "magenta bold"),
+    ("Service", "cyan bold"),
+    ("ReplicaSet", "green bold"),
+    ("StatefulSet", "green bold"),
+    ("ConfigMap", "magenta bold"),
+    ("Secret", "red bold"),
+    ("CronJob", "green bold"),
+    ("Job", "magenta bold"),
+    ("PersistentVolume", "blue bold"),
+    ("StorageClass", "red bold"),
+    ("Rollout", "magenta bold"),
+    ("success", "dark_green"),
+    ("warning", "dark_yellow"),
+    ("danger", "dark_red"),
+    ("info", "dark_blue"),
+    ("prompt_context", "red bold"),
+    ("prompt_namespace", "green bold"),
+    ("prompt_range", "blue"),
+    ("prompt_select_none", "dark_yellow"),
+    ("config_val", "yellow"),
+    ("context_table", "red"),
+    ("json_key", "blue bold"),
+    ("json_string", "green"),
+];
+
+// only dark colors, since the bright ones are hard to read on a light background
+const LIGHT_THEME: &[(&str, &str)] = &[
+    ("Pod", "dark_blue bold"),
+    ("Crd", "dark_cyan bold"),
+    ("Node", "dark_cyan bold"),
+    ("DaemonSet", "dark_blue bold"),
+    ("Deployment", "dark_magenta bold"),
+    ("Service", "dark_cyan bold"),
+    ("ReplicaSet", "dark_green bold"),
+    ("StatefulSet", "dark_green bold"),
+    ("ConfigMap", "dark_magenta bold"),
+    ("Secret", "dark_red bold"),
+    ("CronJob", "dark_green bold"),
+    ("Job", "dark_magenta bold"),
+    ("PersistentVolume", "dark_blue bold"),
+    ("StorageClass", "dark_red bold"),
+    ("Rollout", "dark_magenta bold"),
+    ("success", "dark_green"),
+    ("warning", "130"),
+    ("danger", "dark_red"),
+    ("info", "dark_blue"),
+    ("prompt_context", "dark_red bold"),
+    ("prompt_namespace", "dark_green bold"),
+    ("prompt_range", "dark_blue"),
+    ("prompt_select_none", "dark_magenta"),
+    ("config_val", "dark_blue"),
+    ("context_table", "dark_red"),
+    ("json_key", "dark_blue bold"),
+    ("json_string", "dark_green"),
+];
+
+const MONOCHROME_THEME: &[(&str, &str)] = &[
+    ("Pod", "bold"),
+    ("Crd", "bold"),
+    ("Node", "bold"),
+    ("DaemonSet", "bold"),
+    ("Deployment", "bold"),
+    ("Service", "bold"),
+    ("ReplicaSet", "bold"),
+    ("StatefulSet", "bold"),
+    ("ConfigMap", "bold"),
+    ("Secret", "bold"),
+    ("CronJob", "bold"),
+    ("Job", "bold"),
+    ("PersistentVolume", "bold"),
+    ("StorageClass", "bold"),
+    ("Rollout", "bold"),
+    ("success", "none"),
+    ("warning", "underline"),
+    ("danger", "bold"),
+    ("info", "none"),
+    ("prompt_context", "bold"),
+    ("prompt_namespace", "bold"),
+    ("prompt_range", "underline"),
+    ("prompt_select_none", "dim"),
+    ("config_val", "none"),
+    ("context_table", "none"),
+    ("json_key", "bold"),
+    ("json_string", "none"),
+];
+
+/// Check if output should be colored. It shouldn't be if the NO_COLOR environment variable is set
+/// to anything (see https://no-color.org), or if stdout isn't a terminal.
+pub fn color_enabled() -> bool {
+    let no_color = matches!(std::env::var_os("NO_COLOR"), Some(v) if !v.is_empty());
+    !no_color && std::io::stdout().is_tty()
 }
 
-macro_rules! obj_style {
-    ($fg: expr) => {
-        ContentStyle {
-            foreground_color: Some($fg),
-            background_color: None,
-            underline_color: None,
-            attributes: Attributes::default(),
+fn parse_color(s: &str) -> Result<Color, String> {
+    if let Ok(color) = Color::try_from(s) {
+        return Ok(color);
+    }
+    if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() == 6 {
+            let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            if let (Some(r), Some(g), Some(b)) = (component(0), component(2), component(4)) {
+                return Ok(Color::Rgb { r, g, b });
+            }
         }
-    };
-    ($fg: expr, $attrs: expr) => {
-        ContentStyle {
-            foreground_color: Some($fg),
-            background_color: None,
-            underline_color: None,
-            attributes: $attrs,
+    }
+    s.parse::<u8>()
+        .map(Color::AnsiValue)
+        .map_err(|_| format!("unknown color '{s}'"))
+}
+
+/// Parse a style. This is a space separated list of words, each of which is one of:
+///  - a color: a name like red or dark_red, an ansi color number (0-255), or #rrggbb
+///  - on_<color>: a background color, like on_blue
+///  - an attribute: bold, dim, italic, underline, or reverse
+///  - none: no style (this is useful to remove the style a theme sets)
+pub fn parse_style(spec: &str) -> Result<ContentStyle, String> {
+    let mut style = ContentStyle::new();
+    for word in spec.split_whitespace() {
+        let word = word.to_lowercase();
+        match word.as_str() {
+            "none" => {}
+            "bold" => style.attributes.set(Attribute::Bold),
+            "dim" => style.attributes.set(Attribute::Dim),
+            "italic" => style.attributes.set(Attribute::Italic),
+            "underline" => style.attributes.set(Attribute::Underlined),
+            "reverse" => style.attributes.set(Attribute::Reverse),
+            _ => match word.strip_prefix("on_") {
+                Some(bg) => style.background_color = Some(parse_color(bg)?),
+                None => style.foreground_color = Some(parse_color(&word)?),
+            },
         }
-    };
+    }
+    Ok(style)
 }
 
-lazy_static! {
-    static ref BOLD: Attributes = {
-        let mut attrs = Attributes::default();
-        attrs.set(Attribute::Bold);
-        attrs
+fn theme_styles(theme: &[(&str, &str)]) -> HashMap<String, ContentStyle> {
+    theme
+        .iter()
+        .map(|(name, spec)| {
+            let style = parse_style(spec).expect("Invalid style in built-in theme");
+            (name.to_string(), style)
+        })
+        .collect()
+}
+
+pub struct Styles {
+    styles: HashMap<String, ContentStyle>,
+    color: bool,
+}
+
+macro_rules! style {
+    ($style_name:ident) => {
+        pub fn $style_name<'a>(&self, s: &'a str) -> StyledContent<&'a str> {
+            self.get(stringify!($style_name)).apply(s)
+        }
     };
-    static ref NOSTYLE: ContentStyle = ContentStyle::new();
 }
 
 impl Styles {
+    /// The default theme, colored if color_enabled says it should be
     pub fn new() -> Styles {
-        let prompt_object_map = HashMap::from([
-            ("Pod", obj_style!(Color::Yellow, *BOLD)),
-            ("Crd", obj_style!(Color::Blue, *BOLD)),
-            ("Node", obj_style!(Color::Blue, *BOLD)),
-            ("DaemonSet", obj_style!(Color::Yellow, *BOLD)),
-            ("Deployment", obj_style!(Color::Magenta, *BOLD)),
-            ("Service", obj_style!(Color::Cyan, *BOLD)),
-            ("ReplicaSet", obj_style!(Color::Green, *BOLD)),
-            ("StatefulSet", obj_style!(Color::Green, *BOLD)),
-            ("ConfigMap", obj_style!(Color::Magenta, *BOLD)),
-            ("Secret", obj_style!(Color::Red, *BOLD)),
-            ("CronJob", obj_style!(Color::Green, *BOLD)),
-            ("Job", obj_style!(Color::Magenta, *BOLD)),
-            ("PersistentVolume", obj_style!(Color::Blue, *BOLD)),
-            ("StorageClass", obj_style!(Color::Red, *BOLD)),
-            #[cfg(feature = "argorollouts")]
-            ("Rollout", obj_style!(Color::Magenta, *BOLD)),
-        ]);
-
-        Styles { prompt_object_map }
+        Styles {
+            styles: theme_styles(DARK_THEME),
+            color: color_enabled(),
+        }
     }
 
-    pub fn prompt_object<'a>(&self, name: &'a str, type_str: &str) -> StyledContent<&'a str> {
-        match self.prompt_object_map.get(type_str) {
-            Some(style) => style.apply(name),
-            None => NOSTYLE.apply(name),
+    /// Build styles from the theme section of the config
+    pub fn with_theme(theme: &ThemeConfig) -> Result<Styles, ClickError> {
+        Styles::build(theme, color_enabled())
+    }
+
+    /// Build styles from theme, with or without color
+    pub fn build(theme: &ThemeConfig, color: bool) -> Result<Styles, ClickError> {
+        let base = match theme.base.as_deref().unwrap_or(THEMES[0]) {
+            "dark" => DARK_THEME,
+            "light" => LIGHT_THEME,
+            "monochrome" => MONOCHROME_THEME,
+            other => {
+                return Err(ClickError::ConfigFileError(format!(
+                    "Unknown theme '{other}', expected one of: {}",
+                    THEMES.join(", ")
+                )))
+            }
+        };
+        let mut styles = theme_styles(base);
+        for (name, spec) in theme.styles.iter() {
+            if !styles.contains_key(name) {
+                return Err(ClickError::ConfigFileError(format!(
+                    "Unknown style name '{name}' in theme"
+                )));
+            }
+            let style = parse_style(spec).map_err(|e| {
+                ClickError::ConfigFileError(format!("Invalid style for {name}: {e}"))
+            })?;
+            styles.insert(name.clone(), style);
+        }
+        Ok(Styles { styles, color })
+    }
+
+    /// Whether these styles produce any colors or attributes at all
+    pub fn color_enabled(&self) -> bool {
+        self.color
+    }
+
+    /// Get the style with the specified name. This is the empty style if color is disabled or
+    /// there is no style by that name
+    pub fn get(&self, name: &str) -> ContentStyle {
+        match self.styles.get(name) {
+            Some(style) if self.color => *style,
+            _ => ContentStyle::new(),
         }
     }
 
+    /// Parse a style as parse_style does, but also allow the names of styles in this theme, so
+    /// "danger reverse" is the danger style, reversed
+    pub fn resolve(&self, spec: &str) -> Result<ContentStyle, String> {
+        let mut style = ContentStyle::new();
+        for word in spec.split_whitespace() {
+            let word_style = match self.styles.get(word) {
+                Some(word_style) => *word_style,
+                None => parse_style(word)?,
+            };
+            if word_style.foreground_color.is_some() {
+                style.foreground_color = word_style.foreground_color;
+            }
+            if word_style.background_color.is_some() {
+                style.background_color = word_style.background_color;
+            }
+            style.attributes.extend(word_style.attributes);
+        }
+        Ok(if self.color {
+            style
+        } else {
+            ContentStyle::new()
+        })
+    }
+
+    pub fn prompt_object<'a>(&self, name: &'a str, type_str: &str) -> StyledContent<&'a str> {
+        self.get(type_str).apply(name)
+    }
+
     // general colors
-    style!(success, s {s.dark_green()});
-    style!(warning, s {s.dark_yellow()});
-    style!(danger,  s {s.dark_red()});
+    style!(success);
+    style!(warning);
+    style!(danger);
 
     // prompt colors
-    style!(prompt_context,     s {s.red().bold()});
-    style!(prompt_namespace,   s {s.green().bold()});
-    style!(prompt_range,       s {s.blue()});
-    style!(prompt_select_none, s {s.dark_yellow()});
+    style!(prompt_context);
+    style!(prompt_namespace);
+    style!(prompt_range);
+    style!(prompt_select_none);
 
     // config printing colors
     // TODO: Maybe add this
-    // style!(config_key, s {s.red()});
+    // style!(config_key);
     pub fn config_val_string(&self, s: String) -> StyledContent<String> {
-        s.yellow()
+        self.get("config_val").apply(s)
     }
-    style!(config_val, s {s.yellow()});
+    style!(config_val);
 
-    // other colors
-    pub fn success_color(&self) -> Color {
-        Color::DarkGreen
+    // other colors, these are None if the theme doesn't set a foreground color for them
+    pub fn success_color(&self) -> Option<Color> {
+        self.get("success").foreground_color
     }
-    pub fn warning_color(&self) -> Color {
-        Color::DarkYellow
+    pub fn warning_color(&self) -> Option<Color> {
+        self.get("warning").foreground_color
     }
-    pub fn danger_color(&self) -> Color {
-        Color::DarkRed
+    pub fn danger_color(&self) -> Option<Color> {
+        self.get("danger").foreground_color
     }
-    pub fn info_color(&self) -> Color {
-        Color::DarkBlue
+    pub fn info_color(&self) -> Option<Color> {
+        self.get("info").foreground_color
     }
 
-    pub fn context_table_color(&self) -> Color {
-        Color::Red
+    pub fn context_table_color(&self) -> Option<Color> {
+        self.get("context_table").foreground_color
     }
 
     // attributes
-    style!(bold, s {s.bold()});
+    pub fn bold<'a>(&self, s: &'a str) -> StyledContent<&'a str> {
+        if self.color {
+            s.bold()
+        } else {
+            ContentStyle::new().apply(s)
+        }
+    }
 }
 
 impl Default for Styles {
@@ -131,3 +328,67 @@ impl Default for Styles {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_style() {
+        let style = parse_style("dark_red on_#0a0B0c bold underline").unwrap();
+        assert_eq!(style.foreground_color, Some(Color::DarkRed));
+        assert_eq!(
+            style.background_color,
+            Some(Color::Rgb {
+                r: 10,
+                g: 11,
+                b: 12
+            })
+        );
+        assert!(style.attributes.has(Attribute::Bold));
+        assert!(style.attributes.has(Attribute::Underlined));
+        assert!(!style.attributes.has(Attribute::Italic));
+
+        assert_eq!(
+            parse_style("208").unwrap().foreground_color,
+            Some(Color::AnsiValue(208))
+        );
+        assert_eq!(parse_style("none").unwrap(), ContentStyle::new());
+        assert!(parse_style("purple").is_err());
+        assert!(parse_style("#12345").is_err());
+    }
+
+    #[test]
+    fn test_themes() {
+        // every built-in theme defines the same styles
+        for theme in [LIGHT_THEME, MONOCHROME_THEME] {
+            let mut names: Vec<&str> = theme.iter().map(|(name, _)| *name).collect();
+            let mut dark: Vec<&str> = DARK_THEME.iter().map(|(name, _)| *name).collect();
+            names.sort_unstable();
+            dark.sort_unstable();
+            assert_eq!(names, dark);
+        }
+
+        let mut theme = ThemeConfig {
+            base: Some("light".to_string()),
+            ..Default::default()
+        };
+        theme
+            .styles
+            .insert("Pod".to_string(), "cyan italic".to_string());
+        let styles = Styles::build(&theme, true).unwrap();
+        assert_eq!(styles.get("Pod").foreground_color, Some(Color::Cyan));
+        assert_eq!(styles.danger_color(), Some(Color::DarkRed));
+        assert!(styles.get("Namespace") == ContentStyle::new());
+
+        let styles = Styles::build(&theme, false).unwrap();
+        assert_eq!(styles.get("Pod"), ContentStyle::new());
+        assert_eq!(styles.danger_color(), None);
+
+        theme.base = Some("solarized".to_string());
+        assert!(Styles::build(&theme, true).is_err());
+        theme.base = None;
+        theme.styles.insert("Pods".to_string(), "red".to_string());
+        assert!(Styles::build(&theme, true).is_err());
+    }
+}
@@ -15,6 +15,8 @@
 use crate::command::format_duration;
 use crate::command::time_since;
 use crate::env::Env;
+use crate::error::ClickError;
+use crate::kobj::KObj;
 /// Stuff to handle outputting a table of resources, including
 /// applying filters and sorting
 use crate::output::ClickWriter;
@@ -44,6 +46,7 @@ enum CellSpecTxt<'a> {
     Str(Cow<'a, str>),
 }
 
+#[derive(Clone, Copy)]
 pub enum ColorType {
     Info,
     Success,
@@ -52,15 +55,27 @@ pub enum ColorType {
 }
 
 // An enum to hold either an actual color, or a color type like "success"
+#[derive(Clone, Copy)]
 pub enum TableColor {
     Color(Color),
     ColorType(ColorType),
 }
 
+/// Set the foreground color of cell, if there is one. This is useful with the color methods of
+/// Styles, which return None when there shouldn't be a color
+pub fn color_cell(cell: Cell, color: Option<Color>) -> Cell {
+    match color {
+        Some(color) => cell.fg(color),
+        None => cell,
+    }
+}
+
 impl TableColor {
-    fn to_color(&self, env: &Env) -> Color {
+    /// The color to use, or None if there shouldn't be one, because color is disabled or the
+    /// theme doesn't give this ColorType a color
+    fn to_color(self, env: &Env) -> Option<Color> {
         match self {
-            TableColor::Color(color) => *color,
+            TableColor::Color(color) => env.styles.color_enabled().then_some(color),
             TableColor::ColorType(color_type) => match color_type {
                 ColorType::Info => env.styles.info_color(),
                 ColorType::Success => env.styles.success_color(),
@@ -153,16 +168,14 @@ impl<'a> CellSpec<'a> {
             cell
         };
 
-        let cell = if let Some(fg) = &self.fg {
-            cell.fg(fg.to_color(env))
-        } else {
-            cell
+        let cell = match self.fg.and_then(|fg| fg.to_color(env)) {
+            Some(fg) => cell.fg(fg),
+            None => cell,
         };
 
-        if let Some(bg) = &self.bg {
-            cell.bg(bg.to_color(env))
-        } else {
-            cell
+        match self.bg.and_then(|bg| bg.to_color(env)) {
+            Some(bg) => cell.bg(bg),
+            None => cell,
         }
     }
 
@@ -471,9 +484,166 @@ pub fn print_table<T: Into<comfy_table::Row>>(
     table
 }
 
+/// How a `where` column filter compares a cell to its value
+#[derive(Debug, PartialEq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Match,
+    NotMatch,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A filter on the rows of a table, from a `| where` stage. Either a regex that matches if any
+/// cell in the row matches (skipping the first skip cells, so the #### index column isn't
+/// matched), or a comparison against one column, like `status=Running` or `restarts>=3`
+#[derive(Debug)]
+pub enum RowFilter {
+    Regex(Regex, usize),
+    Column {
+        index: usize,
+        op: FilterOp,
+        value: String,
+        regex: Option<Regex>,
+    },
+}
+
+/// Parse a cell as a number or a duration (like the ones in the Age column), so columns can be
+/// compared numerically
+fn cell_number(s: &str) -> Option<f64> {
+    let s = s.trim();
+    s.parse::<f64>()
+        .ok()
+        .or_else(|| humantime::parse_duration(s).ok().map(|d| d.as_secs_f64()))
+}
+
+impl RowFilter {
+    /// Parse expr into a filter for rows of table. If expr starts with the name of a column
+    /// followed by one of =, ==, !=, ~, !~, <, <=, > or >= it's a column comparison, otherwise
+    /// it's a regex
+    pub fn new(expr: &str, table: &comfy_table::Table) -> Result<RowFilter, ClickError> {
+        let column_re =
+            Regex::new(r"^\s*([^=!~<>]*?)\s*(==|=|!=|!~|~|<=|<|>=|>)\s*(.*?)\s*$").unwrap();
+        if let (Some(caps), Some(header)) = (column_re.captures(expr), table.header()) {
+            let col = &caps[1];
+            let index = header
+                .cell_iter()
+                .position(|cell| cell.content().eq_ignore_ascii_case(col));
+            if let Some(index) = index {
+                let op = match &caps[2] {
+                    "=" | "==" => FilterOp::Eq,
+                    "!=" => FilterOp::Ne,
+                    "~" => FilterOp::Match,
+                    "!~" => FilterOp::NotMatch,
+                    "<" => FilterOp::Lt,
+                    "<=" => FilterOp::Le,
+                    ">" => FilterOp::Gt,
+                    _ => FilterOp::Ge,
+                };
+                let value = caps[3].to_string();
+                let regex = match op {
+                    FilterOp::Match | FilterOp::NotMatch => {
+                        Some(Regex::new(&value).map_err(|e| {
+                            ClickError::ParseErr(format!("Invalid regex {value}: {e}"))
+                        })?)
+                    }
+                    _ => None,
+                };
+                return Ok(RowFilter::Column {
+                    index,
+                    op,
+                    value,
+                    regex,
+                });
+            }
+        }
+        let skip = table
+            .header()
+            .and_then(|header| header.cell_iter().next())
+            .map(|cell| usize::from(cell.content() == "####"))
+            .unwrap_or(0);
+        Regex::new(expr)
+            .map(|regex| RowFilter::Regex(regex, skip))
+            .map_err(|e| ClickError::ParseErr(format!("Invalid regex {expr}: {e}")))
+    }
+
+    fn matches(&self, row: &comfy_table::Row) -> bool {
+        match self {
+            RowFilter::Regex(regex, skip) => row
+                .cell_iter()
+                .skip(*skip)
+                .any(|cell| regex.is_match(&cell.content())),
+            RowFilter::Column {
+                index,
+                op,
+                value,
+                regex,
+            } => {
+                let content = match row.cell_iter().nth(*index) {
+                    Some(cell) => cell.content(),
+                    None => return false,
+                };
+                match op {
+                    FilterOp::Eq => content == *value,
+                    FilterOp::Ne => content != *value,
+                    FilterOp::Match => regex.as_ref().unwrap().is_match(&content),
+                    FilterOp::NotMatch => !regex.as_ref().unwrap().is_match(&content),
+                    _ => {
+                        let ordering = match (cell_number(&content), cell_number(value)) {
+                            (Some(l), Some(r)) => l.partial_cmp(&r),
+                            _ => Some(content.as_str().cmp(value.as_str())),
+                        };
+                        match ordering {
+                            Some(Ordering::Less) => matches!(op, FilterOp::Lt | FilterOp::Le),
+                            Some(Ordering::Equal) => matches!(op, FilterOp::Le | FilterOp::Ge),
+                            Some(Ordering::Greater) => matches!(op, FilterOp::Gt | FilterOp::Ge),
+                            None => false,
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Keep the rows of table (and the matching objects in objs) that match all of filters. If the
+/// first column is the index column it's renumbered so the new table can be used for selection.
+pub fn filter_table(
+    table: &comfy_table::Table,
+    objs: &[KObj],
+    filters: &[RowFilter],
+) -> (comfy_table::Table, Vec<KObj>) {
+    let renumber = table
+        .header()
+        .and_then(|header| header.cell_iter().next())
+        .map(|cell| cell.content() == "####")
+        .unwrap_or(false);
+    let mut filtered = comfy_table::Table::new();
+    if let Some(header) = table.header() {
+        filtered.set_header(header.clone());
+    }
+    let mut kept = vec![];
+    for (row, obj) in table.row_iter().zip(objs.iter()) {
+        if filters.iter().all(|filter| filter.matches(row)) {
+            let mut cells: Vec<Cell> = row.cell_iter().cloned().collect();
+            if renumber && !cells.is_empty() {
+                cells[0] = Cell::new(kept.len()).set_alignment(CellAlignment::Right);
+            }
+            filtered.add_row(cells);
+            kept.push(obj.clone());
+        }
+    }
+    (filtered, kept)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::table::raw_quantity;
+    use crate::kobj::{KObj, ObjType};
+    use crate::table::{filter_table, raw_quantity, RowFilter};
+    use comfy_table::Table;
     use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
 
     #[test]
@@ -489,4 +659,59 @@ mod tests {
         assert_eq!(raw_quantity(&Quantity("34".to_string())), 34.0);
         assert_eq!(raw_quantity(&Quantity("-3456".to_string())), -3456.0);
     }
+
+    fn filter_rows(table: &Table, objs: &[KObj], exprs: &[&str]) -> (Vec<Vec<String>>, usize) {
+        let filters: Vec<RowFilter> = exprs
+            .iter()
+            .map(|expr| RowFilter::new(expr, table).unwrap())
+            .collect();
+        let (filtered, kept) = filter_table(table, objs, &filters);
+        let rows = filtered
+            .row_iter()
+            .map(|row| row.cell_iter().map(|cell| cell.content()).collect())
+            .collect();
+        (rows, kept.len())
+    }
+
+    #[test]
+    fn test_filter_table() {
+        let mut table = Table::new();
+        table.set_header(vec!["####", "Name", "Restarts", "Age", "Status"]);
+        table.add_row(vec!["0", "web-1", "0", "5m 3s", "Running"]);
+        table.add_row(vec!["1", "web-2", "12", "2d 3h", "CrashLoopBackOff"]);
+        table.add_row(vec!["2", "db-0", "3", "1h 2m", "CrashLoopBackOff"]);
+        let objs: Vec<KObj> = ["web-1", "web-2", "db-0"]
+            .iter()
+            .map(|name| KObj {
+                name: name.to_string(),
+                namespace: Some("default".to_string()),
+                typ: ObjType::Pod { containers: vec![] },
+            })
+            .collect();
+
+        let (rows, kept) = filter_rows(&table, &objs, &["CrashLoop"]);
+        assert_eq!(kept, 2);
+        assert_eq!(rows[0][..2], ["0", "web-2"]);
+        assert_eq!(rows[1][..2], ["1", "db-0"]);
+
+        let (rows, _) = filter_rows(&table, &objs, &["status=Running"]);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0][1], "web-1");
+        assert_eq!(filter_rows(&table, &objs, &["status!=Running"]).1, 2);
+        assert_eq!(filter_rows(&table, &objs, &["name ~ ^web"]).1, 2);
+        assert_eq!(filter_rows(&table, &objs, &["name!~^web"]).1, 1);
+        assert_eq!(filter_rows(&table, &objs, &["restarts>=3"]).1, 2);
+        assert_eq!(filter_rows(&table, &objs, &["restarts < 3"]).1, 1);
+        assert_eq!(filter_rows(&table, &objs, &["age>1h"]).1, 2);
+        assert_eq!(
+            filter_rows(&table, &objs, &["CrashLoop", "restarts>10"]).0[0][1],
+            "web-2"
+        );
+        // not a column, so a regex
+        assert_eq!(filter_rows(&table, &objs, &["nope=1"]).1, 0);
+        // the regex isn't matched against the index column
+        assert_eq!(filter_rows(&table, &objs, &["^1$"]).1, 0);
+        assert_eq!(filter_rows(&table, &objs, &["^2"]).0[0][1], "web-2");
+        assert!(RowFilter::new("(bad", &table).is_err());
+    }
 }
@@ -0,0 +1,647 @@
+// Copyright 2021 Databricks, Inc.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Full screen terminal interfaces. This has the dashboard (click --tui, or the dashboard command),
+//! and helpers shared with the pick command.
+//!
+//! The dashboard doesn't talk to the cluster itself. Everything it shows comes from running the
+//! normal click commands (the list command, describe, events and logs) through the command
+//! processor and capturing their output, so it behaves just like the repl.
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, Clear, ClearType},
+    tty::IsTty,
+};
+use regex::Regex;
+
+use crate::command_processor::CommandProcessor;
+use crate::env::Env;
+use crate::error::ClickError;
+use crate::kobj::KObj;
+use crate::picker::{align_rows, fuzzy_score};
+
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+
+// how often the dashboard re-runs the list command
+const LIST_REFRESH: Duration = Duration::from_secs(10);
+// how often the dashboard re-fetches the logs of the selected pod
+const LOGS_REFRESH: Duration = Duration::from_secs(3);
+// how long the highlight has to stay on an object before its details are fetched, so moving
+// through the list doesn't fetch the details of every object passed over
+const DETAILS_DELAY: Duration = Duration::from_millis(250);
+// how many lines of logs the dashboard shows
+const LOG_LINES: usize = 100;
+
+lazy_static! {
+    static ref ANSI_RE: Regex = Regex::new("\u{1b}\\[[0-9;]*m").unwrap();
+}
+
+/// Puts the terminal into raw mode on an alternate screen, and puts it back when dropped, even if
+/// we're unwinding from a panic
+pub struct TerminalGuard {
+    stdout: Stdout,
+}
+
+impl TerminalGuard {
+    pub fn new() -> io::Result<TerminalGuard> {
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode()?;
+        queue!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+        stdout.flush()?;
+        Ok(TerminalGuard { stdout })
+    }
+
+    pub fn stdout(&mut self) -> &mut Stdout {
+        &mut self.stdout
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = queue!(self.stdout, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = self.stdout.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Truncate s so it fits in width columns
+pub fn fit(s: &str, width: usize) -> String {
+    s.chars().take(width).collect()
+}
+
+/// Split command output into lines that can be drawn in a pane. Panes are drawn a line at a time
+/// and truncated, so colors would be cut off part way through and are removed.
+pub fn text_lines(text: &str) -> Vec<String> {
+    ANSI_RE
+        .replace_all(text, "")
+        .lines()
+        .map(|l| l.replace('\t', "    "))
+        .collect()
+}
+
+/// The header and a line for each object of the last list, for showing in a full screen
+/// interface. The last table is used if there is one (without the index column), otherwise the
+/// name, type and namespace of each object are shown.
+pub fn list_lines(env: &Env, objs: &[KObj]) -> (String, Vec<String>) {
+    let table = env
+        .get_last_table()
+        .filter(|table| table.row_iter().count() == objs.len());
+    match table {
+        Some(table) => {
+            let header: Vec<String> = table
+                .header()
+                .map(|header| header.cell_iter().map(|cell| cell.content()).collect())
+                .unwrap_or_default();
+            let skip = usize::from(header.first().map(|h| h == "####").unwrap_or(false));
+            let rows: Vec<Vec<String>> = table
+                .row_iter()
+                .map(|row| {
+                    row.cell_iter()
+                        .skip(skip)
+                        .map(|cell| cell.content().replace('\n', " "))
+                        .collect()
+                })
+                .collect();
+            let header: Vec<String> = header.into_iter().skip(skip).collect();
+            align_rows(&header, &rows)
+        }
+        None => {
+            let rows: Vec<Vec<String>> = objs
+                .iter()
+                .map(|obj| {
+                    vec![
+                        obj.name().to_string(),
+                        obj.type_str().to_string(),
+                        obj.namespace.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            let header = vec![
+                "Name".to_string(),
+                "Type".to_string(),
+                "Namespace".to_string(),
+            ];
+            align_rows(&header, &rows)
+        }
+    }
+}
+
+/// What's being typed into the bottom line of the dashboard
+enum Input {
+    Filter(String),
+    ListCommand(String),
+    Exec(String),
+}
+
+const HINTS: &str = "j/k move  / filter  : list command  r refresh  d delete  e exec  l logs  \
+Enter select and exit  q quit";
+
+struct Dashboard {
+    list_cmd: String,
+    header: String,
+    lines: Vec<String>,
+    objs: Vec<KObj>,
+    filter: String,
+    /// indexes into objs of the objects that match filter
+    matched: Vec<usize>,
+    /// position of the highlighted object in matched
+    cursor: usize,
+    scroll: usize,
+    /// the object describe, events and logs are showing
+    shown: Option<KObj>,
+    describe: Vec<String>,
+    events: Vec<String>,
+    logs: Vec<String>,
+    input: Option<Input>,
+    message: String,
+    listed_at: Instant,
+    logs_at: Instant,
+}
+
+impl Dashboard {
+    fn new(list_cmd: &str) -> Dashboard {
+        Dashboard {
+            list_cmd: list_cmd.to_string(),
+            header: String::new(),
+            lines: vec![],
+            objs: vec![],
+            filter: String::new(),
+            matched: vec![],
+            cursor: 0,
+            scroll: 0,
+            shown: None,
+            describe: vec![],
+            events: vec![],
+            logs: vec![],
+            input: None,
+            message: String::new(),
+            listed_at: Instant::now(),
+            logs_at: Instant::now(),
+        }
+    }
+
+    fn current(&self) -> Option<&KObj> {
+        self.matched.get(self.cursor).map(|&i| &self.objs[i])
+    }
+
+    /// Find the objects that match the filter, keeping the highlight on keep if it still matches
+    fn update_matches(&mut self, keep: Option<KObj>) {
+        self.matched = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| fuzzy_score(&self.filter, line).is_some())
+            .map(|(i, _)| i)
+            .collect();
+        self.cursor = keep
+            .and_then(|cur| self.matched.iter().position(|&i| self.objs[i] == cur))
+            .unwrap_or(0);
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        if self.matched.is_empty() {
+            return;
+        }
+        let max = self.matched.len() as isize - 1;
+        self.cursor = (self.cursor as isize + delta).clamp(0, max) as usize;
+    }
+
+    /// Run the list command, and show the objects it returns
+    fn refresh_list(&mut self, processor: &mut CommandProcessor) {
+        let (output, ok) = processor.run_captured(&self.list_cmd);
+        self.listed_at = Instant::now();
+        if !ok {
+            self.message = text_lines(&output).join(" ");
+            return;
+        }
+        let keep = self.current().cloned();
+        let env = processor.env();
+        self.objs = env.get_last_objs().cloned().unwrap_or_default();
+        let (header, lines) = list_lines(env, &self.objs);
+        self.header = header;
+        self.lines = lines;
+        self.update_matches(keep);
+        // refetch the details, the object may have changed
+        self.shown = None;
+    }
+
+    /// Are the details being shown for some object other than the highlighted one
+    fn details_stale(&self) -> bool {
+        self.current() != self.shown.as_ref()
+    }
+
+    /// Select the highlighted object, and fetch its description, events and logs if it's changed
+    fn refresh_details(&mut self, processor: &mut CommandProcessor) {
+        let current = self.current().cloned();
+        if current == self.shown {
+            if current.as_ref().map(|obj| obj.is_pod()).unwrap_or(false)
+                && self.logs_at.elapsed() >= LOGS_REFRESH
+            {
+                self.refresh_logs(processor);
+            }
+            return;
+        }
+        self.shown = current.clone();
+        self.describe.clear();
+        self.events.clear();
+        self.logs.clear();
+        if let Some(obj) = current {
+            processor.env_mut().select_objs(vec![obj.clone()]);
+            self.describe = text_lines(&processor.run_captured("describe -e false").0);
+            self.events = text_lines(&processor.run_captured("events").0);
+            if obj.is_pod() {
+                self.refresh_logs(processor);
+            } else {
+                self.logs = vec!["Logs are only available for pods".to_string()];
+            }
+        }
+    }
+
+    fn refresh_logs(&mut self, processor: &mut CommandProcessor) {
+        let logs = processor.run_captured(&format!("logs -t {LOG_LINES}")).0;
+        self.logs = text_lines(&logs);
+        self.logs_at = Instant::now();
+    }
+
+    fn draw(&mut self, stdout: &mut Stdout, env: &Env) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let (width, height) = (width as usize, height as usize);
+        let list_width = (width * 45 / 100).max(30).min(width);
+        let list_height = height.saturating_sub(3);
+
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if list_height > 0 && self.cursor >= self.scroll + list_height {
+            self.scroll = self.cursor + 1 - list_height;
+        }
+
+        queue!(stdout, Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        let context = env.context.as_ref().map(|c| c.name.as_str());
+        let title = format!(
+            " [{}] [{}] {}  ({} of {} shown{})",
+            context.unwrap_or("none"),
+            env.namespace.as_deref().unwrap_or("none"),
+            self.list_cmd,
+            self.matched.len(),
+            self.objs.len(),
+            if self.filter.is_empty() {
+                String::new()
+            } else {
+                format!(", filter: {}", self.filter)
+            }
+        );
+        queue!(
+            stdout,
+            SetAttribute(Attribute::Reverse),
+            Print(format!("{:width$}", fit(&title, width))),
+            SetAttribute(Attribute::Reset),
+            cursor::MoveTo(0, 1),
+            SetAttribute(Attribute::Dim),
+            Print(fit(&format!(" {}", self.header), list_width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+
+        for (row, &index) in self
+            .matched
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(list_height)
+            .map(|(pos, index)| (pos - self.scroll, index))
+        {
+            let line = fit(&format!(" {}", self.lines[index]), list_width);
+            queue!(stdout, cursor::MoveTo(0, (row + 2) as u16))?;
+            if row + self.scroll == self.cursor {
+                queue!(
+                    stdout,
+                    SetAttribute(Attribute::Reverse),
+                    Print(format!("{line:list_width$}")),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                queue!(stdout, Print(line))?;
+            }
+        }
+
+        // the detail panes are stacked on the right: describe, events, then logs
+        let pane_col = list_width + 1;
+        let pane_width = width.saturating_sub(pane_col + 1);
+        let pane_rows = height.saturating_sub(2);
+        for row in 1..=pane_rows {
+            queue!(
+                stdout,
+                cursor::MoveTo(list_width as u16, row as u16),
+                Print("│")
+            )?;
+        }
+        let describe_rows = pane_rows * 40 / 100;
+        let events_rows = pane_rows * 25 / 100;
+        let logs_rows = pane_rows - describe_rows - events_rows;
+        // logs show their last lines, the others their first
+        let logs_skip = self.logs.len().saturating_sub(logs_rows.saturating_sub(1));
+        // until the highlight settles, don't show the details of the previous object
+        let loading = vec!["Loading...".to_string()];
+        let panes = if self.details_stale() && self.current().is_some() {
+            [
+                ("Describe", &loading, describe_rows, 0),
+                ("Events", &loading, events_rows, 0),
+                ("Logs", &loading, logs_rows, 0),
+            ]
+        } else {
+            [
+                ("Describe", &self.describe, describe_rows, 0),
+                ("Events", &self.events, events_rows, 0),
+                ("Logs", &self.logs, logs_rows, logs_skip),
+            ]
+        };
+        let mut top = 1;
+        for (title, lines, rows, skip) in panes {
+            if rows == 0 {
+                continue;
+            }
+            let title = format!("─ {title} {}", "─".repeat(pane_width));
+            queue!(
+                stdout,
+                cursor::MoveTo(pane_col as u16, top as u16),
+                SetAttribute(Attribute::Bold),
+                Print(fit(&title, pane_width)),
+                SetAttribute(Attribute::Reset)
+            )?;
+            for (i, line) in lines.iter().skip(skip).take(rows - 1).enumerate() {
+                queue!(
+                    stdout,
+                    cursor::MoveTo(pane_col as u16, (top + 1 + i) as u16),
+                    Print(fit(line, pane_width))
+                )?;
+            }
+            top += rows;
+        }
+
+        let bottom = match self.input {
+            Some(Input::Filter(ref s)) => format!("/{s}"),
+            Some(Input::ListCommand(ref s)) => format!(":{s}"),
+            Some(Input::Exec(ref s)) => format!("exec: {s}"),
+            None if !self.message.is_empty() => self.message.clone(),
+            None => HINTS.to_string(),
+        };
+        queue!(
+            stdout,
+            cursor::MoveTo(0, height.saturating_sub(1) as u16),
+            Print(fit(&bottom, width))
+        )?;
+        stdout.flush()
+    }
+}
+
+/// What to do after a key press
+enum Action {
+    Continue,
+    Quit,
+    /// leave full screen mode and run a command in the terminal, like exec
+    RunInTerminal(String),
+}
+
+impl Dashboard {
+    fn handle_input_key(&mut self, key: KeyEvent, processor: &mut CommandProcessor) -> Action {
+        let mut input = match self.input.take() {
+            Some(input) => input,
+            None => return Action::Continue,
+        };
+        let text = match input {
+            Input::Filter(ref mut s) | Input::ListCommand(ref mut s) | Input::Exec(ref mut s) => s,
+        };
+        match key.code {
+            KeyCode::Esc => {
+                if let Input::Filter(_) = input {
+                    self.filter.clear();
+                    self.update_matches(self.current().cloned());
+                }
+                return Action::Continue;
+            }
+            KeyCode::Enter => {
+                return match input {
+                    Input::Filter(_) => Action::Continue,
+                    Input::ListCommand(cmd) => {
+                        if !cmd.trim().is_empty() {
+                            self.list_cmd = cmd.trim().to_string();
+                            self.filter.clear();
+                            self.cursor = 0;
+                            self.refresh_list(processor);
+                        }
+                        Action::Continue
+                    }
+                    Input::Exec(cmd) => Action::RunInTerminal(format!("exec {cmd}")),
+                };
+            }
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) => text.push(c),
+            _ => {}
+        }
+        if let Input::Filter(ref filter) = input {
+            self.filter = filter.clone();
+            self.update_matches(self.current().cloned());
+        }
+        self.input = Some(input);
+        Action::Continue
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, processor: &mut CommandProcessor) -> Action {
+        if self.input.is_some() {
+            return self.handle_input_key(key, processor);
+        }
+        self.message.clear();
+        let page = terminal::size()
+            .map(|(_, h)| h.saturating_sub(3).max(1) as isize)
+            .unwrap_or(10);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Action::Quit
+            }
+            KeyCode::Enter => {
+                if let Some(obj) = self.current().cloned() {
+                    processor.env_mut().select_objs(vec![obj]);
+                }
+                return Action::Quit;
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-page),
+            KeyCode::PageDown => self.move_cursor(page),
+            KeyCode::Home | KeyCode::Char('g') => self.cursor = 0,
+            KeyCode::End | KeyCode::Char('G') => self.move_cursor(isize::MAX / 2),
+            KeyCode::Char('/') => self.input = Some(Input::Filter(self.filter.clone())),
+            KeyCode::Char(':') => self.input = Some(Input::ListCommand(String::new())),
+            KeyCode::Char('r') => self.refresh_list(processor),
+            KeyCode::Char('d') if self.current().is_some() => {
+                return Action::RunInTerminal("delete".to_string())
+            }
+            KeyCode::Char('e') if self.current().map(|o| o.is_pod()).unwrap_or(false) => {
+                self.input = Some(Input::Exec("sh".to_string()))
+            }
+            KeyCode::Char('l') if self.current().map(|o| o.is_pod()).unwrap_or(false) => {
+                return Action::RunInTerminal("logs -f".to_string())
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+}
+
+/// Run the dashboard, starting with the objects returned by list_cmd. When it exits, the
+/// highlighted object is the current selection.
+pub fn run_dashboard(processor: &mut CommandProcessor, list_cmd: &str) -> Result<(), ClickError> {
+    if !io::stdout().is_tty() {
+        return Err(ClickError::CommandError(
+            "The dashboard needs a terminal, and output is redirected".to_string(),
+        ));
+    }
+    let mut dashboard = Dashboard::new(list_cmd);
+    dashboard.refresh_list(processor);
+    let mut guard = Some(TerminalGuard::new()?);
+    loop {
+        if let Some(guard) = guard.as_mut() {
+            dashboard.draw(guard.stdout(), processor.env())?;
+        }
+        // key presses only redraw. The details are fetched once no key has been pressed for a
+        // moment, and we wake up now and then to refresh things even if no keys are pressed
+        let timeout = if dashboard.details_stale() {
+            DETAILS_DELAY
+        } else {
+            Duration::from_secs(1)
+        };
+        if !event::poll(timeout)? {
+            dashboard.refresh_details(processor);
+            if dashboard.listed_at.elapsed() >= LIST_REFRESH && dashboard.input.is_none() {
+                dashboard.refresh_list(processor);
+            }
+            continue;
+        }
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+        match dashboard.handle_key(key, processor) {
+            Action::Continue => {}
+            Action::Quit => break,
+            Action::RunInTerminal(cmd) => {
+                // put the terminal back to normal so the command can prompt, or take it over
+                drop(guard.take());
+                if let Some(obj) = dashboard.current().cloned() {
+                    processor.env_mut().select_objs(vec![obj]);
+                }
+                println!("> {cmd}");
+                let _ = processor.run_commands(&cmd);
+                println!("\nPress Enter to return to the dashboard");
+                let mut line = String::new();
+                io::stdin().read_line(&mut line)?;
+                guard = Some(TerminalGuard::new()?);
+                dashboard.refresh_list(processor);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{get_test_config, ClickConfig};
+    use crate::kobj::ObjType;
+
+    use std::path::PathBuf;
+
+    fn pod(name: &str) -> KObj {
+        KObj {
+            name: name.to_string(),
+            namespace: Some("default".to_string()),
+            typ: ObjType::Pod { containers: vec![] },
+        }
+    }
+
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit("web-123", 3), "web");
+        assert_eq!(fit("web", 10), "web");
+        assert_eq!(fit("", 5), "");
+        // widths are in characters, not bytes
+        assert_eq!(fit("naïve-pod", 4), "naïv");
+    }
+
+    #[test]
+    fn test_text_lines() {
+        assert_eq!(
+            text_lines("\u{1b}[32mRunning\u{1b}[0m\n\tweb-1\n\nend"),
+            vec!["Running", "    web-1", "", "end"]
+        );
+        assert!(text_lines("").is_empty());
+    }
+
+    #[test]
+    fn test_list_lines() {
+        let mut env = Env::new(
+            get_test_config(),
+            ClickConfig::default(),
+            PathBuf::from("/tmp/click.config"),
+        );
+        let objs = vec![pod("web-1"), pod("database-0")];
+
+        // no table, so name, type and namespace are shown, aligned to the widest value
+        env.set_last_objs(objs.clone(), None);
+        let (header, lines) = list_lines(&env, &objs);
+        assert_eq!(header, "Name        Type  Namespace");
+        assert_eq!(
+            lines,
+            vec!["web-1       Pod   default", "database-0  Pod   default"]
+        );
+
+        // the index column is dropped, and multi-line cells are joined
+        let mut table = comfy_table::Table::new();
+        table.set_header(vec!["####", "Name", "Status"]);
+        table.add_row(vec!["0", "web-1", "Running"]);
+        table.add_row(vec!["1", "database-0", "Init\nWaiting"]);
+        env.set_last_objs(objs.clone(), Some(table));
+        let (header, lines) = list_lines(&env, &objs);
+        assert_eq!(header, "Name        Status");
+        assert_eq!(
+            lines,
+            vec!["web-1       Running", "database-0  Init Waiting"]
+        );
+
+        // a table that doesn't match the objects isn't used
+        let (header, _) = list_lines(&env, &objs[..1]);
+        assert_eq!(header, "Name   Type  Namespace");
+    }
+
+    #[test]
+    fn test_no_nested_dashboard() {
+        let mut env = Env::new(
+            get_test_config(),
+            ClickConfig::default(),
+            PathBuf::from("/tmp/click.config"),
+        );
+        assert!(env.request_dashboard("pods".to_string()).is_ok());
+        assert_eq!(env.take_dashboard_request(), Some("pods".to_string()));
+        env.set_dashboard_open(true);
+        assert!(env.request_dashboard("pods".to_string()).is_err());
+        assert_eq!(env.take_dashboard_request(), None);
+    }
+}